@@ -1,31 +1,113 @@
-use actix_web::web;
 use reqwest::Client;
 use std::collections::HashMap;
 use anyhow::{Result, Context};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
 use crate::config::Config;
 
-#[derive(serde::Deserialize)]
+#[derive(Debug, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
 pub struct TokenResponse {
     pub access_token: String,
-    pub expires_in: u64
+    pub expires_in: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
 }
 
 pub async fn get_access_token(
     code: &str, 
+    code_verifier: &str,
     config: &Config
 ) -> Result<TokenResponse> {
-    let client: Client = Client::new();
-
     let mut params: HashMap<&str, &str> = HashMap::new();
     params.insert("code", code);
+    params.insert("code_verifier", code_verifier);
     params.insert("client_id", &config.client_id);
     params.insert("client_secret", &config.client_secret);
     params.insert("redirect_uri", &config.redirect_uri);
     params.insert("grant_type", "authorization_code");
 
+    request_token(&params, config)
+        .await
+        .context("Error exchanging code for token")
+}
+
+pub async fn refresh_access_token(
+    refresh_token: &str,
+    config: &Config
+) -> Result<TokenResponse> {
+    let mut params: HashMap<&str, &str> = HashMap::new();
+    params.insert("refresh_token", refresh_token);
+    params.insert("client_id", &config.client_id);
+    params.insert("client_secret", &config.client_secret);
+    params.insert("grant_type", "refresh_token");
+
+    request_token(&params, config)
+        .await
+        .context("Error refreshing access token")
+}
+
+#[derive(serde::Serialize)]
+struct JwtBearerClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub: Option<&'a str>,
+}
+
+/// Exchanges a signed RS256 JWT assertion for an access token (RFC 7523).
+///
+/// `subject` impersonates a Workspace user through domain-wide delegation.
+pub async fn get_service_account_token(
+    client_email: &str,
+    private_key_id: Option<&str>,
+    private_key_pem: &str,
+    subject: Option<&str>,
+    config: &Config
+) -> Result<TokenResponse> {
+    let iat = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .context("System clock is before the Unix epoch")?
+        .as_secs();
+
+    let claims = JwtBearerClaims {
+        iss: client_email,
+        scope: &config.scope,
+        aud: &config.token_uri,
+        iat,
+        exp: iat + 3600,
+        sub: subject,
+    };
+
+    let mut header = Header::new(Algorithm::RS256);
+    header.kid = private_key_id.map(str::to_string);
+
+    let key = EncodingKey::from_rsa_pem(private_key_pem.as_bytes())
+        .context("Failed to parse service account private key")?;
+    let assertion = encode(&header, &claims, &key)
+        .context("Failed to sign JWT assertion")?;
+
+    let mut params: HashMap<&str, &str> = HashMap::new();
+    params.insert("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer");
+    params.insert("assertion", &assertion);
+
+    request_token(&params, config)
+        .await
+        .context("Error exchanging JWT assertion for token")
+}
+
+async fn request_token(
+    params: &HashMap<&str, &str>,
+    config: &Config
+) -> Result<TokenResponse> {
+    let client: Client = Client::new();
+
     let response = client
-        .post(config.token_uri)
-        .form(&params)
+        .post(&config.token_uri)
+        .form(params)
         .send()
         .await
         .context("Failed to send request to token URI")?;
@@ -41,33 +123,108 @@ pub async fn get_access_token(
             .text()
             .await
             .context("Failed to retrieve error message from response")?;
-        anyhow::bail!("Error exchanging code for token: {}", error_message);
+        anyhow::bail!("Token endpoint returned an error: {}", error_message);
+    }
+}
+
+/// Revokes an access or refresh token at the configured revoke endpoint.
+///
+/// `REVOKE_URI` may carry a `{}` placeholder for the token (as in `...revoke?token={}`);
+/// otherwise the token is sent as a form parameter. Tokens Google already considers
+/// invalid are treated as revoked.
+pub async fn revoke_token(token: &str, config: &Config) -> Result<()> {
+    let client = Client::new();
+
+    let request = if config.revoke_uri.contains("{}") {
+        let encoded: String = url::form_urlencoded::byte_serialize(token.as_bytes()).collect();
+        client.post(config.revoke_uri.replace("{}", &encoded))
+    } else {
+        client.post(&config.revoke_uri).form(&[("token", token)])
+    };
+
+    let response = request
+        .send()
+        .await
+        .context("Failed to send request to revoke URI")?;
+
+    if response.status().is_success() {
+        return Ok(());
     }
+
+    let error_message = response
+        .text()
+        .await
+        .context("Failed to retrieve error message from response")?;
+    if error_message.contains("invalid_token") {
+        return Ok(());
+    }
+    anyhow::bail!("Error revoking token: {}", error_message);
 }
 
-pub fn build_auth_url(config: web::Data<Config>) -> String {
+pub fn build_auth_url(config: &Config, state: &str, code_challenge: &str) -> String {
     format!(
-        "{}?client_id={}&redirect_uri={}&response_type=code&scope={}",
-        config.auth_uri, config.client_id, config.redirect_uri, config.scope
+        "{}?client_id={}&redirect_uri={}&response_type=code&scope={}&access_type=offline&prompt=consent&state={}&code_challenge={}&code_challenge_method=S256",
+        config.auth_uri, config.client_id, config.redirect_uri, config.scope, state, code_challenge
     )
 }
 
-pub async fn validate_token(token: String) -> Result<bool> {
+/// Subset of Google's tokeninfo response used for authorization decisions.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct TokenInfo {
+    pub aud: Option<String>,
+    pub scopes: Vec<String>,
+    pub exp: Option<u64>,
+    pub expires_in: Option<u64>,
+    pub email: Option<String>,
+}
+
+impl TokenInfo {
+    /// Parses a tokeninfo response, where numeric fields are returned as strings.
+    pub fn from_json(json: &serde_json::Value) -> Self {
+        let as_u64 = |value: &serde_json::Value| match value {
+            serde_json::Value::String(value) => value.parse().ok(),
+            value => value.as_u64(),
+        };
+        let as_string = |value: &serde_json::Value| value.as_str().map(str::to_string);
+
+        TokenInfo {
+            aud: as_string(&json["aud"]),
+            scopes: json["scope"]
+                .as_str()
+                .map(|scope| scope.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_default(),
+            exp: as_u64(&json["exp"]),
+            expires_in: as_u64(&json["expires_in"]),
+            email: as_string(&json["email"]),
+        }
+    }
+
+    pub fn has_any_scope(&self, scopes: &[&str]) -> bool {
+        self.scopes.iter().any(|granted| scopes.contains(&granted.as_str()))
+    }
+}
+
+/// Checks a token against Google's tokeninfo endpoint.
+///
+/// Returns the parsed token info, or `None` when Google rejects the token.
+pub async fn validate_token(token: &str, config: &Config) -> Result<Option<TokenInfo>> {
     let client = Client::new();
-    
-    let url = format!("https://www.googleapis.com/oauth2/v3/tokeninfo?access_token={}", token);
 
-    let response = client.get(&url).send().await.context("Failed to validate token")?;
+    let response = client
+        .get(&config.tokeninfo_uri)
+        .query(&[("access_token", token)])
+        .send()
+        .await
+        .context("Failed to validate token")?;
 
     if response.status().is_success() {
         let json: serde_json::Value = response
             .json()
             .await
             .context("Failed to parse token validation response")?;
-        println!("Token info: {:?}", json);
-        Ok(true)
+        Ok(Some(TokenInfo::from_json(&json)))
     } else {
         println!("Token is invalid or expired.");
-        Ok(false)
+        Ok(None)
     }
 }
@@ -0,0 +1,80 @@
+use actix_web::{http::StatusCode, HttpResponse};
+use serde::Deserialize;
+use std::fmt;
+
+/// A failed Drive API call, typed so handlers can pass a missing file or a
+/// refused operation on to the client instead of answering 500.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DriveApiError {
+    /// The file does not exist or is not visible to the caller.
+    NotFound(String),
+    /// The caller may see the file but not perform the operation on it.
+    Forbidden(String),
+    /// Any other non-success status, with Drive's message.
+    Status(u16, String),
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: ErrorDetail,
+}
+
+#[derive(Deserialize)]
+struct ErrorDetail {
+    message: String,
+}
+
+impl DriveApiError {
+    /// Builds the error from a non-success response, keeping Drive's
+    /// `error.message` when the body has one.
+    pub async fn from_response(response: reqwest::Response) -> Self {
+        let status = response.status().as_u16();
+        let message = match response.text().await {
+            Ok(body) => serde_json::from_str::<ErrorBody>(&body).map(|body| body.error.message).unwrap_or(body),
+            Err(_) => String::new(),
+        };
+        Self::new(status, message)
+    }
+
+    pub fn new(status: u16, message: impl Into<String>) -> Self {
+        let message = message.into();
+        match status {
+            404 => DriveApiError::NotFound(message),
+            403 => DriveApiError::Forbidden(message),
+            status => DriveApiError::Status(status, message),
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            DriveApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            DriveApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            DriveApiError::Status(_, _) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl fmt::Display for DriveApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DriveApiError::NotFound(message) => write!(f, "File not found: {}", message),
+            DriveApiError::Forbidden(message) => write!(f, "Permission denied: {}", message),
+            DriveApiError::Status(status, message) => write!(f, "Drive API error {}: {}", status, message),
+        }
+    }
+}
+
+impl std::error::Error for DriveApiError {}
+
+/// The status to answer with for a failed Drive call: 404 and 403 from
+/// Drive are passed on, anything else is a 500.
+pub fn drive_error_status(err: &anyhow::Error) -> StatusCode {
+    err.downcast_ref::<DriveApiError>()
+        .map_or(StatusCode::INTERNAL_SERVER_ERROR, DriveApiError::status_code)
+}
+
+/// Logs a failed Drive call and answers it with [`drive_error_status`].
+pub fn drive_error_response(action: &str, err: anyhow::Error) -> HttpResponse {
+    eprintln!("Error {}: {:?}", action, err);
+    HttpResponse::build(drive_error_status(&err)).body(format!("Error {}: {:?}", action, err))
+}
@@ -0,0 +1,157 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// MIME type Drive uses for folders.
+pub const FOLDER_MIME_TYPE: &str = "application/vnd.google-apps.folder";
+
+/// Escapes a value for use inside a single-quoted Drive query string literal.
+pub fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+/// Checks that a timestamp has the RFC 3339 shape Drive expects, e.g.
+/// `2024-10-23T10:00:00Z` or `2024-10-23T10:00:00.5-05:00`.
+pub fn is_rfc3339(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    let digits = |range: std::ops::Range<usize>| bytes.get(range).is_some_and(|part| part.iter().all(u8::is_ascii_digit));
+
+    if bytes.len() < 20
+        || !digits(0..4)
+        || bytes[4] != b'-'
+        || !digits(5..7)
+        || bytes[7] != b'-'
+        || !digits(8..10)
+        || !matches!(bytes[10], b'T' | b't')
+        || !digits(11..13)
+        || bytes[13] != b':'
+        || !digits(14..16)
+        || bytes[16] != b':'
+        || !digits(17..19)
+    {
+        return false;
+    }
+
+    let mut rest = &value[19..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let len = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if len == 0 {
+            return false;
+        }
+        rest = &fraction[len..];
+    }
+
+    match rest.as_bytes() {
+        [b'Z' | b'z'] => true,
+        [b'+' | b'-', h1, h2, b':', m1, m2] => [h1, h2, m1, m2].iter().all(|b| b.is_ascii_digit()),
+        _ => false,
+    }
+}
+
+/// Converts an RFC 3339 timestamp, such as Drive's `modifiedTime`, to a
+/// `SystemTime`. Fractional seconds are dropped, as HTTP dates cannot carry them.
+pub fn parse_rfc3339(value: &str) -> Option<SystemTime> {
+    if !is_rfc3339(value) {
+        return None;
+    }
+
+    let number = |range: std::ops::Range<usize>| value[range].parse::<i64>().ok();
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let offset = match value.as_bytes()[value.len() - 1] {
+        b'Z' | b'z' => 0,
+        _ => {
+            let zone = &value[value.len() - 6..];
+            let minutes = zone[1..3].parse::<i64>().ok()? * 60 + zone[4..6].parse::<i64>().ok()?;
+            if zone.starts_with('-') { -minutes * 60 } else { minutes * 60 }
+        }
+    };
+
+    // Days since the epoch in the proleptic Gregorian calendar (Howard Hinnant's `days_from_civil`).
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let seconds = days * 86_400 + hour * 3_600 + minute * 60 + second - offset;
+    u64::try_from(seconds).ok().map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+/// Builds a Drive `q` search string from structured filters.
+///
+/// Every value is escaped, so user input can never break out of its literal.
+/// Clauses are joined with `and`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DriveQuery {
+    clauses: Vec<String>,
+}
+
+impl DriveQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name_contains(self, name: &str) -> Self {
+        self.clause(format!("name contains '{}'", escape(name)))
+    }
+
+    pub fn name_equals(self, name: &str) -> Self {
+        self.clause(format!("name = '{}'", escape(name)))
+    }
+
+    pub fn full_text_contains(self, text: &str) -> Self {
+        self.clause(format!("fullText contains '{}'", escape(text)))
+    }
+
+    pub fn mime_type(self, mime_type: &str) -> Self {
+        self.clause(format!("mimeType = '{}'", escape(mime_type)))
+    }
+
+    pub fn folders_only(self) -> Self {
+        self.mime_type(FOLDER_MIME_TYPE)
+    }
+
+    /// Expects an RFC 3339 timestamp; see [`is_rfc3339`].
+    pub fn modified_after(self, timestamp: &str) -> Self {
+        self.clause(format!("modifiedTime > '{}'", escape(timestamp)))
+    }
+
+    /// Expects an RFC 3339 timestamp; see [`is_rfc3339`].
+    pub fn modified_before(self, timestamp: &str) -> Self {
+        self.clause(format!("modifiedTime < '{}'", escape(timestamp)))
+    }
+
+    pub fn owner(self, email: &str) -> Self {
+        self.clause(format!("'{}' in owners", escape(email)))
+    }
+
+    pub fn in_parents(self, folder_id: &str) -> Self {
+        self.clause(format!("'{}' in parents", escape(folder_id)))
+    }
+
+    pub fn trashed(self, trashed: bool) -> Self {
+        self.clause(format!("trashed = {}", trashed))
+    }
+
+    pub fn starred(self, starred: bool) -> Self {
+        self.clause(format!("starred = {}", starred))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    /// Compiles the filters into the `q` parameter value.
+    pub fn build(&self) -> String {
+        self.clauses.join(" and ")
+    }
+
+    fn clause(mut self, clause: String) -> Self {
+        self.clauses.push(clause);
+        self
+    }
+}
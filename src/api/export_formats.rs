@@ -0,0 +1,99 @@
+/// Prefix of the MIME types of Google Workspace documents, which have no
+/// binary content and must be exported instead of downloaded.
+pub const WORKSPACE_MIME_PREFIX: &str = "application/vnd.google-apps.";
+
+/// A format a Google Workspace document can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportFormat {
+    /// Name used in the `format` query parameter.
+    pub name: &'static str,
+    pub mime_type: &'static str,
+    pub extension: &'static str,
+}
+
+const fn format(name: &'static str, mime_type: &'static str, extension: &'static str) -> ExportFormat {
+    ExportFormat { name, mime_type, extension }
+}
+
+const PDF: ExportFormat = format("pdf", "application/pdf", "pdf");
+const TXT: ExportFormat = format("txt", "text/plain", "txt");
+const JPG: ExportFormat = format("jpg", "image/jpeg", "jpg");
+const PNG: ExportFormat = format("png", "image/png", "png");
+const SVG: ExportFormat = format("svg", "image/svg+xml", "svg");
+/// Zipped HTML, one file per sheet or per document with its images.
+const ZIP: ExportFormat = format("zip", "application/zip", "zip");
+
+const DOCUMENT_FORMATS: &[ExportFormat] = &[
+    PDF,
+    format("docx", "application/vnd.openxmlformats-officedocument.wordprocessingml.document", "docx"),
+    format("odt", "application/vnd.oasis.opendocument.text", "odt"),
+    format("rtf", "application/rtf", "rtf"),
+    TXT,
+    format("html", "text/html", "html"),
+    format("epub", "application/epub+zip", "epub"),
+    format("md", "text/markdown", "md"),
+    ZIP,
+];
+
+const SPREADSHEET_FORMATS: &[ExportFormat] = &[
+    PDF,
+    format("xlsx", "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet", "xlsx"),
+    format("ods", "application/x-vnd.oasis.opendocument.spreadsheet", "ods"),
+    // Only the first sheet is exported to CSV and TSV.
+    format("csv", "text/csv", "csv"),
+    format("tsv", "text/tab-separated-values", "tsv"),
+    ZIP,
+];
+
+const PRESENTATION_FORMATS: &[ExportFormat] = &[
+    PDF,
+    format("pptx", "application/vnd.openxmlformats-officedocument.presentationml.presentation", "pptx"),
+    format("odp", "application/vnd.oasis.opendocument.presentation", "odp"),
+    TXT,
+    // Images only contain the first slide.
+    JPG,
+    PNG,
+    SVG,
+];
+
+const DRAWING_FORMATS: &[ExportFormat] = &[PDF, JPG, PNG, SVG];
+
+const SCRIPT_FORMATS: &[ExportFormat] = &[format("json", "application/vnd.google-apps.script+json", "json")];
+
+pub fn is_workspace_type(mime_type: &str) -> bool {
+    mime_type.starts_with(WORKSPACE_MIME_PREFIX)
+}
+
+/// Formats a Google Workspace document of `mime_type` can be exported to,
+/// default first. Empty for types that cannot be exported, such as folders,
+/// forms or shortcuts.
+pub fn export_formats(mime_type: &str) -> &'static [ExportFormat] {
+    match mime_type.strip_prefix(WORKSPACE_MIME_PREFIX) {
+        Some("document") => DOCUMENT_FORMATS,
+        Some("spreadsheet") => SPREADSHEET_FORMATS,
+        Some("presentation") => PRESENTATION_FORMATS,
+        Some("drawing") => DRAWING_FORMATS,
+        Some("script") => SCRIPT_FORMATS,
+        _ => &[],
+    }
+}
+
+/// Picks the export format named `requested` for a document of
+/// `mime_type`, or its default format when none is requested.
+pub fn find_export_format(mime_type: &str, requested: Option<&str>) -> Option<ExportFormat> {
+    let formats = export_formats(mime_type);
+    match requested {
+        Some(name) => formats.iter().find(|format| format.name.eq_ignore_ascii_case(name.trim())).copied(),
+        None => formats.first().copied(),
+    }
+}
+
+/// Names the exported file after the document, with the format's extension.
+pub fn export_file_name(name: &str, format: &ExportFormat) -> String {
+    let suffix = format!(".{}", format.extension);
+    if name.to_ascii_lowercase().ends_with(&suffix) {
+        name.to_string()
+    } else {
+        format!("{}{}", name, suffix)
+    }
+}
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// Fields that can be selected with `fields=`, as (name in this API, name in Drive).
+pub const METADATA_FIELDS: &[(&str, &str)] = &[
+    ("id", "id"),
+    ("name", "name"),
+    ("mime_type", "mimeType"),
+    ("size", "size"),
+    ("created_time", "createdTime"),
+    ("modified_time", "modifiedTime"),
+    ("md5_checksum", "md5Checksum"),
+    ("owners", "owners"),
+    ("parents", "parents"),
+    ("web_view_link", "webViewLink"),
+    ("icon_link", "iconLink"),
+    ("starred", "starred"),
+    ("trashed", "trashed"),
+    ("capabilities", "capabilities"),
+    ("app_properties", "appProperties"),
+];
+
+/// The fields to ask Drive for. Only names from [`METADATA_FIELDS`] are
+/// accepted, so what Drive returns always fits [`FileMetadata`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSelection {
+    /// Indices into `METADATA_FIELDS`, in table order.
+    fields: Vec<usize>,
+}
+
+impl FieldSelection {
+    /// Every field of [`FileMetadata`].
+    pub fn all() -> Self {
+        FieldSelection { fields: (0..METADATA_FIELDS.len()).collect() }
+    }
+
+    /// Parses a comma-separated list such as `name,size,modified_time`.
+    /// Drive's own names (`modifiedTime`) are accepted too; `id` is always
+    /// included.
+    pub fn parse(fields: &str) -> Result<Self, String> {
+        let mut selected = vec![0];
+        for field in fields.split(',').map(str::trim).filter(|field| !field.is_empty()) {
+            match METADATA_FIELDS.iter().position(|(name, drive_name)| field == *name || field == *drive_name) {
+                Some(index) => selected.push(index),
+                None => {
+                    let names: Vec<&str> = METADATA_FIELDS.iter().map(|(name, _)| *name).collect();
+                    return Err(format!("Unknown field '{}'; valid fields are: {}", field, names.join(", ")));
+                }
+            }
+        }
+        selected.sort_unstable();
+        selected.dedup();
+        Ok(FieldSelection { fields: selected })
+    }
+
+    /// Names of the selected fields in this API's responses.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.fields.iter().map(|&index| METADATA_FIELDS[index].0)
+    }
+
+    /// The `fields` parameter for `files.get`.
+    pub fn drive_fields(&self) -> String {
+        self.fields.iter().map(|&index| METADATA_FIELDS[index].1).collect::<Vec<_>>().join(",")
+    }
+
+    /// The `fields` parameter for `files.list`.
+    pub fn drive_list_fields(&self) -> String {
+        format!("nextPageToken,files({})", self.drive_fields())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Owner {
+    #[serde(default, alias = "displayName", skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, alias = "emailAddress", skip_serializing_if = "Option::is_none")]
+    pub email_address: Option<String>,
+}
+
+/// What the caller may do with the file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Capabilities {
+    #[serde(default, alias = "canEdit", skip_serializing_if = "Option::is_none")]
+    pub can_edit: Option<bool>,
+    #[serde(default, alias = "canComment", skip_serializing_if = "Option::is_none")]
+    pub can_comment: Option<bool>,
+    #[serde(default, alias = "canShare", skip_serializing_if = "Option::is_none")]
+    pub can_share: Option<bool>,
+    #[serde(default, alias = "canCopy", skip_serializing_if = "Option::is_none")]
+    pub can_copy: Option<bool>,
+    #[serde(default, alias = "canDownload", skip_serializing_if = "Option::is_none")]
+    pub can_download: Option<bool>,
+    #[serde(default, alias = "canRename", skip_serializing_if = "Option::is_none")]
+    pub can_rename: Option<bool>,
+    #[serde(default, alias = "canTrash", skip_serializing_if = "Option::is_none")]
+    pub can_trash: Option<bool>,
+    #[serde(default, alias = "canDelete", skip_serializing_if = "Option::is_none")]
+    pub can_delete: Option<bool>,
+}
+
+/// A file's metadata as Drive reports it. Read from Drive's camelCase and
+/// written in this API's snake_case; fields left out of the selection are
+/// omitted.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FileMetadata {
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, alias = "mimeType", skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// In bytes; Google Workspace documents have none.
+    #[serde(default, deserialize_with = "size_from_string", skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(default, alias = "createdTime", skip_serializing_if = "Option::is_none")]
+    pub created_time: Option<String>,
+    #[serde(default, alias = "modifiedTime", skip_serializing_if = "Option::is_none")]
+    pub modified_time: Option<String>,
+    #[serde(default, alias = "md5Checksum", skip_serializing_if = "Option::is_none")]
+    pub md5_checksum: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owners: Option<Vec<Owner>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parents: Option<Vec<String>>,
+    #[serde(default, alias = "webViewLink", skip_serializing_if = "Option::is_none")]
+    pub web_view_link: Option<String>,
+    #[serde(default, alias = "iconLink", skip_serializing_if = "Option::is_none")]
+    pub icon_link: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub starred: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trashed: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<Capabilities>,
+    #[serde(default, alias = "appProperties", skip_serializing_if = "Option::is_none")]
+    pub app_properties: Option<BTreeMap<String, String>>,
+}
+
+/// Drive sends `size` as a decimal string; this API sends it as a number.
+fn size_from_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Size {
+        Number(u64),
+        Text(String),
+    }
+
+    match Option::<Size>::deserialize(deserializer)? {
+        Some(Size::Number(size)) => Ok(Some(size)),
+        Some(Size::Text(size)) => size.parse().map(Some).map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
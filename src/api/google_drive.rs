@@ -1,10 +1,16 @@
-use actix_web::http::header::HeaderMap;
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
-use reqwest::{header::RANGE, Client};
-use serde::Deserialize;
+use actix_web::http::header::{HeaderMap, HeaderName};
+use actix_web::web::Bytes;
+use futures::stream::{BoxStream, StreamExt};
+use reqwest::header::{AUTHORIZATION, CONTENT_RANGE, CONTENT_TYPE, ETAG, RANGE};
+use reqwest::{redirect::Policy, Client, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::json;
+use crate::api::drive_error::DriveApiError;
+use crate::api::drive_query::{DriveQuery, FOLDER_MIME_TYPE};
+use crate::api::file_metadata::{FieldSelection, FileMetadata};
 use crate::config::Config;
 use anyhow::{Result, Context};
+use std::collections::BTreeMap;
 
 #[derive(Deserialize)]
 pub struct File {
@@ -16,132 +22,464 @@ pub struct File {
     pub created_time: Option<String>,
 }
 
+/// One page of `files.list`; the files are [`File`]s unless other fields were requested.
 #[derive(Deserialize)]
-pub struct FileList {
-    pub files: Vec<File>,
+pub struct FileList<F = File> {
+    #[serde(default = "Vec::new")]
+    pub files: Vec<F>,
+    #[serde(rename = "nextPageToken")]
+    pub next_page_token: Option<String>,
 }
 
-pub async fn list_folders(token: &str, config: &Config) -> Result<Vec<File>> {
+/// Largest page size accepted by the Drive `files.list` endpoint.
+pub const MAX_PAGE_SIZE: u32 = 1000;
+
+const FOLDER_FIELDS: &str = "nextPageToken,files(id,name)";
+const FILE_FIELDS: &str = "nextPageToken,files(id,name,mimeType,createdTime)";
+
+/// Fetches a single page of `files.list` for the given search query.
+pub async fn list_files_page<F: DeserializeOwned>(
+    token: &str,
+    q: &str,
+    fields: &str,
+    page_size: Option<u32>,
+    page_token: Option<&str>,
+    config: &Config,
+) -> Result<FileList<F>> {
     let client = Client::new();
-    let folder_url = format!("{}?q=mimeType='application/vnd.google-apps.folder'&fields=files(id,name)", &config.drive_api_base_url);
+
+    let mut params = vec![("q", q.to_string()), ("fields", fields.to_string())];
+    if let Some(page_size) = page_size {
+        params.push(("pageSize", page_size.to_string()));
+    }
+    if let Some(page_token) = page_token {
+        params.push(("pageToken", page_token.to_string()));
+    }
 
     let response = client
-        .get(&folder_url)
+        .get(&config.drive_api_base_url)
         .bearer_auth(token)
+        .query(&params)
         .send()
         .await
-        .context("Failed to send request to list folders")?;
+        .context("Failed to send request to list files")?;
 
-    let file_list = response.json::<FileList>().await
-        .context("Failed to parse response as FileList")?;
+    if !response.status().is_success() {
+        return Err(DriveApiError::from_response(response).await).context("Failed to list files");
+    }
 
-    if !file_list.files.is_empty() {
-        Ok(file_list.files)
-    } else {
-        println!("No folders found.");
-        Ok(vec![])
+    response.json::<FileList<F>>().await
+        .context("Failed to parse response as FileList")
+}
+
+/// Follows `nextPageToken` until Drive reports no further pages.
+pub async fn list_all_files<F: DeserializeOwned>(
+    token: &str,
+    q: &str,
+    fields: &str,
+    page_size: Option<u32>,
+    config: &Config,
+) -> Result<Vec<F>> {
+    let mut files = Vec::new();
+    let mut page_token: Option<String> = None;
+
+    loop {
+        let page = list_files_page(token, q, fields, Some(page_size.unwrap_or(MAX_PAGE_SIZE)), page_token.as_deref(), config).await?;
+        files.extend(page.files);
+
+        match page.next_page_token {
+            Some(next) if !next.is_empty() => page_token = Some(next),
+            _ => return Ok(files),
+        }
+    }
+}
+
+pub async fn list_folders(token: &str, page_size: Option<u32>, page_token: Option<&str>, config: &Config) -> Result<FileList> {
+    list_files_page(token, &DriveQuery::new().folders_only().build(), FOLDER_FIELDS, page_size, page_token, config)
+        .await
+        .context("Failed to list folders")
+}
+
+pub async fn list_all_folders(token: &str, page_size: Option<u32>, config: &Config) -> Result<Vec<File>> {
+    list_all_files(token, &DriveQuery::new().folders_only().build(), FOLDER_FIELDS, page_size, config)
+        .await
+        .context("Failed to list folders")
+}
+
+pub async fn list_files_from_folder(
+    token: &str,
+    folder_id: &str,
+    page_size: Option<u32>,
+    page_token: Option<&str>,
+    config: &Config,
+) -> Result<FileList> {
+    list_files_page(token, &DriveQuery::new().in_parents(folder_id).trashed(false).build(), FILE_FIELDS, page_size, page_token, config)
+        .await
+        .context(format!("Failed to list files in folder '{}'", folder_id))
+}
+
+pub async fn list_all_files_from_folder(
+    token: &str,
+    folder_id: &str,
+    page_size: Option<u32>,
+    config: &Config,
+) -> Result<Vec<File>> {
+    list_all_files(token, &DriveQuery::new().in_parents(folder_id).trashed(false).build(), FILE_FIELDS, page_size, config)
+        .await
+        .context(format!("Failed to list files in folder '{}'", folder_id))
+}
+
+pub async fn search_files(
+    token: &str,
+    query: &DriveQuery,
+    page_size: Option<u32>,
+    page_token: Option<&str>,
+    config: &Config,
+) -> Result<FileList> {
+    list_files_page(token, &query.build(), FILE_FIELDS, page_size, page_token, config)
+        .await
+        .context("Failed to search files")
+}
+
+pub async fn search_all_files(token: &str, query: &DriveQuery, page_size: Option<u32>, config: &Config) -> Result<Vec<File>> {
+    list_all_files(token, &query.build(), FILE_FIELDS, page_size, config)
+        .await
+        .context("Failed to search files")
+}
+
+/// Lists the selected metadata of the files matching `query`.
+pub async fn list_file_metadata(
+    token: &str,
+    query: &DriveQuery,
+    fields: &FieldSelection,
+    page_size: Option<u32>,
+    page_token: Option<&str>,
+    config: &Config,
+) -> Result<FileList<FileMetadata>> {
+    list_files_page(token, &query.build(), &fields.drive_list_fields(), page_size, page_token, config)
+        .await
+        .context("Failed to list file metadata")
+}
+
+pub async fn list_all_file_metadata(
+    token: &str,
+    query: &DriveQuery,
+    fields: &FieldSelection,
+    page_size: Option<u32>,
+    config: &Config,
+) -> Result<Vec<FileMetadata>> {
+    list_all_files(token, &query.build(), &fields.drive_list_fields(), page_size, config)
+        .await
+        .context("Failed to list file metadata")
+}
+
+/// What a download needs to know about a file before fetching its content.
+#[derive(Debug, Default, Deserialize)]
+pub struct DownloadMetadata {
+    pub name: Option<String>,
+    #[serde(rename = "mimeType")]
+    pub mime_type: Option<String>,
+    /// Only set for binary files; Google Docs have none.
+    #[serde(rename = "md5Checksum")]
+    pub md5_checksum: Option<String>,
+    #[serde(rename = "sha256Checksum")]
+    pub sha256_checksum: Option<String>,
+    #[serde(rename = "modifiedTime")]
+    pub modified_time: Option<String>,
+    /// Drive reports sizes as decimal strings.
+    pub size: Option<String>,
+    #[serde(default)]
+    pub parents: Vec<String>,
+}
+
+impl DownloadMetadata {
+    pub fn content_length(&self) -> Option<u64> {
+        self.size.as_deref().and_then(|size| size.parse().ok())
     }
 }
 
-pub async fn list_files_from_folder(token: &str, folder_id: &str, config: &Config) -> Result<Vec<File>> {
+pub const DOWNLOAD_METADATA_FIELDS: &str = "name,mimeType,md5Checksum,sha256Checksum,modifiedTime,size,parents";
+
+pub async fn get_download_metadata(token: &str, file_id: &str, config: &Config) -> Result<DownloadMetadata> {
     let client = Client::new();
-    let files_url = format!(
-        "{}?q='{}' in parents&fields=files(id,name,mimeType,createdTime)", 
-        &config.drive_api_base_url, folder_id
-    );
+    let file_url = format!("{}/{}", &config.drive_api_base_url, file_id);
 
     let response = client
-        .get(&files_url)
+        .get(&file_url)
+        .query(&[("fields", DOWNLOAD_METADATA_FIELDS)])
         .bearer_auth(token)
         .send()
         .await
-        .context(format!("Failed to send request to list files in folder '{}'", folder_id))?;
+        .context(format!("Failed to send request for metadata of file '{}'", file_id))?;
 
-    let file_list = response.json::<FileList>().await
-        .context("Failed to parse response as FileList")?;
+    if !response.status().is_success() {
+        return Err(DriveApiError::from_response(response).await).context("Failed to get file metadata");
+    }
 
-    if !file_list.files.is_empty() {
-        Ok(file_list.files)
-    } else {
-        println!("No files found in folder.");
-        Ok(vec![])
+    response.json().await.context("Failed to parse file metadata")
+}
+
+/// A file body streamed from Drive, with the response headers worth passing on.
+pub struct DownloadStream {
+    pub content_type: Option<String>,
+    pub content_length: Option<u64>,
+    pub etag: Option<String>,
+    pub body: BoxStream<'static, Result<Bytes>>,
+}
+
+/// Starts downloading a file, or the inclusive byte `range` of it, without
+/// buffering: the body is read from Drive only as fast as the returned
+/// stream is consumed.
+pub async fn download_file(token: &str, file_id: &str, range: Option<(u64, u64)>, config: &Config) -> Result<DownloadStream> {
+    let client = Client::new();
+    let file_url = format!("{}/{}", &config.drive_api_base_url, file_id);
+
+    let mut request = client
+        .get(&file_url)
+        .query(&[("alt", "media")])
+        .bearer_auth(token);
+    if let Some((start, end)) = range {
+        request = request.header(RANGE, format!("bytes={}-{}", start, end));
+    }
+
+    let response = request
+        .send()
+        .await
+        .context(format!("Failed to send request to download file '{}'", file_id))?;
+
+    if !response.status().is_success() {
+        return Err(DriveApiError::from_response(response).await).context("Failed to download file");
     }
+    if range.is_some() && response.status() != StatusCode::PARTIAL_CONTENT {
+        return Err(anyhow::anyhow!("Drive ignored the requested range: {}", response.status()));
+    }
+
+    let header = |name: reqwest::header::HeaderName| response.headers().get(name).and_then(|value| value.to_str().ok()).map(str::to_string);
+    let content_type = header(CONTENT_TYPE);
+    let etag = header(ETAG);
+    let content_length = response.content_length();
+
+    Ok(DownloadStream {
+        content_type,
+        content_length,
+        etag,
+        body: response
+            .bytes_stream()
+            .map(|chunk| chunk.context("Failed to read file bytes from response"))
+            .boxed(),
+    })
 }
 
-pub async fn download_pdf(token: &str, file_id: &str, config: &Config) -> Result<Vec<u8>> {
+/// Starts exporting a Google Workspace document to `mime_type`, streamed
+/// like `download_file`. Drive does not support ranges on exports.
+pub async fn export_file(token: &str, file_id: &str, mime_type: &str, config: &Config) -> Result<DownloadStream> {
     let client = Client::new();
-    let file_url = format!("{}/{}?alt=media", &config.drive_api_base_url, file_id);
+    let export_url = format!("{}/{}/export", &config.drive_api_base_url, file_id);
 
     let response = client
-        .get(&file_url)
+        .get(&export_url)
+        .query(&[("mimeType", mime_type)])
         .bearer_auth(token)
         .send()
         .await
-        .context(format!("Failed to send request to download file '{}'", file_id))?;
+        .context(format!("Failed to send request to export file '{}'", file_id))?;
 
-    if response.status().is_success() {
-        let file_bytes = response.bytes().await
-            .context("Failed to read file bytes from response")?;
+    if !response.status().is_success() {
+        return Err(DriveApiError::from_response(response).await).context("Failed to export file");
+    }
+
+    let header = |name: reqwest::header::HeaderName| response.headers().get(name).and_then(|value| value.to_str().ok()).map(str::to_string);
+    let content_type = header(CONTENT_TYPE);
+    let etag = header(ETAG);
+    let content_length = response.content_length();
+
+    Ok(DownloadStream {
+        content_type,
+        content_length,
+        etag,
+        body: response
+            .bytes_stream()
+            .map(|chunk| chunk.context("Failed to read exported bytes from response"))
+            .boxed(),
+    })
+}
+
+/// Every chunk of a resumable upload except the last must be a multiple of this size.
+pub const UPLOAD_CHUNK_ALIGNMENT: usize = 256 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkStatus {
+    /// `308 Resume Incomplete`: Drive has persisted every byte before `next_offset`.
+    Incomplete { next_offset: u64 },
+    /// The upload is finished and the file was created.
+    Complete { file_id: String, md5_checksum: Option<String> },
+}
 
-        Ok(file_bytes.to_vec())
+/// Fields of the file Drive returns when a resumable upload completes.
+pub const UPLOAD_RESPONSE_FIELDS: &str = "id,md5Checksum";
+
+/// Builds the `Content-Range` header for a chunk starting at `start`.
+///
+/// `total` stays unknown (`*`) until the last chunk. An empty last chunk
+/// is sent as `bytes */total`.
+pub fn content_range(start: u64, len: usize, total: Option<u64>) -> String {
+    let total = total.map_or_else(|| "*".to_string(), |total| total.to_string());
+
+    if len == 0 {
+        format!("bytes */{}", total)
     } else {
-        Err(anyhow::anyhow!("Failed to download file: {}", response.status()))
+        format!("bytes {}-{}/{}", start, start + len as u64 - 1, total)
     }
 }
 
-pub async fn upload_pdf_file(
+/// A parsed `Content-Range: bytes a-b/total` request header. The range is
+/// absent for `bytes */total`, and the total is `None` when sent as `*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentRange {
+    pub range: Option<(u64, u64)>,
+    pub total: Option<u64>,
+}
+
+pub fn parse_content_range(value: &str) -> Option<ContentRange> {
+    let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+
+    let total = match total.trim() {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    let range = match range.trim() {
+        "*" => None,
+        range => {
+            let (start, end) = range.split_once('-')?;
+            let (start, end): (u64, u64) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+            if end < start || total.is_some_and(|total| end >= total) {
+                return None;
+            }
+            Some((start, end))
+        }
+    };
+
+    Some(ContentRange { range, total })
+}
+
+/// Reads the `Range: bytes=0-N` header of a 308 response as the next byte to send.
+/// A missing header means Drive has not persisted anything yet.
+pub fn next_offset_from_range(range: Option<&str>) -> Option<u64> {
+    match range {
+        None => Some(0),
+        Some(range) => {
+            let (start, end) = range.trim().strip_prefix("bytes=")?.split_once('-')?;
+            if start.trim() != "0" {
+                return None;
+            }
+            end.trim().parse::<u64>().ok().map(|end| end + 1)
+        }
+    }
+}
+
+/// Sends one chunk of a resumable upload with the matching `Content-Range`.
+pub async fn upload_chunk(
     token: &str,
     resumable_url: &str,
-    file_content: Vec<u8>,
-    start_byte: Option<u64>
-) -> Result<String> {
-    let client = Client::new();
-    let mut headers = HeaderMap::new();
-    if let Some(start) = start_byte {
-        headers.insert(RANGE, format!("bytes={}-", start).parse()?);
-    }
+    chunk: Vec<u8>,
+    start: u64,
+    total: Option<u64>,
+) -> Result<ChunkStatus> {
+    // 308 is "Resume Incomplete" here, never a redirect to follow.
+    let client = Client::builder()
+        .redirect(Policy::none())
+        .build()
+        .context("Failed to build HTTP client")?;
 
+    let range = content_range(start, chunk.len(), total);
     let response = client
         .put(resumable_url)
         .bearer_auth(token)
-        .headers(headers.into())
-        .body(file_content)
+        .header(CONTENT_RANGE, &range)
+        .body(chunk)
         .send()
         .await
-        .context("Failed to upload PDF file chunk")?;
+        .context(format!("Failed to upload file chunk ({})", range))?;
 
-    if response.status().is_success() {
+    if response.status() == StatusCode::PERMANENT_REDIRECT {
+        let range_header = response.headers().get(RANGE).map(|value| value.to_str()).transpose()
+            .context("Invalid Range header in resumable upload response")?;
+        let next_offset = next_offset_from_range(range_header)
+            .ok_or_else(|| anyhow::anyhow!("Unexpected Range header in resumable upload response: {:?}", range_header))?;
+
+        Ok(ChunkStatus::Incomplete { next_offset })
+    } else if response.status().is_success() {
         let json_response: serde_json::Value = response.json().await
-            .context("Failed to parse response as JSON after uploading chunk")?;
+            .context("Failed to parse response as JSON after uploading the last chunk")?;
         let file_id = json_response["id"].as_str().unwrap_or("").to_string();
-        Ok(file_id)
+        let md5_checksum = json_response["md5Checksum"].as_str().map(str::to_string);
+
+        Ok(ChunkStatus::Complete { file_id, md5_checksum })
     } else {
-        Err(anyhow::anyhow!("Failed to upload file: {}", response.status()))
+        Err(anyhow::anyhow!("Failed to upload file chunk ({}): {}", range, response.status()))
+    }
+}
+
+/// Metadata of the Drive file a resumable upload creates.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UploadMetadata {
+    pub name: String,
+    pub mime_type: Option<String>,
+    pub description: Option<String>,
+    pub app_properties: BTreeMap<String, String>,
+    /// Defaults to the root folder.
+    pub folder_id: Option<String>,
+    /// Size of the content, sent as `X-Upload-Content-Length` when known.
+    pub size: Option<u64>,
+}
+
+impl UploadMetadata {
+    pub fn new(name: impl Into<String>) -> Self {
+        UploadMetadata { name: name.into(), ..UploadMetadata::default() }
+    }
+
+    /// The `files.create` request body.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut body = json!({
+            "name": self.name,
+            "parents": [self.folder_id.as_deref().unwrap_or("root")]
+        });
+        if let Some(mime_type) = &self.mime_type {
+            body["mimeType"] = json!(mime_type);
+        }
+        if let Some(description) = &self.description {
+            body["description"] = json!(description);
+        }
+        if !self.app_properties.is_empty() {
+            body["appProperties"] = json!(self.app_properties);
+        }
+        body
     }
 }
 
 pub async fn initialize_resumable_upload(
     token: &str,
-    folder_id: &str,
-    file_name: &str,
+    metadata: &UploadMetadata,
     config: &Config,
 ) -> Result<String> {
     let client = Client::new();
-    let upload_url = format!("{}?uploadType=resumable", &config.drive_upload_url);
-
-    let metadata = json!({
-        "name": file_name,
-        "parents": [folder_id]
-    });
+    // Query parameters of the first request also shape the final response.
+    let upload_url = format!("{}?uploadType=resumable&fields={}", &config.drive_upload_url, UPLOAD_RESPONSE_FIELDS);
 
     let mut headers = HeaderMap::new();
     headers.insert(CONTENT_TYPE, "application/json".parse()?);
     headers.insert(AUTHORIZATION, format!("Bearer {}", token).parse()?);
+    if let Some(mime_type) = &metadata.mime_type {
+        headers.insert(HeaderName::from_static("x-upload-content-type"), mime_type.parse().context("Invalid MIME type")?);
+    }
+    if let Some(size) = metadata.size {
+        headers.insert(HeaderName::from_static("x-upload-content-length"), size.into());
+    }
 
     let response = client
         .post(&upload_url)
         .headers(headers.into())
-        .json(&metadata)
+        .json(&metadata.to_json())
         .send()
         .await
         .context("Failed to initialize resumable upload")?;
@@ -156,3 +494,240 @@ pub async fn initialize_resumable_upload(
         Err(anyhow::anyhow!("Failed to initialize upload: {}", response.status()))
     }
 }
+
+/// Cancels a resumable upload session; Drive answers `499` once it is gone.
+pub async fn cancel_resumable_upload(token: &str, resumable_url: &str) -> Result<()> {
+    let client = Client::new();
+
+    let response = client
+        .delete(resumable_url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context("Failed to cancel resumable upload")?;
+
+    match response.status().as_u16() {
+        200..=299 | 404 | 499 => Ok(()),
+        status => Err(anyhow::anyhow!("Failed to cancel upload: {}", status)),
+    }
+}
+
+/// A folder to create with `files.create`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FolderMetadata {
+    pub name: String,
+    /// Defaults to the root folder.
+    pub parent_id: Option<String>,
+    pub app_properties: BTreeMap<String, String>,
+}
+
+impl FolderMetadata {
+    pub fn new(name: impl Into<String>) -> Self {
+        FolderMetadata { name: name.into(), ..FolderMetadata::default() }
+    }
+
+    /// The `files.create` request body.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut body = json!({
+            "name": self.name,
+            "mimeType": FOLDER_MIME_TYPE,
+            "parents": [self.parent_id.as_deref().unwrap_or("root")]
+        });
+        if !self.app_properties.is_empty() {
+            body["appProperties"] = json!(self.app_properties);
+        }
+        body
+    }
+}
+
+pub async fn create_folder(token: &str, folder: &FolderMetadata, config: &Config) -> Result<File> {
+    let client = Client::new();
+
+    let response = client
+        .post(&config.drive_api_base_url)
+        .query(&[("fields", "id,name,mimeType,createdTime")])
+        .bearer_auth(token)
+        .json(&folder.to_json())
+        .send()
+        .await
+        .context(format!("Failed to send request to create folder '{}'", folder.name))?;
+
+    if !response.status().is_success() {
+        return Err(DriveApiError::from_response(response).await).context("Failed to create folder");
+    }
+
+    response.json::<File>().await.context("Failed to parse created folder")
+}
+
+/// Fields returned for a file after it is changed or copied.
+const UPDATED_FILE_FIELDS: &str = "id,name,mimeType,createdTime";
+
+/// Moves a file to the trash, or restores it from there when `trashed` is false.
+pub async fn set_trashed(token: &str, file_id: &str, trashed: bool, config: &Config) -> Result<File> {
+    let client = Client::new();
+    let file_url = format!("{}/{}", &config.drive_api_base_url, file_id);
+
+    let response = client
+        .patch(&file_url)
+        .query(&[("fields", UPDATED_FILE_FIELDS)])
+        .bearer_auth(token)
+        .json(&json!({ "trashed": trashed }))
+        .send()
+        .await
+        .context(format!("Failed to send request to update file '{}'", file_id))?;
+
+    if !response.status().is_success() {
+        let action = if trashed { "trash" } else { "restore" };
+        return Err(DriveApiError::from_response(response).await).context(format!("Failed to {} file", action));
+    }
+
+    response.json::<File>().await.context("Failed to parse updated file")
+}
+
+/// Deletes a file permanently, skipping the trash.
+pub async fn delete_file(token: &str, file_id: &str, config: &Config) -> Result<()> {
+    let client = Client::new();
+    let file_url = format!("{}/{}", &config.drive_api_base_url, file_id);
+
+    let response = client
+        .delete(&file_url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(format!("Failed to send request to delete file '{}'", file_id))?;
+
+    if !response.status().is_success() {
+        return Err(DriveApiError::from_response(response).await).context("Failed to delete file");
+    }
+    Ok(())
+}
+
+/// Permanently deletes every file in the user's trash.
+pub async fn empty_trash(token: &str, config: &Config) -> Result<()> {
+    let client = Client::new();
+    let trash_url = format!("{}/trash", &config.drive_api_base_url);
+
+    let response = client
+        .delete(&trash_url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .context("Failed to send request to empty the trash")?;
+
+    if !response.status().is_success() {
+        return Err(DriveApiError::from_response(response).await).context("Failed to empty the trash");
+    }
+    Ok(())
+}
+
+/// Changes to apply with `files.update`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileUpdate {
+    pub name: Option<String>,
+    /// Folders to add the file to; with `remove_parents` this moves it.
+    pub add_parents: Vec<String>,
+    pub remove_parents: Vec<String>,
+}
+
+impl FileUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.add_parents.is_empty() && self.remove_parents.is_empty()
+    }
+}
+
+/// Renames a file and/or moves it between parents, in a single request.
+pub async fn update_file(token: &str, file_id: &str, update: &FileUpdate, config: &Config) -> Result<File> {
+    let client = Client::new();
+    let file_url = format!("{}/{}", &config.drive_api_base_url, file_id);
+
+    let mut query = vec![("fields", UPDATED_FILE_FIELDS.to_string())];
+    if !update.add_parents.is_empty() {
+        query.push(("addParents", update.add_parents.join(",")));
+    }
+    if !update.remove_parents.is_empty() {
+        query.push(("removeParents", update.remove_parents.join(",")));
+    }
+    let mut body = json!({});
+    if let Some(name) = &update.name {
+        body["name"] = json!(name);
+    }
+
+    let response = client
+        .patch(&file_url)
+        .query(&query)
+        .bearer_auth(token)
+        .json(&body)
+        .send()
+        .await
+        .context(format!("Failed to send request to update file '{}'", file_id))?;
+
+    if !response.status().is_success() {
+        return Err(DriveApiError::from_response(response).await).context("Failed to update file");
+    }
+
+    response.json::<File>().await.context("Failed to parse updated file")
+}
+
+/// Where to put a copy made with `files.copy`, and what to call it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CopyMetadata {
+    /// Keeps Drive's default name when unset.
+    pub name: Option<String>,
+    /// Keeps the source's parents when unset.
+    pub parent_id: Option<String>,
+}
+
+impl CopyMetadata {
+    /// The `files.copy` request body.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut body = json!({});
+        if let Some(name) = &self.name {
+            body["name"] = json!(name);
+        }
+        if let Some(parent_id) = &self.parent_id {
+            body["parents"] = json!([parent_id]);
+        }
+        body
+    }
+}
+
+/// Copies a file on Drive's side; folders cannot be copied this way.
+pub async fn copy_file(token: &str, file_id: &str, copy: &CopyMetadata, config: &Config) -> Result<File> {
+    let client = Client::new();
+    let copy_url = format!("{}/{}/copy", &config.drive_api_base_url, file_id);
+
+    let response = client
+        .post(&copy_url)
+        .query(&[("fields", UPDATED_FILE_FIELDS)])
+        .bearer_auth(token)
+        .json(&copy.to_json())
+        .send()
+        .await
+        .context(format!("Failed to send request to copy file '{}'", file_id))?;
+
+    if !response.status().is_success() {
+        return Err(DriveApiError::from_response(response).await).context("Failed to copy file");
+    }
+
+    response.json::<File>().await.context("Failed to parse copied file")
+}
+
+/// Fetches the selected metadata of one file, without its content.
+pub async fn get_file_metadata(token: &str, file_id: &str, fields: &FieldSelection, config: &Config) -> Result<FileMetadata> {
+    let client = Client::new();
+    let file_url = format!("{}/{}", &config.drive_api_base_url, file_id);
+
+    let response = client
+        .get(&file_url)
+        .query(&[("fields", fields.drive_fields())])
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(format!("Failed to send request for metadata of file '{}'", file_id))?;
+
+    if !response.status().is_success() {
+        return Err(DriveApiError::from_response(response).await).context("Failed to get file metadata");
+    }
+
+    response.json().await.context("Failed to parse file metadata")
+}
@@ -1,2 +1,7 @@
 pub mod auth;
-pub mod google_drive;
\ No newline at end of file
+pub mod drive_error;
+pub mod drive_query;
+pub mod google_drive;
+pub mod export_formats;
+pub mod file_metadata;
+pub mod permissions;
\ No newline at end of file
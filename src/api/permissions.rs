@@ -0,0 +1,309 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+use crate::api::drive_error::DriveApiError;
+use crate::config::Config;
+
+const PERMISSION_FIELDS: &str = "id,type,role,emailAddress,domain,displayName,allowFileDiscovery,expirationTime";
+
+/// Who a permission grants access to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionType {
+    User,
+    Group,
+    Domain,
+    /// Anyone with the link.
+    Anyone,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionRole {
+    Owner,
+    Organizer,
+    FileOrganizer,
+    Writer,
+    Commenter,
+    Reader,
+}
+
+/// A permission on a file or folder, as Drive reports it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Permission {
+    pub id: Option<String>,
+    #[serde(rename = "type")]
+    pub permission_type: PermissionType,
+    pub role: PermissionRole,
+    /// Set for `user` and `group` permissions.
+    #[serde(default, alias = "emailAddress", skip_serializing_if = "Option::is_none")]
+    pub email_address: Option<String>,
+    /// Set for `domain` permissions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(default, alias = "displayName", skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// Whether `domain` and `anyone` permissions make the file show up in searches.
+    #[serde(default, alias = "allowFileDiscovery", skip_serializing_if = "Option::is_none")]
+    pub allow_file_discovery: Option<bool>,
+    #[serde(default, alias = "expirationTime", skip_serializing_if = "Option::is_none")]
+    pub expiration_time: Option<String>,
+}
+
+/// A permission to grant with `permissions.create`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewPermission {
+    pub permission_type: PermissionType,
+    pub role: PermissionRole,
+    pub email_address: Option<String>,
+    pub domain: Option<String>,
+    pub allow_file_discovery: Option<bool>,
+    pub expiration_time: Option<String>,
+}
+
+/// How Drive should carry out a new permission.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PermissionOptions {
+    /// Drive notifies users and groups unless this is `Some(false)`.
+    pub send_notification_email: Option<bool>,
+    pub email_message: Option<String>,
+    /// Required to make someone else the owner; the caller becomes a writer.
+    pub transfer_ownership: bool,
+}
+
+/// Changes to apply with `permissions.update`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PermissionUpdate {
+    pub role: PermissionRole,
+    pub expiration_time: Option<String>,
+    pub transfer_ownership: bool,
+}
+
+impl NewPermission {
+    /// Checks the combinations Drive would refuse, so callers get a 400
+    /// that says why.
+    pub fn validate(&self, options: &PermissionOptions) -> Result<(), String> {
+        let has_email = self.email_address.as_deref().is_some_and(|email| !email.trim().is_empty());
+        let has_domain = self.domain.as_deref().is_some_and(|domain| !domain.trim().is_empty());
+
+        match self.permission_type {
+            PermissionType::User | PermissionType::Group => {
+                if !has_email {
+                    return Err("email_address is required for user and group permissions".to_string());
+                }
+                if has_domain {
+                    return Err("domain is only allowed for domain permissions".to_string());
+                }
+            }
+            PermissionType::Domain => {
+                if !has_domain {
+                    return Err("domain is required for domain permissions".to_string());
+                }
+                if has_email {
+                    return Err("email_address is only allowed for user and group permissions".to_string());
+                }
+            }
+            PermissionType::Anyone => {
+                if has_email || has_domain {
+                    return Err("anyone permissions take neither email_address nor domain".to_string());
+                }
+            }
+        }
+
+        let notifies = matches!(self.permission_type, PermissionType::User | PermissionType::Group);
+        if !notifies && (options.send_notification_email.is_some() || options.email_message.is_some()) {
+            return Err("Notification emails can only be sent to users and groups".to_string());
+        }
+        if options.email_message.is_some() && options.send_notification_email == Some(false) {
+            return Err("email_message needs send_notification_email".to_string());
+        }
+        if self.allow_file_discovery.is_some() && notifies {
+            return Err("allow_file_discovery is only allowed for domain and anyone permissions".to_string());
+        }
+
+        validate_ownership(self.role, options.transfer_ownership)?;
+        if self.role == PermissionRole::Owner {
+            if self.permission_type != PermissionType::User {
+                return Err("Only a user can become the owner".to_string());
+            }
+            if options.send_notification_email == Some(false) {
+                return Err("Ownership transfers always notify the new owner".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// The `permissions.create` request body.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut body = json!({ "type": self.permission_type, "role": self.role });
+        if let Some(email_address) = &self.email_address {
+            body["emailAddress"] = json!(email_address);
+        }
+        if let Some(domain) = &self.domain {
+            body["domain"] = json!(domain);
+        }
+        if let Some(allow_file_discovery) = self.allow_file_discovery {
+            body["allowFileDiscovery"] = json!(allow_file_discovery);
+        }
+        if let Some(expiration_time) = &self.expiration_time {
+            body["expirationTime"] = json!(expiration_time);
+        }
+        body
+    }
+}
+
+impl PermissionUpdate {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_ownership(self.role, self.transfer_ownership)
+    }
+}
+
+/// Drive only makes someone the owner when the transfer is explicit, and
+/// `transfer_ownership` means nothing for any other role.
+fn validate_ownership(role: PermissionRole, transfer_ownership: bool) -> Result<(), String> {
+    match (role, transfer_ownership) {
+        (PermissionRole::Owner, false) => Err("Making someone the owner needs transfer_ownership".to_string()),
+        (PermissionRole::Owner, true) | (_, false) => Ok(()),
+        (_, true) => Err("transfer_ownership needs the owner role".to_string()),
+    }
+}
+
+/// Everyone a file is shared with.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PermissionList {
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(Deserialize)]
+struct PermissionPage {
+    #[serde(default)]
+    permissions: Vec<Permission>,
+    #[serde(rename = "nextPageToken")]
+    next_page_token: Option<String>,
+}
+
+fn permissions_url(config: &Config, file_id: &str) -> String {
+    format!("{}/{}/permissions", &config.drive_api_base_url, file_id)
+}
+
+/// Lists every permission on a file, following all pages.
+pub async fn list_permissions(token: &str, file_id: &str, config: &Config) -> Result<Vec<Permission>> {
+    let client = Client::new();
+    let fields = format!("nextPageToken,permissions({})", PERMISSION_FIELDS);
+    let mut permissions = Vec::new();
+    let mut page_token: Option<String> = None;
+
+    loop {
+        let mut params = vec![("fields", fields.clone()), ("pageSize", "100".to_string())];
+        if let Some(page_token) = &page_token {
+            params.push(("pageToken", page_token.clone()));
+        }
+
+        let response = client
+            .get(permissions_url(config, file_id))
+            .query(&params)
+            .bearer_auth(token)
+            .send()
+            .await
+            .context(format!("Failed to send request to list permissions of file '{}'", file_id))?;
+
+        if !response.status().is_success() {
+            return Err(DriveApiError::from_response(response).await).context("Failed to list permissions");
+        }
+
+        let page: PermissionPage = response.json().await.context("Failed to parse permissions")?;
+        permissions.extend(page.permissions);
+        match page.next_page_token {
+            Some(next) if !next.is_empty() => page_token = Some(next),
+            _ => return Ok(permissions),
+        }
+    }
+}
+
+pub async fn create_permission(
+    token: &str,
+    file_id: &str,
+    permission: &NewPermission,
+    options: &PermissionOptions,
+    config: &Config,
+) -> Result<Permission> {
+    let client = Client::new();
+
+    let mut params = vec![("fields", PERMISSION_FIELDS.to_string())];
+    if let Some(send_notification_email) = options.send_notification_email {
+        params.push(("sendNotificationEmail", send_notification_email.to_string()));
+    }
+    if let Some(email_message) = &options.email_message {
+        params.push(("emailMessage", email_message.clone()));
+    }
+    if options.transfer_ownership {
+        params.push(("transferOwnership", "true".to_string()));
+    }
+
+    let response = client
+        .post(permissions_url(config, file_id))
+        .query(&params)
+        .bearer_auth(token)
+        .json(&permission.to_json())
+        .send()
+        .await
+        .context(format!("Failed to send request to share file '{}'", file_id))?;
+
+    if !response.status().is_success() {
+        return Err(DriveApiError::from_response(response).await).context("Failed to create permission");
+    }
+
+    response.json().await.context("Failed to parse created permission")
+}
+
+pub async fn update_permission(
+    token: &str,
+    file_id: &str,
+    permission_id: &str,
+    update: &PermissionUpdate,
+    config: &Config,
+) -> Result<Permission> {
+    let client = Client::new();
+
+    let mut params = vec![("fields", PERMISSION_FIELDS.to_string())];
+    if update.transfer_ownership {
+        params.push(("transferOwnership", "true".to_string()));
+    }
+    let mut body = json!({ "role": update.role });
+    if let Some(expiration_time) = &update.expiration_time {
+        body["expirationTime"] = json!(expiration_time);
+    }
+
+    let response = client
+        .patch(format!("{}/{}", permissions_url(config, file_id), permission_id))
+        .query(&params)
+        .bearer_auth(token)
+        .json(&body)
+        .send()
+        .await
+        .context(format!("Failed to send request to update permission '{}'", permission_id))?;
+
+    if !response.status().is_success() {
+        return Err(DriveApiError::from_response(response).await).context("Failed to update permission");
+    }
+
+    response.json().await.context("Failed to parse updated permission")
+}
+
+pub async fn delete_permission(token: &str, file_id: &str, permission_id: &str, config: &Config) -> Result<()> {
+    let client = Client::new();
+
+    let response = client
+        .delete(format!("{}/{}", permissions_url(config, file_id), permission_id))
+        .bearer_auth(token)
+        .send()
+        .await
+        .context(format!("Failed to send request to delete permission '{}'", permission_id))?;
+
+    if !response.status().is_success() {
+        return Err(DriveApiError::from_response(response).await).context("Failed to delete permission");
+    }
+    Ok(())
+}
@@ -1,7 +1,17 @@
+use actix_web::cookie::Key;
 use std::env;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthMode {
+    /// Callers authenticate through the browser OAuth flow or send their own tokens.
+    OAuth,
+    /// Drive calls use server-held service-account credentials.
+    ServiceAccount,
+}
+
 #[derive(Clone)]
 pub struct Config {
+    pub auth_mode: AuthMode,
     pub client_id: String,
     pub client_secret: String,
     pub redirect_uri: String,
@@ -9,26 +19,115 @@ pub struct Config {
     pub serv_addrs: String,
     pub drive_api_base_url: String,
     pub drive_upload_url: String,
-    pub auth_uri: &'static str,
-    pub token_uri: &'static str,
+    pub auth_uri: String,
+    pub token_uri: String,
+    pub tokeninfo_uri: String,
+    pub revoke_uri: String,
+    pub userinfo_uri: String,
+    pub session_key: Key,
+    pub session_cookie_secure: bool,
+    pub session_store: String,
+    pub session_db_path: String,
+    pub token_cache_capacity: usize,
+    pub token_cache_negative_ttl_secs: u64,
+    pub upload_chunk_size: usize,
+    /// Names of the upload validators to enable, e.g. `pdf`, `png`, `docx`.
+    pub upload_allowed_types: Vec<String>,
+    pub max_upload_size: u64,
+    pub validate_pdf_xref: bool,
+    pub service_account_key_path: Option<String>,
+    pub service_account_subject: Option<String>,
+    pub service_api_key: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Config {
     pub fn new() -> Self {
         dotenv::dotenv().ok();
 
-        let client_id = env::var("CLIENT_ID").expect("Client ID is missing.");
-        let client_secret = env::var("CLIENT_SECRET").expect("Client secret is missing.");
+        let auth_mode = match env::var("AUTH_MODE").as_deref() {
+            Ok("service_account") => AuthMode::ServiceAccount,
+            _ => AuthMode::OAuth,
+        };
+
+        let (client_id, client_secret) = match auth_mode {
+            AuthMode::OAuth => (
+                env::var("CLIENT_ID").expect("Client ID is missing."),
+                env::var("CLIENT_SECRET").expect("Client secret is missing."),
+            ),
+            AuthMode::ServiceAccount => (
+                env::var("CLIENT_ID").unwrap_or_default(),
+                env::var("CLIENT_SECRET").unwrap_or_default(),
+            ),
+        };
         let scope = env::var("SCOPE").unwrap_or_else(|_| "https://www.googleapis.com/auth/drive".to_string());
         let serv_addrs = env::var("SERV_ADDRS").unwrap_or_else(|_| "0.0.0.0:8080".to_string());
         let redirect_uri = env::var("REDIRECT_URI").unwrap_or_else(|_| "http://127.0.0.1:8080/auth/callback".to_string());
         let drive_api_base_url = env::var("GOOGLE_DRIVE_API_BASE_URL").unwrap_or_else(|_| "https://www.googleapis.com/drive/v3/files".to_string());
         let drive_upload_url = env::var("GOOGLE_DRIVE_UPLOAD_URL").unwrap_or_else(|_| "https://www.googleapis.com/upload/drive/v3/files".to_string());
      
-        let auth_uri = "https://accounts.google.com/o/oauth2/auth";
-        let token_uri = "https://oauth2.googleapis.com/token";
+        let auth_uri = env::var("AUTH_URI").unwrap_or_else(|_| "https://accounts.google.com/o/oauth2/auth".to_string());
+        let token_uri = env::var("TOKEN_URI").unwrap_or_else(|_| "https://oauth2.googleapis.com/token".to_string());
+        let tokeninfo_uri = env::var("TOKENINFO_URI").unwrap_or_else(|_| "https://www.googleapis.com/oauth2/v3/tokeninfo".to_string());
+        let revoke_uri = env::var("REVOKE_URI").unwrap_or_else(|_| "https://oauth2.googleapis.com/revoke".to_string());
+        let userinfo_uri = env::var("USERINFO_URI").unwrap_or_else(|_| "https://openidconnect.googleapis.com/v1/userinfo".to_string());
+
+        let session_key = match env::var("SESSION_SECRET") {
+            Ok(secret) => {
+                assert!(secret.len() >= 64, "SESSION_SECRET must be at least 64 bytes long.");
+                Key::from(secret.as_bytes())
+            }
+            Err(_) => {
+                println!("SESSION_SECRET not set, session cookies will not survive a restart.");
+                Key::generate()
+            }
+        };
+        let session_cookie_secure = env::var("SESSION_COOKIE_SECURE")
+            .map(|value| value == "true")
+            .unwrap_or(false);
+        let session_store = env::var("SESSION_STORE").unwrap_or_else(|_| "memory".to_string());
+        let session_db_path = env::var("SESSION_DB_PATH").unwrap_or_else(|_| "sessions.db".to_string());
+        let token_cache_capacity = env::var("TOKEN_CACHE_CAPACITY")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(10_000);
+        let token_cache_negative_ttl_secs = env::var("TOKEN_CACHE_NEGATIVE_TTL_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(30);
+        let upload_chunk_size = env::var("UPLOAD_CHUNK_SIZE")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(8 * 1024 * 1024);
+        let upload_allowed_types = env::var("UPLOAD_ALLOWED_TYPES")
+            .unwrap_or_else(|_| "pdf".to_string())
+            .split(',')
+            .map(|name| name.trim().to_lowercase())
+            .filter(|name| !name.is_empty())
+            .collect();
+        let max_upload_size = env::var("MAX_UPLOAD_SIZE")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(100 * 1024 * 1024);
+        let validate_pdf_xref = env::var("VALIDATE_PDF_XREF")
+            .map(|value| value == "true")
+            .unwrap_or(false);
+
+        let service_account_key_path = env::var("SERVICE_ACCOUNT_KEY_PATH").ok();
+        let service_account_subject = env::var("SERVICE_ACCOUNT_SUBJECT").ok();
+        let service_api_key = env::var("SERVICE_API_KEY").ok();
+        if auth_mode == AuthMode::ServiceAccount {
+            assert!(service_account_key_path.is_some(), "SERVICE_ACCOUNT_KEY_PATH is missing.");
+            assert!(service_api_key.is_some(), "SERVICE_API_KEY is missing.");
+        }
 
         Config {
+            auth_mode,
             client_id,
             client_secret,
             redirect_uri,
@@ -38,6 +137,22 @@ impl Config {
             drive_upload_url,
             auth_uri,
             token_uri,
+            tokeninfo_uri,
+            revoke_uri,
+            userinfo_uri,
+            session_key,
+            session_cookie_secure,
+            session_store,
+            session_db_path,
+            token_cache_capacity,
+            token_cache_negative_ttl_secs,
+            upload_chunk_size,
+            upload_allowed_types,
+            max_upload_size,
+            validate_pdf_xref,
+            service_account_key_path,
+            service_account_subject,
+            service_api_key,
         }
     }
 }
@@ -1,35 +1,114 @@
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use anyhow::Context;
-use crate::{api::auth::build_auth_url, config::Config, services::auth_service::{AuthCallbackQuery, AuthService}};
+use crate::{
+    api::auth::build_auth_url,
+    config::Config,
+    middlewares::{
+        session::{bearer_token, login_state_cookie, login_state_from_cookie, login_state_removal_cookie, session_cookie, session_id_from_cookie, session_removal_cookie},
+        token_cache::TokenCache,
+    },
+    services::{
+        auth_service::{AuthCallbackQuery, AuthService, SessionResponse},
+        pkce::{code_challenge, generate_code_verifier, generate_state},
+        session_store::{new_session_id, PendingLogin, Session, SessionStore},
+    },
+};
 
 #[utoipa::path(
     get,
     path = "/auth/callback",
     params(
-        ("code" = String, Query, description = "Authorization code returned by the OAuth2 provider after the user authorizes the application")
+        ("code" = Option<String>, Query, description = "Authorization code returned by the OAuth2 provider after the user authorizes the application"),
+        ("state" = Option<String>, Query, description = "State value issued by /auth, used to reject forged callbacks"),
+        ("error" = Option<String>, Query, description = "Error code returned by the OAuth2 provider when authorization fails or is denied"),
+        ("error_description" = Option<String>, Query, description = "Human readable description of the provider error")
     ),
     responses(
-        (status = 200, description = "Processes the response from the OAuth2 provider after the redirection, using the authorization code to get an access token."),
+        (status = 200, description = "Processes the response from the OAuth2 provider after the redirection, exchanging the authorization code for tokens that are kept server-side. Sets a signed HttpOnly session cookie and returns the opaque session id.", body = SessionResponse),
+        (status = 400, description = "Missing or unknown state, a state that does not match the browser's login_state cookie, missing code, or the provider returned an error."),
         (status = 500, description = "Failed to get access token.")
     ),
     tag = "auth"
 )]
 pub async fn auth_callback<T: AuthService>(
+    req: HttpRequest,
     query: web::Query<AuthCallbackQuery>,
     config: web::Data<Config>,
     token_service: web::Data<T>,
+    session_store: web::Data<dyn SessionStore>,
 ) -> impl Responder {
+    let mut response = complete_login(&req, &query, &config, token_service.get_ref(), session_store.get_ref()).await;
+    // The pre-login cookie is single use, like the state it carries.
+    if let Err(err) = response.add_cookie(&login_state_removal_cookie()) {
+        eprintln!("Error clearing login state cookie: {:?}", err);
+    }
+    response
+}
+
+async fn complete_login<T: AuthService>(
+    req: &HttpRequest,
+    query: &AuthCallbackQuery,
+    config: &Config,
+    token_service: &T,
+    session_store: &dyn SessionStore,
+) -> HttpResponse {
+    let pending_login = match &query.state {
+        Some(state) => match session_store.take_login(state).await {
+            Ok(pending_login) => pending_login,
+            Err(err) => {
+                eprintln!("Error loading pending login: {:?}", err);
+                return HttpResponse::InternalServerError().body(format!("Error loading pending login: {:?}", err));
+            }
+        },
+        None => None,
+    };
+
+    if let Some(error) = &query.error {
+        let description = query.error_description
+            .as_deref()
+            .map(|description| format!(": {}", description))
+            .unwrap_or_default();
+        return HttpResponse::BadRequest().body(format!("Authorization failed: {}{}", error, description));
+    }
+
+    let state = match &query.state {
+        Some(state) => state,
+        None => return HttpResponse::BadRequest().body("Missing state parameter"),
+    };
+
+    // A state issued to another browser means someone is trying to log this one into their account.
+    if login_state_from_cookie(req, config).as_deref() != Some(state.as_str()) {
+        return HttpResponse::BadRequest().body("State does not match the login started in this browser");
+    }
+
+    let pending_login = match pending_login {
+        Some(pending_login) => pending_login,
+        None => return HttpResponse::BadRequest().body("Invalid or expired state parameter"),
+    };
+
+    let code = match &query.code {
+        Some(code) => code,
+        None => return HttpResponse::BadRequest().body("Missing authorization code"),
+    };
+
     match token_service
-        .get_access_token(&query.code, &config)
+        .get_access_token(code, &pending_login.code_verifier, config)
         .await
         .context("Failed to obtain access token")
     {
         Ok(token_response) => {
-            HttpResponse::Ok().body(format!(
-                "Access Token: {}\nExpires in: {}",
-                token_response.access_token,
-                token_response.expires_in
-            ))
+            let session_id = new_session_id();
+            let session = Session::from_token_response(token_response);
+            let expires_in = session.expires_in();
+
+            if let Err(err) = session_store.insert(&session_id, session).await {
+                eprintln!("Error storing session: {:?}", err);
+                return HttpResponse::InternalServerError().body(format!("Error storing session: {:?}", err));
+            }
+
+            HttpResponse::Ok()
+                .cookie(session_cookie(&session_id, config))
+                .json(SessionResponse { session_id, expires_in })
         }
         Err(err) => {
             HttpResponse::InternalServerError().body(format!("Error obtaining token: {:?}", err))
@@ -37,16 +116,163 @@ pub async fn auth_callback<T: AuthService>(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    responses(
+        (status = 200, description = "The session's Google tokens were renewed server-side; returns the session id and its new lifetime", body = SessionResponse),
+        (status = 400, description = "The session has no refresh token"),
+        (status = 401, description = "No session cookie or bearer session id was provided, or the session does not exist"),
+        (status = 500, description = "Failed to refresh access token.")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "auth"
+)]
+pub async fn refresh_token<T: AuthService>(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    token_service: web::Data<T>,
+    session_store: web::Data<dyn SessionStore>,
+) -> impl Responder {
+    let session_id = match session_id_from_cookie(&req, &config).or_else(|| bearer_token(&req)) {
+        Some(session_id) => session_id,
+        None => return HttpResponse::Unauthorized().body("Session missing"),
+    };
+
+    let session = match session_store.get(&session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::Unauthorized().body("Unknown session"),
+        Err(err) => {
+            eprintln!("Error loading session: {:?}", err);
+            return HttpResponse::InternalServerError().body(format!("Error loading session: {:?}", err));
+        }
+    };
+
+    let refresh_token = match &session.refresh_token {
+        Some(refresh_token) => refresh_token.clone(),
+        None => return HttpResponse::BadRequest().body("Session has no refresh token"),
+    };
+
+    match token_service
+        .refresh_access_token(&refresh_token, &config)
+        .await
+        .context("Failed to refresh access token")
+    {
+        Ok(token_response) => {
+            if let Some(cache) = req.app_data::<web::Data<TokenCache>>() {
+                cache.invalidate(&session.access_token);
+            }
+            let session = session.refreshed(token_response);
+            let expires_in = session.expires_in();
+
+            if let Err(err) = session_store.insert(&session_id, session).await {
+                eprintln!("Error storing session: {:?}", err);
+                return HttpResponse::InternalServerError().body(format!("Error storing session: {:?}", err));
+            }
+
+            HttpResponse::Ok().json(SessionResponse { session_id, expires_in })
+        }
+        Err(err) => {
+            eprintln!("Error refreshing token: {:?}", err);
+            HttpResponse::InternalServerError().body(format!("Error refreshing token: {:?}", err))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    responses(
+        (status = 200, description = "Token revoked at Google and the session, cached validation and session cookie cleared"),
+        (status = 401, description = "No session cookie or bearer token was provided"),
+        (status = 502, description = "Local state was cleared but Google failed to revoke the token")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "auth"
+)]
+pub async fn logout<T: AuthService>(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    token_service: web::Data<T>,
+    session_store: web::Data<dyn SessionStore>,
+) -> impl Responder {
+    let token_cache = req.app_data::<web::Data<TokenCache>>();
+    let bearer = bearer_token(&req);
+    let session_id = session_id_from_cookie(&req, &config).or_else(|| bearer.clone());
+
+    let mut session = None;
+    if let Some(session_id) = &session_id {
+        match session_store.get(session_id).await {
+            Ok(found) => session = found,
+            Err(err) => {
+                eprintln!("Error loading session: {:?}", err);
+                return HttpResponse::InternalServerError().body(format!("Error loading session: {:?}", err));
+            }
+        }
+    }
+
+    let token = match (&session, bearer) {
+        (Some(session), _) => {
+            if let Some(cache) = token_cache {
+                cache.invalidate(&session.access_token);
+            }
+            session.refresh_token.clone().unwrap_or_else(|| session.access_token.clone())
+        }
+        (None, Some(bearer)) => bearer,
+        (None, None) => return HttpResponse::Unauthorized().body("Authorization token missing"),
+    };
+
+    if let Some(cache) = token_cache {
+        cache.invalidate(&token);
+    }
+    if let (Some(_), Some(session_id)) = (&session, &session_id) {
+        if let Err(err) = session_store.remove(session_id).await {
+            eprintln!("Error removing session: {:?}", err);
+            return HttpResponse::InternalServerError().body(format!("Error removing session: {:?}", err));
+        }
+    }
+
+    match token_service.revoke_token(&token, &config).await {
+        Ok(()) => HttpResponse::Ok()
+            .cookie(session_removal_cookie())
+            .body("Logged out"),
+        Err(err) => {
+            eprintln!("Error revoking token: {:?}", err);
+            HttpResponse::BadGateway()
+                .cookie(session_removal_cookie())
+                .body(format!("Logged out locally, but token revocation failed: {:?}", err))
+        }
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/auth",
     responses(
-        (status = 200, description = "Returns authentication URL")
+        (status = 200, description = "Returns authentication URL carrying a one-time state and a PKCE S256 code challenge. Sets a short-lived signed HttpOnly login_state cookie that the callback must present with the same state."),
+        (status = 500, description = "Failed to store the pending login.")
     ),
     tag = "auth"
 )]
 pub async fn get_auth_url(
-    config: web::Data<Config>
-) -> String {
-    build_auth_url(config)
+    config: web::Data<Config>,
+    session_store: web::Data<dyn SessionStore>,
+) -> impl Responder {
+    let state = generate_state();
+    let code_verifier = generate_code_verifier();
+    let challenge = code_challenge(&code_verifier);
+
+    match session_store.insert_login(&state, PendingLogin::new(code_verifier)).await {
+        Ok(()) => HttpResponse::Ok()
+            .cookie(login_state_cookie(&state, &config))
+            .body(build_auth_url(&config, &state, &challenge)),
+        Err(err) => {
+            eprintln!("Error storing pending login: {:?}", err);
+            HttpResponse::InternalServerError().body(format!("Error storing pending login: {:?}", err))
+        }
+    }
 }
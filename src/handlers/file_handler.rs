@@ -0,0 +1,202 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use crate::{api::{drive_error::drive_error_response, drive_query::FOLDER_MIME_TYPE, file_metadata::{FieldSelection, FileMetadata}, google_drive::{CopyMetadata, FileUpdate}}, config::Config, middlewares::auth_guard::access_token, services::{folder_copy::{copy_folder, CopyResult}, google_drive_service::{DriveService, FileInfo}}};
+
+#[derive(Debug, Deserialize)]
+pub struct FileId {
+    file_id: String,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FieldsQuery {
+    /// Comma-separated metadata fields to return, e.g. `name,size,modified_time`
+    /// (Drive's names such as `modifiedTime` work too). `id` is always included.
+    pub fields: Option<String>,
+}
+
+impl FieldsQuery {
+    /// The parsed selection, `None` when no `fields` were given.
+    pub fn selection(&self) -> Result<Option<FieldSelection>, String> {
+        self.fields.as_deref().map(FieldSelection::parse).transpose()
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/drive/files/{file_id}/metadata",
+    params(
+        ("file_id" = String, Path, description = "ID of the file"),
+        FieldsQuery
+    ),
+    responses(
+        (status = 200, description = "The file's metadata, limited to the selected fields", body = FileMetadata),
+        (status = 400, description = "Authorization token missing or invalid, or unknown field"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation, or Drive refused access to the file"),
+        (status = 404, description = "File not found"),
+        (status = 500, description = "Internal server error while fetching the metadata")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn get_file_metadata<T: DriveService>(
+    file_id: web::Path<FileId>,
+    query: web::Query<FieldsQuery>,
+    req: HttpRequest,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+) -> impl Responder {
+    let token_str = match access_token(&req) {
+        Some(token) => token,
+        None => return HttpResponse::BadRequest().body("Authorization token missing or invalid"),
+    };
+
+    let fields = match query.selection() {
+        Ok(fields) => fields.unwrap_or_else(FieldSelection::all),
+        Err(message) => return HttpResponse::BadRequest().body(message),
+    };
+
+    match drive_service.get_file_metadata(&token_str, &file_id.file_id, &fields, &config).await {
+        Ok(metadata) => HttpResponse::Ok().json(metadata),
+        Err(err) => drive_error_response("getting file metadata", err),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateFileRequest {
+    /// New name for the file.
+    pub name: Option<String>,
+    /// Folders to add the file to.
+    #[serde(default)]
+    pub add_parents: Vec<String>,
+    /// Folders to take the file out of; together with `add_parents` this moves it.
+    #[serde(default)]
+    pub remove_parents: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct CopyFileRequest {
+    /// Name of the copy; defaults to the source's name.
+    pub name: Option<String>,
+    /// Folder to put the copy in; defaults to the source's folder.
+    pub parent_id: Option<String>,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/drive/files/{file_id}",
+    params(
+        ("file_id" = String, Path, description = "ID of the file to rename or move")
+    ),
+    request_body = UpdateFileRequest,
+    responses(
+        (status = 200, description = "File renamed and/or moved", body = FileInfo),
+        (status = 400, description = "Authorization token missing or invalid, or nothing to change"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation, or Drive refused to change the file"),
+        (status = 404, description = "File not found"),
+        (status = 500, description = "Internal server error while updating the file")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn update_file<T: DriveService>(
+    file_id: web::Path<FileId>,
+    body: web::Json<UpdateFileRequest>,
+    req: HttpRequest,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+) -> impl Responder {
+    let token_str = match access_token(&req) {
+        Some(token) => token,
+        None => return HttpResponse::BadRequest().body("Authorization token missing or invalid"),
+    };
+
+    let body = body.into_inner();
+    let update = FileUpdate { name: body.name, add_parents: body.add_parents, remove_parents: body.remove_parents };
+    if update.is_empty() {
+        return HttpResponse::BadRequest().body("Provide name, add_parents or remove_parents");
+    }
+    if update.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return HttpResponse::BadRequest().body("name must not be empty");
+    }
+    if update.add_parents.iter().any(|parent| update.remove_parents.contains(parent)) {
+        return HttpResponse::BadRequest().body("A folder cannot be both added and removed");
+    }
+
+    match drive_service.update_file(&token_str, &file_id.file_id, &update, &config).await {
+        Ok(file) => HttpResponse::Ok().json(file),
+        Err(err) => drive_error_response("updating file", err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/drive/files/{file_id}/copy",
+    params(
+        ("file_id" = String, Path, description = "ID of the file or folder to copy")
+    ),
+    request_body = CopyFileRequest,
+    responses(
+        (status = 201, description = "File copied, or folder copied with everything in it", body = CopyResult),
+        (status = 207, description = "Folder copied, but some items in it were not; see failures", body = CopyResult),
+        (status = 400, description = "Authorization token missing or invalid, or empty name"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation, or Drive refused to copy the file"),
+        (status = 404, description = "File not found"),
+        (status = 500, description = "Internal server error while copying")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn copy_file<T: DriveService>(
+    file_id: web::Path<FileId>,
+    body: Option<web::Json<CopyFileRequest>>,
+    req: HttpRequest,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+) -> impl Responder {
+    let token_str = match access_token(&req) {
+        Some(token) => token,
+        None => return HttpResponse::BadRequest().body("Authorization token missing or invalid"),
+    };
+
+    let body = body.map(web::Json::into_inner).unwrap_or_default();
+    if body.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return HttpResponse::BadRequest().body("name must not be empty");
+    }
+
+    let metadata = match drive_service.get_download_metadata(&token_str, &file_id.file_id, &config).await {
+        Ok(metadata) => metadata,
+        Err(err) => return drive_error_response("copying file", err),
+    };
+
+    if metadata.mime_type.as_deref() == Some(FOLDER_MIME_TYPE) {
+        let name = body.name.or(metadata.name).unwrap_or_default();
+        let parent_id = body.parent_id.or_else(|| metadata.parents.first().cloned()).unwrap_or_else(|| "root".to_string());
+
+        match copy_folder(drive_service.get_ref(), &token_str, &file_id.file_id, &name, &parent_id, &config).await {
+            Ok(copy) if copy.failures.is_empty() => HttpResponse::Created().json(copy),
+            Ok(copy) => HttpResponse::MultiStatus().json(copy),
+            Err(err) => drive_error_response("copying folder", err),
+        }
+    } else {
+        let copy = CopyMetadata { name: body.name, parent_id: body.parent_id };
+        match drive_service.copy_file(&token_str, &file_id.file_id, &copy, &config).await {
+            Ok(file) => HttpResponse::Created().json(CopyResult {
+                id: file.id.unwrap_or_default(),
+                name: file.name,
+                mime_type: file.mime_type,
+                folders_copied: 0,
+                files_copied: 1,
+                failures: Vec::new(),
+            }),
+            Err(err) => drive_error_response("copying file", err),
+        }
+    }
+}
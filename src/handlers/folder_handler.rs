@@ -0,0 +1,114 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::collections::BTreeMap;
+use crate::{api::{drive_error::drive_error_response, google_drive::FolderMetadata}, config::Config, middlewares::auth_guard::access_token, services::{folder_path::{ensure_folder_path, parse_folder_path, PathSegment}, google_drive_service::DriveService}};
+use anyhow::Context;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateFolderRequest {
+    /// Name of the folder to create; give either `name` or `path`.
+    pub name: Option<String>,
+    /// Slash-separated folders to find or create, like `mkdir -p`, e.g. `Invoices/2026/Q3`.
+    pub path: Option<String>,
+    /// Folder to create in; defaults to the root folder.
+    pub parent_id: Option<String>,
+    /// Stored as the new folder's `appProperties`.
+    #[serde(default)]
+    pub app_properties: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreatedFolder {
+    pub id: String,
+    pub name: String,
+    pub parent_id: String,
+    /// False when `path` named a folder that already existed.
+    pub created: bool,
+    /// Every folder along `path`, from the outermost one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<PathSegment>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/drive/folders",
+    request_body = CreateFolderRequest,
+    responses(
+        (status = 200, description = "Every folder along `path` already existed", body = CreatedFolder),
+        (status = 201, description = "Folder created", body = CreatedFolder),
+        (status = 400, description = "Authorization token missing or invalid, or invalid name or path"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation, or Drive refused to create the folder"),
+        (status = 404, description = "Parent folder not found"),
+        (status = 500, description = "Internal server error while creating the folder")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn create_folder<T: DriveService>(
+    req: HttpRequest,
+    body: web::Json<CreateFolderRequest>,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+) -> impl Responder {
+    let token_str = match access_token(&req) {
+        Some(token) => token,
+        None => return HttpResponse::BadRequest().body("Authorization token missing or invalid"),
+    };
+    let parent_id = body.parent_id.clone().unwrap_or_else(|| "root".to_string());
+
+    match (&body.name, &body.path) {
+        (Some(name), None) => {
+            if name.trim().is_empty() {
+                return HttpResponse::BadRequest().body("Missing name");
+            }
+
+            let folder = FolderMetadata {
+                name: name.clone(),
+                parent_id: Some(parent_id.clone()),
+                app_properties: body.app_properties.clone(),
+            };
+            match drive_service.create_folder(&token_str, &folder, &config).await.context("Failed to create folder") {
+                Ok(created) => HttpResponse::Created().json(CreatedFolder {
+                    id: created.id.unwrap_or_default(),
+                    name: created.name.unwrap_or_else(|| name.clone()),
+                    parent_id,
+                    created: true,
+                    path: Vec::new(),
+                }),
+                Err(err) => drive_error_response("creating folder", err),
+            }
+        }
+        (None, Some(path)) => {
+            let segments = match parse_folder_path(path) {
+                Ok(segments) => segments,
+                Err(message) => return HttpResponse::BadRequest().body(message),
+            };
+
+            match ensure_folder_path(drive_service.get_ref(), &token_str, &parent_id, &segments, &body.app_properties, &config)
+                .await
+                .context("Failed to create folder path")
+            {
+                Ok(path) => {
+                    let leaf = &path[path.len() - 1];
+                    let folder = CreatedFolder {
+                        id: leaf.id.clone(),
+                        name: leaf.name.clone(),
+                        parent_id: path.len().checked_sub(2).map_or(parent_id, |index| path[index].id.clone()),
+                        created: leaf.created,
+                        path,
+                    };
+                    if folder.path.iter().any(|segment| segment.created) {
+                        HttpResponse::Created().json(folder)
+                    } else {
+                        HttpResponse::Ok().json(folder)
+                    }
+                }
+                Err(err) => drive_error_response("creating folder path", err),
+            }
+        }
+        _ => HttpResponse::BadRequest().body("Provide either name or path"),
+    }
+}
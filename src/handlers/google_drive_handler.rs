@@ -1,18 +1,51 @@
-use actix_multipart::Multipart;
-use actix_web::{web, HttpRequest, HttpResponse, Responder};
-use futures::StreamExt;
-use serde::Deserialize;
-use utoipa::ToSchema;
+use actix_multipart::{Field, Multipart};
+use actix_web::{http::header::{self, Charset, ContentDisposition, DispositionParam, DispositionType, ETag, EntityTag, ExtendedValue, Header, HeaderValue, HttpDate, IfModifiedSince, IfNoneMatch, IfRange, LastModified, CONTENT_LENGTH}, web, HttpRequest, HttpResponse, HttpResponseBuilder, Responder};
+use futures::future::{self, FutureExt, LocalBoxFuture};
+use futures::stream::{self, LocalBoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use std::collections::BTreeMap;
+use std::sync::Arc;
 use std::time::Instant;
-use crate::{config::Config, services::google_drive_service::{DriveService, FileInfo, FolderInfo}};
+use tokio::sync::{mpsc, Semaphore};
+use crate::{api::{drive_error::drive_error_response, file_metadata::FieldSelection, drive_query::{is_rfc3339, parse_rfc3339, DriveQuery}, export_formats::{export_file_name, export_formats, find_export_format, is_workspace_type, ExportFormat}, google_drive::{DownloadMetadata, UploadMetadata, MAX_PAGE_SIZE}}, config::Config, handlers::file_handler::FieldsQuery, middlewares::auth_guard::access_token, services::{batch_upload::{upload_file, PartError, UploadResult, UPLOAD_CHANNEL_CAPACITY, UPLOAD_CONCURRENCY}, checksum::{checksums_match, digest_header, digest_stream, hex_to_base64, is_hex_checksum}, byte_range::{closing_boundary, multipart_length, new_boundary, parse_range, part_header, ByteRange, RangeRequest}, folder_tree::{walk_tree, TreeNode, DEFAULT_MAX_DEPTH, MAX_DEPTH_LIMIT, TREE_CONCURRENCY}, google_drive_service::{DriveService, FilePage, FolderPage, PageRequest}, mime_sniff::{sniff_mime_type, SNIFF_LEN}, upload_validation::UploadValidation}};
 use anyhow::Context;
 
+/// Rejects page sizes the Drive API would refuse.
+fn invalid_page_size(page: &PageRequest) -> Option<HttpResponse> {
+    match page.page_size {
+        Some(size) if !(1..=MAX_PAGE_SIZE).contains(&size) => Some(
+            HttpResponse::BadRequest().body(format!("page_size must be between 1 and {}", MAX_PAGE_SIZE)),
+        ),
+        _ => None,
+    }
+}
+
+/// Answers a listing with the `fields` the caller selected instead of the
+/// default summary of each file.
+async fn list_metadata<T: DriveService>(
+    drive_service: &T,
+    token: &str,
+    query: &DriveQuery,
+    fields: &FieldSelection,
+    page: &PageRequest,
+    config: &Config,
+    action: &str,
+) -> HttpResponse {
+    match drive_service.list_file_metadata(token, query, fields, page, config).await {
+        Ok(files) => HttpResponse::Ok().json(files),
+        Err(err) => drive_error_response(action, err),
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/drive/list-folders",
+    params(PageRequest, FieldsQuery),
     responses(
-        (status = 200, description = "List of folders in the user's Google Drive", body = [FolderInfo]),
-        (status = 400, description = "Authorization token missing or invalid"),
+        (status = 200, description = "One page of folders in the user's Google Drive; a FileMetadataPage when `fields` is set", body = FolderPage),
+        (status = 400, description = "Authorization token missing or invalid, invalid page_size, or unknown field"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation, or Drive refused to list folders"),
         (status = 500, description = "Internal server error while listing folders.")
     ),
     security(
@@ -22,36 +55,67 @@ use anyhow::Context;
 )]
 pub async fn get_list_folders<T: DriveService>(
     req: HttpRequest,
+    page: web::Query<PageRequest>,
+    fields: web::Query<FieldsQuery>,
     config: web::Data<Config>,
     drive_service: web::Data<T>,
 ) -> impl Responder {
-    let token = match req.headers().get("Authorization") {
-        Some(token) => token.to_str().ok().map(|t| t.replace("Bearer ", "")),
-        None => None,
-    };
+    let token = access_token(&req);
 
     if let Some(token_str) = token {
-        match drive_service.list_folders(&token_str, &config).await.context("Failed to list folders") {
-            Ok(folders) => HttpResponse::Ok().json(folders),
-            Err(err) => {
-                eprintln!("Error listing folders: {:?}", err);
-                HttpResponse::InternalServerError().body(format!("Error listing folders: {:?}", err))
+        if let Some(response) = invalid_page_size(&page) {
+            return response;
+        }
+        match fields.selection() {
+            Ok(Some(fields)) => {
+                let query = DriveQuery::new().folders_only();
+                return list_metadata(drive_service.get_ref(), &token_str, &query, &fields, &page, &config, "listing folders").await;
             }
+            Ok(None) => {}
+            Err(message) => return HttpResponse::BadRequest().body(message),
+        }
+
+        match drive_service.list_folders(&token_str, &page, &config).await.context("Failed to list folders") {
+            Ok(folders) => HttpResponse::Ok().json(folders),
+            Err(err) => drive_error_response("listing folders", err),
         }
     } else {
         HttpResponse::BadRequest().body("Authorization token missing or invalid")
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ListFilesQuery {
+    folder_id: Option<String>,
+    page_size: Option<u32>,
+    page_token: Option<String>,
+    #[serde(default)]
+    all: bool,
+}
+
+impl ListFilesQuery {
+    fn page(&self) -> PageRequest {
+        PageRequest {
+            page_size: self.page_size,
+            page_token: self.page_token.clone(),
+            all: self.all,
+        }
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/drive/files",
     params(
-        ("folder_id" = String, Query, description = "ID of the folder from which to list files")
+        ("folder_id" = String, Query, description = "ID of the folder from which to list files"),
+        PageRequest,
+        FieldsQuery
     ),
     responses(
-        (status = 200, description = "List of files in the specified Google Drive folder", body = [FileInfo]),
-        (status = 400, description = "Authorization token missing or invalid, or folder ID missing"),
+        (status = 200, description = "One page of files in the specified Google Drive folder; a FileMetadataPage when `fields` is set", body = FilePage),
+        (status = 400, description = "Authorization token missing or invalid, folder ID missing, invalid page_size, or unknown field"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation, or Drive refused to list the folder"),
+        (status = 404, description = "Folder not found"),
         (status = 500, description = "Internal server error while listing files.")
     ),
     security(
@@ -61,44 +125,225 @@ pub async fn get_list_folders<T: DriveService>(
 )]
 pub async fn get_list_files_in_folder<T: DriveService>(
     req: HttpRequest,
+    query: web::Query<ListFilesQuery>,
+    fields: web::Query<FieldsQuery>,
     config: web::Data<Config>,
     drive_service: web::Data<T>,
 ) -> impl Responder {
-    let token = match req.headers().get("Authorization") {
-        Some(token) => token.to_str().ok().map(|t| t.replace("Bearer ", "")),
-        None => None,
-    };
+    let token = access_token(&req);
 
     if let Some(token_str) = token {
-        let query_params = req.query_string();
+        let folder_id = match query.folder_id.as_deref() {
+            Some(id) if !id.is_empty() => id,
+            _ => return HttpResponse::BadRequest().body("Missing folder_id in query parameters"),
+        };
 
-        let folder_id = query_params
-            .split('&')
-            .find_map(|param| {
-                let mut kv = param.split('=');
-                if let (Some(key), Some(value)) = (kv.next(), kv.next()) {
-                    if key == "folder_id" {
-                        return Some(value);
-                    }
-                }
-                None
-            });
+        let page = query.page();
+        if let Some(response) = invalid_page_size(&page) {
+            return response;
+        }
+        match fields.selection() {
+            Ok(Some(fields)) => {
+                let query = DriveQuery::new().in_parents(folder_id).trashed(false);
+                return list_metadata(drive_service.get_ref(), &token_str, &query, &fields, &page, &config, "listing files").await;
+            }
+            Ok(None) => {}
+            Err(message) => return HttpResponse::BadRequest().body(message),
+        }
+
+        match drive_service
+            .list_files_in_folder(&token_str, folder_id, &page, &config)
+            .await
+            .context("Failed to list files in folder")
+        {
+            Ok(files) => HttpResponse::Ok().json(files),
+            Err(err) => drive_error_response("listing files", err),
+        }
+    } else {
+        HttpResponse::BadRequest().body("Authorization token missing or invalid")
+    }
+}
+
+/// Structured search filters; each one is escaped and compiled into a Drive `q` string.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    /// Substring the file name must contain.
+    pub name: Option<String>,
+    /// Text to look for in the name, description and content.
+    pub text: Option<String>,
+    /// Exact MIME type, e.g. `application/pdf`.
+    pub mime_type: Option<String>,
+    /// Only files modified after this RFC 3339 timestamp.
+    pub modified_after: Option<String>,
+    /// Only files modified before this RFC 3339 timestamp.
+    pub modified_before: Option<String>,
+    /// Email address of an owner.
+    pub owner: Option<String>,
+    /// Search the trash instead of live files (defaults to `false`).
+    pub trashed: Option<bool>,
+    /// Only starred (`true`) or unstarred (`false`) files.
+    pub starred: Option<bool>,
+    pub page_size: Option<u32>,
+    pub page_token: Option<String>,
+    #[serde(default)]
+    pub all: bool,
+}
+
+impl SearchQuery {
+    /// Compiles the filters, rejecting timestamps Drive would not accept.
+    pub fn to_drive_query(&self) -> Result<DriveQuery, String> {
+        let mut query = DriveQuery::new();
+
+        if let Some(name) = &self.name {
+            query = query.name_contains(name);
+        }
+        if let Some(text) = &self.text {
+            query = query.full_text_contains(text);
+        }
+        if let Some(mime_type) = &self.mime_type {
+            query = query.mime_type(mime_type);
+        }
+        if let Some(timestamp) = &self.modified_after {
+            if !is_rfc3339(timestamp) {
+                return Err(format!("modified_after must be an RFC 3339 timestamp, got '{}'", timestamp));
+            }
+            query = query.modified_after(timestamp);
+        }
+        if let Some(timestamp) = &self.modified_before {
+            if !is_rfc3339(timestamp) {
+                return Err(format!("modified_before must be an RFC 3339 timestamp, got '{}'", timestamp));
+            }
+            query = query.modified_before(timestamp);
+        }
+        if let Some(owner) = &self.owner {
+            query = query.owner(owner);
+        }
+        query = query.trashed(self.trashed.unwrap_or(false));
+        if let Some(starred) = self.starred {
+            query = query.starred(starred);
+        }
 
-        let folder_id = match folder_id {
-            Some(id) => id,
-            None => return HttpResponse::BadRequest().body("Missing folder_id in query parameters"),
+        Ok(query)
+    }
+
+    fn page(&self) -> PageRequest {
+        PageRequest {
+            page_size: self.page_size,
+            page_token: self.page_token.clone(),
+            all: self.all,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/drive/search",
+    params(SearchQuery, FieldsQuery),
+    responses(
+        (status = 200, description = "One page of files matching every filter; a FileMetadataPage when `fields` is set", body = FilePage),
+        (status = 400, description = "Authorization token missing or invalid, malformed timestamp, invalid page_size, or unknown field"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation, or Drive refused the search"),
+        (status = 500, description = "Internal server error while searching files.")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn search_files<T: DriveService>(
+    req: HttpRequest,
+    query: web::Query<SearchQuery>,
+    fields: web::Query<FieldsQuery>,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+) -> impl Responder {
+    let token = access_token(&req);
+
+    if let Some(token_str) = token {
+        let drive_query = match query.to_drive_query() {
+            Ok(drive_query) => drive_query,
+            Err(message) => return HttpResponse::BadRequest().body(message),
         };
 
+        let page = query.page();
+        if let Some(response) = invalid_page_size(&page) {
+            return response;
+        }
+        match fields.selection() {
+            Ok(Some(fields)) => {
+                return list_metadata(drive_service.get_ref(), &token_str, &drive_query, &fields, &page, &config, "searching files").await;
+            }
+            Ok(None) => {}
+            Err(message) => return HttpResponse::BadRequest().body(message),
+        }
+
         match drive_service
-            .list_files_in_folder(&token_str, folder_id, &config)
+            .search_files(&token_str, &drive_query, &page, &config)
             .await
-            .context("Failed to list files in folder")
+            .context("Failed to search files")
         {
             Ok(files) => HttpResponse::Ok().json(files),
-            Err(err) => {
-                eprintln!("Error listing files: {:?}", err);
-                HttpResponse::InternalServerError().body(format!("Error listing files: {:?}", err))
+            Err(err) => drive_error_response("searching files", err),
+        }
+    } else {
+        HttpResponse::BadRequest().body("Authorization token missing or invalid")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FolderId {
+    folder_id: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TreeQuery {
+    /// Number of folder levels to list below the root (1-20, defaults to 5).
+    pub max_depth: Option<u32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/drive/folders/{folder_id}/tree",
+    params(
+        ("folder_id" = String, Path, description = "ID of the folder at the root of the tree"),
+        TreeQuery
+    ),
+    responses(
+        (status = 200, description = "Nested tree of folders and files; folders that failed to list carry an error", body = TreeNode),
+        (status = 400, description = "Authorization token missing or invalid, or invalid max_depth"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation"),
+        (status = 500, description = "The root folder could not be listed.")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn get_folder_tree<T: DriveService>(
+    folder_id: web::Path<FolderId>,
+    query: web::Query<TreeQuery>,
+    req: HttpRequest,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+) -> impl Responder {
+    let token = access_token(&req);
+
+    if let Some(token_str) = token {
+        let max_depth = query.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+        if !(1..=MAX_DEPTH_LIMIT).contains(&max_depth) {
+            return HttpResponse::BadRequest().body(format!("max_depth must be between 1 and {}", MAX_DEPTH_LIMIT));
+        }
+
+        let tree = walk_tree(drive_service.get_ref(), &token_str, &folder_id.folder_id, max_depth, TREE_CONCURRENCY, &config).await;
+
+        match &tree.error {
+            Some(err) => {
+                eprintln!("Error building folder tree: {}", err);
+                HttpResponse::InternalServerError().body(format!("Error building folder tree: {}", err))
             }
+            None => HttpResponse::Ok().json(tree),
         }
     } else {
         HttpResponse::BadRequest().body("Authorization token missing or invalid")
@@ -110,15 +355,174 @@ pub struct FileId {
     file_id: String,
 }
 
+/// `Content-Disposition` for a download, with an ASCII fallback name for
+/// clients that ignore the UTF-8 `filename*` parameter.
+fn attachment(file_name: &str) -> ContentDisposition {
+    let mut parameters = vec![DispositionParam::Filename(
+        file_name.chars().map(|c| if c.is_ascii() && !c.is_ascii_control() { c } else { '_' }).collect(),
+    )];
+    if !file_name.is_ascii() {
+        parameters.push(DispositionParam::FilenameExt(ExtendedValue {
+            charset: Charset::Ext("UTF-8".to_string()),
+            language_tag: None,
+            value: file_name.as_bytes().to_vec(),
+        }));
+    }
+    ContentDisposition { disposition: DispositionType::Attachment, parameters }
+}
+
+/// Validators of a download, derived from Drive's `md5Checksum` and `modifiedTime`.
+struct Validators {
+    etag: Option<EntityTag>,
+    last_modified: Option<HttpDate>,
+}
+
+impl Validators {
+    fn new(metadata: &DownloadMetadata) -> Self {
+        Validators {
+            etag: metadata.md5_checksum.clone().map(EntityTag::new_strong),
+            last_modified: metadata.modified_time.as_deref().and_then(parse_rfc3339).map(HttpDate::from),
+        }
+    }
+
+    fn apply(&self, response: &mut HttpResponseBuilder) {
+        if let Some(etag) = &self.etag {
+            response.insert_header(ETag(etag.clone()));
+        }
+        if let Some(last_modified) = self.last_modified {
+            response.insert_header(LastModified(last_modified));
+        }
+    }
+
+    /// `If-None-Match` takes precedence over `If-Modified-Since` (RFC 9110 §13.2.2).
+    fn is_not_modified(&self, req: &HttpRequest) -> bool {
+        if req.headers().contains_key(header::IF_NONE_MATCH) {
+            return match IfNoneMatch::parse(req) {
+                Ok(IfNoneMatch::Any) => true,
+                Ok(IfNoneMatch::Items(tags)) => {
+                    self.etag.as_ref().is_some_and(|etag| tags.iter().any(|tag| tag.weak_eq(etag)))
+                }
+                Err(_) => false,
+            };
+        }
+
+        match (IfModifiedSince::parse(req), self.last_modified) {
+            (Ok(IfModifiedSince(since)), Some(modified)) => modified <= since,
+            _ => false,
+        }
+    }
+
+    /// A `Range` only applies if the `If-Range` validator, when present, still matches.
+    fn range_applies(&self, req: &HttpRequest) -> bool {
+        if !req.headers().contains_key(header::IF_RANGE) {
+            return true;
+        }
+
+        match IfRange::parse(req) {
+            Ok(IfRange::EntityTag(tag)) => self.etag.as_ref().is_some_and(|etag| tag.strong_eq(etag)),
+            Ok(IfRange::Date(date)) => self.last_modified == Some(date),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Streams several ranges as one `multipart/byteranges` body, fetching each
+/// range from Drive only once the previous one has been sent.
+/// Returns the body's boundary and the body itself.
+fn multipart_byteranges<T: DriveService + 'static>(
+    drive_service: web::Data<T>,
+    config: web::Data<Config>,
+    token: String,
+    file_id: String,
+    ranges: Vec<ByteRange>,
+    content_type: String,
+    total: u64,
+) -> (String, LocalBoxStream<'static, anyhow::Result<web::Bytes>>) {
+    let boundary = new_boundary();
+    let closing = closing_boundary(&boundary);
+    let part_boundary = boundary.clone();
+
+    let body = stream::iter(ranges)
+        .then(move |range| {
+            let (drive_service, config, token, file_id) = (drive_service.clone(), config.clone(), token.clone(), file_id.clone());
+            let header_bytes = web::Bytes::from(part_header(&part_boundary, &content_type, &range, total));
+            async move {
+                let head = stream::once(future::ready(Ok(header_bytes)));
+                let tail = stream::once(future::ready(Ok(web::Bytes::from_static(b"\r\n"))));
+                match drive_service.download_file(&token, &file_id, Some((range.start, range.end)), &config).await {
+                    Ok(download) => head.chain(download.body).chain(tail).boxed_local(),
+                    Err(err) => stream::once(future::ready(Err(err))).boxed_local(),
+                }
+            }
+        })
+        .flatten()
+        .chain(stream::once(future::ready(Ok(web::Bytes::from(closing)))))
+        .boxed_local();
+
+    (boundary, body)
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DownloadQuery {
+    /// Format to export a Google Workspace document (Docs, Sheets, Slides,
+    /// Drawings) to, e.g. `pdf`, `docx`, `xlsx` or `csv`; defaults to `pdf`.
+    /// Not allowed for other files.
+    pub format: Option<String>,
+}
+
+/// Streams a Google Workspace document exported to `format`. Exports have
+/// no checksum or known length, so they are sent whole and chunked.
+async fn export_document<T: DriveService>(
+    drive_service: &T,
+    token: &str,
+    file_id: &str,
+    metadata: &DownloadMetadata,
+    format: ExportFormat,
+    validators: &Validators,
+    config: &Config,
+) -> HttpResponse {
+    match drive_service
+        .export_file(token, file_id, format.mime_type, config)
+        .await
+        .context("Failed to export file")
+    {
+        Ok(export) => {
+            let mut response = HttpResponse::Ok();
+            response.content_type(format.mime_type);
+            if let Some(content_length) = export.content_length {
+                response.no_chunking(content_length);
+            }
+            response.insert_header((header::ACCEPT_RANGES, "none"));
+            validators.apply(&mut response);
+            if let Some(file_name) = &metadata.name {
+                response.insert_header(attachment(&export_file_name(file_name, &format)));
+            }
+            response.streaming(export.body)
+        }
+        Err(err) => drive_error_response("exporting file", err),
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/drive/files/{file_id}",
     params(
-        ("file_id" = String, Path, description = "ID of the pdf file to be downloaded")
+        ("file_id" = String, Path, description = "ID of the file to be downloaded"),
+        DownloadQuery,
+        ("Range" = Option<String>, Header, description = "Byte ranges to download, e.g. `bytes=0-1023` or `bytes=0-99,-100`"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a cached copy; answered with 304 if it still matches"),
+        ("If-Modified-Since" = Option<String>, Header, description = "Date of a cached copy; answered with 304 if the file has not changed since"),
+        ("If-Range" = Option<String>, Header, description = "ETag or date the Range applies to; the whole file is sent if it no longer matches")
     ),
     responses(
-        (status = 200, description = "File successfully downloaded", content_type = "application/pdf"),
-        (status = 400, description = "Authorization token missing or invalid"),
+        (status = 200, description = "File streamed with its Content-Type, Content-Length, ETag, Last-Modified, Content-MD5, Digest and Content-Disposition; Google Workspace documents are exported to the requested format", content_type = "application/octet-stream"),
+        (status = 206, description = "The requested range, or a multipart/byteranges body for several ranges"),
+        (status = 304, description = "The cached copy is still current"),
+        (status = 400, description = "Authorization token missing or invalid, or format not supported for this file"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation, or Drive refused access to the file"),
+        (status = 404, description = "File not found"),
+        (status = 416, description = "No requested range overlaps the file"),
         (status = 500, description = "Error downloading the file")
     ),
     security(
@@ -126,30 +530,253 @@ pub struct FileId {
     ),
     tag = "drive"
 )]
-pub async fn download_pdf_file_by_id<T: DriveService>(
+pub async fn download_pdf_file_by_id<T: DriveService + 'static>(
     file_id: web::Path<FileId>,
+    query: web::Query<DownloadQuery>,
     req: HttpRequest,
     config: web::Data<Config>,
     drive_service: web::Data<T>,
 ) -> impl Responder {
-    let token = match req.headers().get("Authorization") {
-        Some(token) => token.to_str().ok().map(|t| t.replace("Bearer ", "")),
-        None => None,
-    };
+    let token = access_token(&req);
 
     if let Some(token_str) = token {
         let file_id_str = &file_id.file_id;
 
+        let metadata = match drive_service
+            .get_download_metadata(&token_str, file_id_str, &config)
+            .await
+            .context("Failed to download file")
+        {
+            Ok(metadata) => metadata,
+            Err(err) => return drive_error_response("downloading file", err),
+        };
+
+        let source_type = metadata.mime_type.as_deref().unwrap_or_default();
+        let export_format = if is_workspace_type(source_type) {
+            match find_export_format(source_type, query.format.as_deref()) {
+                Some(format) => Some(format),
+                None => {
+                    let supported: Vec<&str> = export_formats(source_type).iter().map(|format| format.name).collect();
+                    return if supported.is_empty() {
+                        HttpResponse::BadRequest().body(format!("Files of type {} cannot be downloaded or exported", source_type))
+                    } else {
+                        HttpResponse::BadRequest().body(format!(
+                            "Unsupported format for {}; expected one of: {}",
+                            source_type,
+                            supported.join(", ")
+                        ))
+                    };
+                }
+            }
+        } else if query.format.is_some() {
+            return HttpResponse::BadRequest().body("format is only supported for Google Workspace documents");
+        } else {
+            None
+        };
+
+        let validators = Validators::new(&metadata);
+        if validators.is_not_modified(&req) {
+            let mut response = HttpResponse::NotModified();
+            validators.apply(&mut response);
+            return response.finish();
+        }
+
+        if let Some(format) = export_format {
+            return export_document(drive_service.get_ref(), &token_str, file_id_str, &metadata, format, &validators, &config).await;
+        }
+
+        let total = metadata.content_length();
+        let range_request = match (req.headers().get(header::RANGE).and_then(|value| value.to_str().ok()), total) {
+            (Some(range), Some(total)) if validators.range_applies(&req) => parse_range(range, total),
+            _ => RangeRequest::Full,
+        };
+        let content_type = metadata.mime_type.clone().unwrap_or_else(|| "application/octet-stream".to_string());
+
+        let range = match range_request {
+            RangeRequest::Unsatisfiable => {
+                let mut response = HttpResponse::RangeNotSatisfiable();
+                validators.apply(&mut response);
+                response.insert_header((header::CONTENT_RANGE, format!("bytes */{}", total.unwrap_or_default())));
+                return response.finish();
+            }
+            RangeRequest::Partial(ranges) if ranges.len() > 1 => {
+                let total = total.unwrap_or_default();
+                let (boundary, body) = multipart_byteranges(
+                    drive_service.clone(),
+                    config.clone(),
+                    token_str,
+                    file_id_str.clone(),
+                    ranges.clone(),
+                    content_type.clone(),
+                    total,
+                );
+                let mut response = HttpResponse::PartialContent();
+                response.content_type(format!("multipart/byteranges; boundary={}", boundary));
+                response.no_chunking(multipart_length(&boundary, &content_type, &ranges, total));
+                validators.apply(&mut response);
+                return response.streaming(body);
+            }
+            RangeRequest::Partial(ranges) => Some(ranges[0]),
+            RangeRequest::Full => None,
+        };
+
         match drive_service
-            .download_pdf(&token_str, file_id_str, &config)
+            .download_file(&token_str, file_id_str, range.map(|range| (range.start, range.end)), &config)
             .await
-            .context("Failed to download PDF file")
+            .context("Failed to download file")
         {
-            Ok(file) => HttpResponse::Ok().body(file),
-            Err(err) => {
-                eprintln!("Error downloading file: {:?}", err);
-                HttpResponse::InternalServerError().body(format!("Error downloading file: {:?}", err))
+            Ok(download) => {
+                let mut response = match range {
+                    Some(range) => {
+                        let mut response = HttpResponse::PartialContent();
+                        response.insert_header((header::CONTENT_RANGE, range.content_range(total.unwrap_or_default())));
+                        response
+                    }
+                    None => HttpResponse::Ok(),
+                };
+                response.content_type(download.content_type.unwrap_or(content_type));
+                if let Some(content_length) = range.map(|range| range.size()).or(download.content_length) {
+                    response.no_chunking(content_length);
+                }
+                response.insert_header((header::ACCEPT_RANGES, "bytes"));
+                validators.apply(&mut response);
+                // `Content-MD5` covers the body; `Digest` covers the whole file, even for a range.
+                if range.is_none() {
+                    if let Some(md5) = metadata.md5_checksum.as_deref().and_then(hex_to_base64) {
+                        response.insert_header(("Content-MD5", md5));
+                    }
+                }
+                if let Some(digest) = digest_header(metadata.md5_checksum.as_deref(), metadata.sha256_checksum.as_deref()) {
+                    response.insert_header(("Digest", digest));
+                }
+                if validators.etag.is_none() {
+                    if let Some(etag) = &download.etag {
+                        response.insert_header((header::ETAG, etag.as_str()));
+                    }
+                }
+                if let Some(file_name) = &metadata.name {
+                    response.insert_header(attachment(file_name));
+                }
+                response.streaming(download.body)
             }
+            Err(err) => drive_error_response("downloading file", err),
+        }
+    } else {
+        HttpResponse::BadRequest().body("Authorization token missing or invalid")
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VerifyQuery {
+    /// MD5 the caller expects, in hex; checked in addition to Drive's.
+    pub md5: Option<String>,
+    /// SHA-256 the caller expects, in hex; checked in addition to Drive's.
+    pub sha256: Option<String>,
+}
+
+/// Checksums of a file as re-downloaded, compared with the expected ones.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifyResult {
+    pub file_id: String,
+    pub verified: bool,
+    pub size: u64,
+    pub md5_checksum: String,
+    pub sha256_checksum: String,
+    /// One entry per checksum or size that did not match.
+    pub mismatches: Vec<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/drive/files/{file_id}/verify",
+    params(
+        ("file_id" = String, Path, description = "ID of the file to verify"),
+        VerifyQuery
+    ),
+    responses(
+        (status = 200, description = "The content matches Drive's checksums and the expected ones", body = VerifyResult),
+        (status = 400, description = "Authorization token missing or invalid, invalid checksum, or the file has no stored content"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation, or Drive refused access to the file"),
+        (status = 404, description = "File not found"),
+        (status = 409, description = "The content does not match; see mismatches", body = VerifyResult),
+        (status = 500, description = "Error downloading the file")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn verify_file<T: DriveService>(
+    file_id: web::Path<FileId>,
+    query: web::Query<VerifyQuery>,
+    req: HttpRequest,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+) -> impl Responder {
+    let token = access_token(&req);
+
+    if let Some(token_str) = token {
+        let file_id_str = &file_id.file_id;
+        if query.md5.as_deref().is_some_and(|md5| !is_hex_checksum(md5, 16)) {
+            return HttpResponse::BadRequest().body("md5 must be 32 hexadecimal characters");
+        }
+        if query.sha256.as_deref().is_some_and(|sha256| !is_hex_checksum(sha256, 32)) {
+            return HttpResponse::BadRequest().body("sha256 must be 64 hexadecimal characters");
+        }
+
+        let metadata = match drive_service
+            .get_download_metadata(&token_str, file_id_str, &config)
+            .await
+            .context("Failed to verify file")
+        {
+            Ok(metadata) => metadata,
+            Err(err) => return drive_error_response("verifying file", err),
+        };
+        if metadata.mime_type.as_deref().is_some_and(is_workspace_type) {
+            return HttpResponse::BadRequest().body("Google Workspace documents have no stored content to verify");
+        }
+
+        let digest = match drive_service.download_file(&token_str, file_id_str, None, &config).await {
+            Ok(download) => digest_stream(download.body).await,
+            Err(err) => Err(err),
+        };
+        let digest = match digest.context("Failed to verify file") {
+            Ok(digest) => digest,
+            Err(err) => return drive_error_response("verifying file", err),
+        };
+
+        let (md5, sha256) = (digest.md5_hex(), digest.sha256_hex());
+        let expected = [
+            ("Drive MD5", metadata.md5_checksum.as_deref(), &md5),
+            ("Drive SHA-256", metadata.sha256_checksum.as_deref(), &sha256),
+            ("expected MD5", query.md5.as_deref(), &md5),
+            ("expected SHA-256", query.sha256.as_deref(), &sha256),
+        ];
+        let mut mismatches: Vec<String> = expected
+            .iter()
+            .filter_map(|(label, expected, actual)| match expected {
+                Some(expected) if !checksums_match(expected, actual) => Some(format!("{} is {}, content has {}", label, expected, actual)),
+                _ => None,
+            })
+            .collect();
+        if let Some(size) = metadata.content_length().filter(|size| *size != digest.size) {
+            mismatches.push(format!("Drive size is {}, content has {} bytes", size, digest.size));
+        }
+
+        let result = VerifyResult {
+            file_id: file_id_str.clone(),
+            verified: mismatches.is_empty(),
+            size: digest.size,
+            md5_checksum: md5,
+            sha256_checksum: sha256,
+            mismatches,
+        };
+        if result.verified {
+            HttpResponse::Ok().json(result)
+        } else {
+            eprintln!("Checksum mismatch verifying file {}: {:?}", file_id_str, result.mismatches);
+            HttpResponse::Conflict().json(result)
         }
     } else {
         HttpResponse::BadRequest().body("Authorization token missing or invalid")
@@ -158,20 +785,70 @@ pub async fn download_pdf_file_by_id<T: DriveService>(
 
 #[derive(ToSchema)]
 pub struct FileUploadBody {
+    /// Name of the Drive file; defaults to the file part's filename.
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// Target folder; overrides the `folder_id` query parameter.
+    pub folder_id: Option<String>,
+    /// Overrides the declared or sniffed MIME type of the file part.
+    pub mime_type: Option<String>,
+    /// JSON object of string values, stored as the file's `appProperties`.
+    pub app_properties: Option<String>,
+    /// The file itself; must come after the other fields.
     pub _file: String,
 }
 
+/// Largest text field accepted alongside the file part.
+const MAX_FORM_FIELD_LEN: usize = 64 * 1024;
+
+async fn read_text_field(field: &mut Field) -> Result<String, HttpResponse> {
+    let mut value = Vec::new();
+    while let Some(chunk) = field.next().await {
+        let data = chunk.map_err(|_| HttpResponse::BadRequest().body("Error reading form field"))?;
+        if value.len() + data.len() > MAX_FORM_FIELD_LEN {
+            return Err(HttpResponse::PayloadTooLarge().body(format!("Form field '{}' is too large", field.name())));
+        }
+        value.extend_from_slice(&data);
+    }
+    String::from_utf8(value).map_err(|_| HttpResponse::BadRequest().body(format!("Form field '{}' is not valid UTF-8", field.name())))
+}
+
+/// Applies one text field of the upload form to the metadata.
+fn apply_form_field(metadata: &mut UploadMetadata, name: &str, value: String) -> Result<(), &'static str> {
+    match name {
+        "name" => metadata.name = value,
+        "description" => metadata.description = Some(value),
+        "folder_id" => metadata.folder_id = Some(value),
+        "mime_type" => {
+            if !value.contains('/') || HeaderValue::from_str(&value).is_err() {
+                return Err("Invalid mime_type");
+            }
+            metadata.mime_type = Some(value);
+        }
+        "app_properties" => {
+            metadata.app_properties = serde_json::from_str::<BTreeMap<String, String>>(&value)
+                .map_err(|_| "app_properties must be a JSON object of string values")?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
 #[utoipa::path(
     post,
     path = "/drive/files",
-    request_body(content = FileUploadBody, description = "PDF file(multipart/form-data) to be uploaded"),
+    request_body(content = FileUploadBody, content_type = "multipart/form-data", description = "One or more files to be uploaded, each preceded by its optional metadata fields"),
     params(
-        ("folder_id" = Option<String>, Query, description = "ID of the folder where the PDF will be uploaded (defaults to root folder if not provided)")
+        ("folder_id" = Option<String>, Query, description = "ID of the folder where the files will be uploaded (defaults to root folder if not provided)")
     ),
     responses(
-        (status = 200, description = "File uploaded successfully", body = String),
-        (status = 400, description = "Authorization token missing or invalid"),
-        (status = 500, description = "Internal server error while uploading file")
+        (status = 200, description = "Every file uploaded successfully", body = [UploadResult]),
+        (status = 207, description = "Some files failed to upload; see each entry's error", body = [UploadResult]),
+        (status = 400, description = "Authorization token missing or invalid, missing file part, invalid form field, or files rejected for different reasons"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation"),
+        (status = 413, description = "A form field exceeds its size limit, or every file exceeds the upload size limit", body = [UploadResult]),
+        (status = 415, description = "Every file is of a type that is not allowed, or its content does not match its type", body = [UploadResult]),
+        (status = 500, description = "No file could be uploaded", body = [UploadResult])
     ),
     security(
         ("bearerAuth" = [])
@@ -182,88 +859,199 @@ pub async fn upload_pdf_file<T: DriveService + Send + Sync + 'static>(
     req: HttpRequest,
     mut payload: Multipart,
     config: web::Data<Config>,
+    validation: web::Data<UploadValidation>,
     drive_service: web::Data<T>,
 ) -> impl Responder {
-    let token = match req.headers().get("Authorization") {
-        Some(token) => token.to_str().ok().map(|t| t.replace("Bearer ", "")),
-        None => None,
-    };
+    let token = access_token(&req);
 
     if let Some(token_str) = token {
-        let mut file_name = String::new();
-        let mut last_file_id = String::new();
-
-        let folder_id = req.query_string()
+        let mut metadata = UploadMetadata::new("");
+        metadata.folder_id = req.query_string()
             .split('&')
             .find_map(|param| {
                 let mut kv = param.split('=');
                 if let (Some(key), Some(value)) = (kv.next(), kv.next()) {
                     if key == "folder_id" {
-                        return Some(value);
+                        return Some(value.to_string());
                     }
                 }
                 None
-            })
-            .unwrap_or("root");
+            });
 
-        let resumable_url = match drive_service.get_ref()
-            .initialize_resumable_upload(&token_str, folder_id, &file_name, &config)
-            .await
-            .context("Failed to initialize resumable upload")
-        {
-            Ok(url) => url,
-            Err(e) => {
-                eprintln!("Failed to initialize upload: {:?}", e);
-                return HttpResponse::InternalServerError().body(format!("Failed to initialize upload: {:?}", e));
-            },
-        };
+        let start_time = Instant::now();
+        let semaphore = Arc::new(Semaphore::new(UPLOAD_CONCURRENCY));
+        let mut uploads: Vec<LocalBoxFuture<'static, UploadResult>> = Vec::new();
 
-        while let Some(Ok(mut field)) = payload.next().await {
-            let content_disposition = field.content_disposition();
+        // Each file part gets its own session, created once its headers are
+        // known; the fields before it describe that file.
+        while let Some(item) = payload.next().await {
+            let mut field = match item {
+                Ok(field) => field,
+                Err(err) => {
+                    eprintln!("Malformed multipart body: {:?}", err);
+                    return HttpResponse::BadRequest().body("Malformed multipart body");
+                }
+            };
 
-            if let Some(name) = content_disposition.get_filename() {
-                file_name = name.to_string();
-                println!("Uploading file: {}", file_name);
+            if field.content_disposition().get_filename().is_none() {
+                let name = field.name().to_string();
+                let value = match read_text_field(&mut field).await {
+                    Ok(value) => value,
+                    Err(response) => return response,
+                };
+                if let Err(message) = apply_form_field(&mut metadata, &name, value) {
+                    return HttpResponse::BadRequest().body(message);
+                }
+                continue;
             }
 
-            while let Some(chunk) = field.next().await {
-                match chunk {
-                    Ok(data) => {
-                        let chunk_size = data.len();
-                        let start_time = Instant::now();
-
-                        println!("Uploading chunk of size: {} bytes", chunk_size);
-
-                        match drive_service.upload_pdf(&token_str, &resumable_url, data.to_vec(), None).await.context("Failed to upload file chunk") {
-                            Ok(file_id) => {
-                                let duration = start_time.elapsed();
-                                println!(
-                                    "Chunk of size {} bytes uploaded successfully in {:?} seconds",
-                                    chunk_size,
-                                    duration.as_secs_f64()
-                                );
-                                last_file_id = file_id;
-                            },
-                            Err(err) => {
-                                eprintln!("Error uploading file chunk: {:?}", err);
-                                return HttpResponse::InternalServerError().body("Error uploading file chunk");
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        eprintln!("Error reading file content");
-                        return HttpResponse::InternalServerError().body("Error reading file content");
+            // `name` and `mime_type` describe a single file; the other fields carry over.
+            let mut file_metadata = UploadMetadata {
+                name: std::mem::take(&mut metadata.name),
+                mime_type: metadata.mime_type.take(),
+                ..metadata.clone()
+            };
+            if file_metadata.name.is_empty() {
+                file_metadata.name = field.content_disposition().get_filename().unwrap_or_default().to_string();
+            }
+            file_metadata.size = field.headers()
+                .get(CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok());
+
+            let declared_type = field.content_type().essence_str().to_string();
+            if file_metadata.mime_type.is_none() && declared_type != "application/octet-stream" {
+                file_metadata.mime_type = Some(declared_type);
+            }
+
+            // A `Field` must not be polled again once it has ended.
+            let mut field = field.fuse();
+            let mut head = Vec::new();
+            let mut read_failed = false;
+            while head.len() < SNIFF_LEN {
+                match field.next().await {
+                    Some(Ok(data)) => head.extend_from_slice(&data),
+                    Some(Err(_)) => {
+                        read_failed = true;
+                        break;
                     }
+                    None => break,
                 }
             }
+            if file_metadata.mime_type.is_none() {
+                file_metadata.mime_type = sniff_mime_type(&head, &file_metadata.name).map(str::to_string);
+            }
+
+            if read_failed {
+                eprintln!("Error reading file content of '{}'", file_metadata.name);
+                uploads.push(future::ready(UploadResult {
+                    file_name: file_metadata.name,
+                    mime_type: file_metadata.mime_type,
+                    file_id: None,
+                    md5_checksum: None,
+                    sha256_checksum: None,
+                    error: Some("Error reading file content".to_string()),
+                    rejection: None,
+                }).boxed_local());
+                break;
+            }
+
+            // The type and the leading bytes are checked before a session is
+            // created, so a rejected file never reaches Drive.
+            let checked = validation
+                .check(file_metadata.mime_type.as_deref(), file_metadata.size)
+                .and_then(|mut check| check.update(&head).map(|_| check));
+            let mut check = match checked {
+                Ok(check) => check,
+                Err(rejection) => {
+                    eprintln!("Rejected upload of '{}': {}", file_metadata.name, rejection);
+                    uploads.push(future::ready(UploadResult::rejected(&file_metadata, rejection)).boxed_local());
+                    continue;
+                }
+            };
+
+            println!("Uploading file: {}", file_metadata.name);
+
+            let permit = semaphore.clone().acquire_owned().await.expect("Upload semaphore is never closed");
+            let (sender, receiver) = mpsc::channel(UPLOAD_CHANNEL_CAPACITY);
+            let (task_service, task_config, task_token) = (drive_service.clone(), config.clone(), token_str.clone());
+            let file_name = file_metadata.name.clone();
+            let task = actix_web::rt::spawn(async move {
+                let result = upload_file(task_service.get_ref(), &task_token, &file_metadata, head, receiver, &task_config).await;
+                drop(permit);
+                result
+            });
+            uploads.push(task.map(move |joined| {
+                joined.unwrap_or_else(|err| UploadResult {
+                    file_name,
+                    mime_type: None,
+                    file_id: None,
+                    md5_checksum: None,
+                    sha256_checksum: None,
+                    error: Some(format!("Upload task failed: {}", err)),
+                    rejection: None,
+                })
+            }).boxed_local());
+
+            // Keep draining the part even if its upload already failed, so the next part can be read.
+            // The rest of the content is still checked as it passes; the upload is only completed
+            // once the whole file has been validated, and is cancelled otherwise.
+            let mut rejected = false;
+            while let Some(chunk) = field.next().await {
+                let chunk = match chunk {
+                    Ok(data) => match check.update(&data) {
+                        Ok(()) => Ok(data),
+                        Err(rejection) => Err(PartError::Rejected(rejection)),
+                    },
+                    Err(_) => Err(PartError::Read("Error reading file content".to_string())),
+                };
+                read_failed = matches!(chunk, Err(PartError::Read(_)));
+                if !rejected {
+                    rejected = chunk.is_err();
+                    let _ = sender.send(chunk).await;
+                }
+                if read_failed {
+                    break;
+                }
+            }
+            if !rejected && !read_failed {
+                if let Err(rejection) = check.finish() {
+                    let _ = sender.send(Err(PartError::Rejected(rejection))).await;
+                }
+            }
+            if read_failed {
+                eprintln!("Error reading file content; ignoring the rest of the request");
+                break;
+            }
+        }
+
+        if uploads.is_empty() {
+            return HttpResponse::BadRequest().body("Missing file part");
         }
 
-        println!("File '{}' uploaded successfully with ID '{}'", file_name, last_file_id);
+        let results = future::join_all(uploads).await;
+        let succeeded = results.iter().filter(|result| result.is_success()).count();
+
+        println!(
+            "{} of {} files uploaded successfully in {:?} seconds",
+            succeeded,
+            results.len(),
+            start_time.elapsed().as_secs_f64()
+        );
 
-        HttpResponse::Ok().json(serde_json::json!({
-            "file_name": file_name,
-            "file_id": last_file_id
-        }))
+        if succeeded == results.len() {
+            HttpResponse::Ok().json(results)
+        } else if succeeded > 0 {
+            HttpResponse::MultiStatus().json(results)
+        } else {
+            // When every file was refused by validation, the response says why.
+            let statuses: Vec<_> = results.iter().map(|result| result.rejection.as_ref().map(|rejection| rejection.status_code())).collect();
+            match statuses[0] {
+                Some(status) if statuses.iter().all(|other| *other == Some(status)) => HttpResponse::build(status).json(results),
+                _ if statuses.iter().all(Option::is_some) => HttpResponse::BadRequest().json(results),
+                _ => HttpResponse::InternalServerError().json(results),
+            }
+        }
     } else {
         eprintln!("Authorization token missing or invalid");
         HttpResponse::BadRequest().body("Authorization token missing or invalid")
@@ -0,0 +1,20 @@
+use actix_web::{web, HttpResponse, Responder};
+use crate::middlewares::token_cache::{TokenCache, TokenCacheStats};
+
+#[utoipa::path(
+    get,
+    path = "/metrics/token-cache",
+    responses(
+        (status = 200, description = "Hit/miss counters and occupancy of the token validation cache", body = TokenCacheStats),
+        (status = 401, description = "No valid session, bearer token or service API key; the counters reveal authentication traffic")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "metrics"
+)]
+pub async fn get_token_cache_stats(
+    token_cache: web::Data<TokenCache>
+) -> impl Responder {
+    HttpResponse::Ok().json(token_cache.stats())
+}
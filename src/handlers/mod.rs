@@ -1,2 +1,8 @@
+pub mod file_handler;
+pub mod folder_handler;
 pub mod google_drive_handler;
-pub mod auth_handler;
\ No newline at end of file
+pub mod auth_handler;
+pub mod metrics_handler;
+pub mod permission_handler;
+pub mod trash_handler;
+pub mod upload_handler;
\ No newline at end of file
@@ -0,0 +1,221 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use crate::{api::{drive_error::drive_error_response, permissions::{NewPermission, Permission, PermissionList, PermissionOptions, PermissionRole, PermissionType, PermissionUpdate}}, config::Config, middlewares::auth_guard::access_token, services::google_drive_service::DriveService};
+
+#[derive(Debug, Deserialize)]
+pub struct FileId {
+    file_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PermissionPath {
+    file_id: String,
+    permission_id: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreatePermissionRequest {
+    #[serde(rename = "type")]
+    pub permission_type: PermissionType,
+    pub role: PermissionRole,
+    /// Required for `user` and `group`.
+    pub email_address: Option<String>,
+    /// Required for `domain`.
+    pub domain: Option<String>,
+    /// For `domain` and `anyone`: whether the file shows up in searches.
+    pub allow_file_discovery: Option<bool>,
+    /// RFC 3339 time at which the access is revoked.
+    pub expiration_time: Option<String>,
+    /// Email users and groups about the share; Drive does by default.
+    pub send_notification_email: Option<bool>,
+    /// Text to include in the notification email.
+    pub email_message: Option<String>,
+    /// Must be set to make a user the owner.
+    #[serde(default)]
+    pub transfer_ownership: bool,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdatePermissionRequest {
+    pub role: PermissionRole,
+    /// RFC 3339 time at which the access is revoked.
+    pub expiration_time: Option<String>,
+    /// Must be set when `role` is `owner`.
+    #[serde(default)]
+    pub transfer_ownership: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/drive/files/{file_id}/permissions",
+    params(
+        ("file_id" = String, Path, description = "ID of the file or folder")
+    ),
+    responses(
+        (status = 200, description = "Everyone the file is shared with", body = PermissionList),
+        (status = 400, description = "Authorization token missing or invalid"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation, or Drive refused to show the file's permissions"),
+        (status = 404, description = "File not found"),
+        (status = 500, description = "Internal server error while listing permissions")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn list_permissions<T: DriveService>(
+    file_id: web::Path<FileId>,
+    req: HttpRequest,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+) -> impl Responder {
+    let token_str = match access_token(&req) {
+        Some(token) => token,
+        None => return HttpResponse::BadRequest().body("Authorization token missing or invalid"),
+    };
+
+    match drive_service.list_permissions(&token_str, &file_id.file_id, &config).await {
+        Ok(permissions) => HttpResponse::Ok().json(PermissionList { permissions }),
+        Err(err) => drive_error_response("listing permissions", err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/drive/files/{file_id}/permissions",
+    params(
+        ("file_id" = String, Path, description = "ID of the file or folder to share")
+    ),
+    request_body = CreatePermissionRequest,
+    responses(
+        (status = 201, description = "File shared", body = Permission),
+        (status = 400, description = "Authorization token missing or invalid, or an invalid combination of type, role and options"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation, or Drive refused to share the file"),
+        (status = 404, description = "File not found"),
+        (status = 500, description = "Internal server error while sharing the file")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn create_permission<T: DriveService>(
+    file_id: web::Path<FileId>,
+    body: web::Json<CreatePermissionRequest>,
+    req: HttpRequest,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+) -> impl Responder {
+    let token_str = match access_token(&req) {
+        Some(token) => token,
+        None => return HttpResponse::BadRequest().body("Authorization token missing or invalid"),
+    };
+
+    let body = body.into_inner();
+    let permission = NewPermission {
+        permission_type: body.permission_type,
+        role: body.role,
+        email_address: body.email_address,
+        domain: body.domain,
+        allow_file_discovery: body.allow_file_discovery,
+        expiration_time: body.expiration_time,
+    };
+    let options = PermissionOptions {
+        send_notification_email: body.send_notification_email,
+        email_message: body.email_message,
+        transfer_ownership: body.transfer_ownership,
+    };
+    if let Err(message) = permission.validate(&options) {
+        return HttpResponse::BadRequest().body(message);
+    }
+
+    match drive_service.create_permission(&token_str, &file_id.file_id, &permission, &options, &config).await {
+        Ok(permission) => HttpResponse::Created().json(permission),
+        Err(err) => drive_error_response("sharing file", err),
+    }
+}
+
+#[utoipa::path(
+    patch,
+    path = "/drive/files/{file_id}/permissions/{permission_id}",
+    params(
+        ("file_id" = String, Path, description = "ID of the file or folder"),
+        ("permission_id" = String, Path, description = "ID of the permission to change")
+    ),
+    request_body = UpdatePermissionRequest,
+    responses(
+        (status = 200, description = "Permission changed", body = Permission),
+        (status = 400, description = "Authorization token missing or invalid, or owner role without transfer_ownership"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation, or Drive refused to change the permission"),
+        (status = 404, description = "File or permission not found"),
+        (status = 500, description = "Internal server error while changing the permission")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn update_permission<T: DriveService>(
+    path: web::Path<PermissionPath>,
+    body: web::Json<UpdatePermissionRequest>,
+    req: HttpRequest,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+) -> impl Responder {
+    let token_str = match access_token(&req) {
+        Some(token) => token,
+        None => return HttpResponse::BadRequest().body("Authorization token missing or invalid"),
+    };
+
+    let body = body.into_inner();
+    let update = PermissionUpdate {
+        role: body.role,
+        expiration_time: body.expiration_time,
+        transfer_ownership: body.transfer_ownership,
+    };
+    if let Err(message) = update.validate() {
+        return HttpResponse::BadRequest().body(message);
+    }
+
+    match drive_service.update_permission(&token_str, &path.file_id, &path.permission_id, &update, &config).await {
+        Ok(permission) => HttpResponse::Ok().json(permission),
+        Err(err) => drive_error_response("updating permission", err),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/drive/files/{file_id}/permissions/{permission_id}",
+    params(
+        ("file_id" = String, Path, description = "ID of the file or folder"),
+        ("permission_id" = String, Path, description = "ID of the permission to remove")
+    ),
+    responses(
+        (status = 204, description = "Permission removed"),
+        (status = 400, description = "Authorization token missing or invalid"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation, or Drive refused to remove the permission"),
+        (status = 404, description = "File or permission not found"),
+        (status = 500, description = "Internal server error while removing the permission")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn delete_permission<T: DriveService>(
+    path: web::Path<PermissionPath>,
+    req: HttpRequest,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+) -> impl Responder {
+    let token_str = match access_token(&req) {
+        Some(token) => token,
+        None => return HttpResponse::BadRequest().body("Authorization token missing or invalid"),
+    };
+
+    match drive_service.delete_permission(&token_str, &path.file_id, &path.permission_id, &config).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => drive_error_response("deleting permission", err),
+    }
+}
@@ -0,0 +1,127 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use crate::{api::drive_error::drive_error_response, config::Config, middlewares::auth_guard::access_token, services::google_drive_service::{DriveService, FileInfo}};
+
+#[derive(Debug, Deserialize)]
+pub struct FileId {
+    file_id: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteQuery {
+    /// Delete the file outright instead of moving it to the trash.
+    #[serde(default)]
+    pub permanent: bool,
+}
+
+#[utoipa::path(
+    delete,
+    path = "/drive/files/{file_id}",
+    params(
+        ("file_id" = String, Path, description = "ID of the file to delete"),
+        DeleteQuery
+    ),
+    responses(
+        (status = 200, description = "File moved to the trash", body = FileInfo),
+        (status = 204, description = "File deleted permanently"),
+        (status = 400, description = "Authorization token missing or invalid"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation, or Drive refused to delete the file"),
+        (status = 404, description = "File not found"),
+        (status = 500, description = "Internal server error while deleting the file")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn delete_file<T: DriveService>(
+    file_id: web::Path<FileId>,
+    query: web::Query<DeleteQuery>,
+    req: HttpRequest,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+) -> impl Responder {
+    let token_str = match access_token(&req) {
+        Some(token) => token,
+        None => return HttpResponse::BadRequest().body("Authorization token missing or invalid"),
+    };
+
+    if query.permanent {
+        match drive_service.delete_file(&token_str, &file_id.file_id, &config).await {
+            Ok(()) => HttpResponse::NoContent().finish(),
+            Err(err) => drive_error_response("deleting file", err),
+        }
+    } else {
+        match drive_service.trash_file(&token_str, &file_id.file_id, &config).await {
+            Ok(file) => HttpResponse::Ok().json(file),
+            Err(err) => drive_error_response("trashing file", err),
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/drive/files/{file_id}/restore",
+    params(
+        ("file_id" = String, Path, description = "ID of the trashed file to restore")
+    ),
+    responses(
+        (status = 200, description = "File restored from the trash", body = FileInfo),
+        (status = 400, description = "Authorization token missing or invalid"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation, or Drive refused to restore the file"),
+        (status = 404, description = "File not found"),
+        (status = 500, description = "Internal server error while restoring the file")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn restore_file<T: DriveService>(
+    file_id: web::Path<FileId>,
+    req: HttpRequest,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+) -> impl Responder {
+    let token_str = match access_token(&req) {
+        Some(token) => token,
+        None => return HttpResponse::BadRequest().body("Authorization token missing or invalid"),
+    };
+
+    match drive_service.restore_file(&token_str, &file_id.file_id, &config).await {
+        Ok(file) => HttpResponse::Ok().json(file),
+        Err(err) => drive_error_response("restoring file", err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/drive/trash/empty",
+    responses(
+        (status = 204, description = "Every trashed file was deleted permanently"),
+        (status = 400, description = "Authorization token missing or invalid"),
+        (status = 403, description = "Token lacks the full Drive scope needed to empty the trash"),
+        (status = 500, description = "Internal server error while emptying the trash")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn empty_trash<T: DriveService>(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+) -> impl Responder {
+    let token_str = match access_token(&req) {
+        Some(token) => token,
+        None => return HttpResponse::BadRequest().body("Authorization token missing or invalid"),
+    };
+
+    match drive_service.empty_trash(&token_str, &config).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => drive_error_response("emptying the trash", err),
+    }
+}
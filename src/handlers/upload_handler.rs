@@ -0,0 +1,540 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::collections::BTreeMap;
+use crate::{api::google_drive::{parse_content_range, ChunkStatus, UploadMetadata}, config::Config, middlewares::auth_guard::access_token, services::{checksum::{verify_stored_md5, FileDigest}, google_drive_service::DriveService, resumable_upload::ResumableUpload, session_store::new_session_id, upload_progress::{UploadChecks, UploadProgress}, upload_store::{UploadSession, UploadStore}, upload_validation::{Rejection, UploadValidation}}};
+use anyhow::Context;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateUploadRequest {
+    pub file_name: String,
+    /// Defaults to the root folder.
+    pub folder_id: Option<String>,
+    /// Size of the whole file, if already known.
+    pub total_size: Option<u64>,
+    pub mime_type: Option<String>,
+    pub description: Option<String>,
+    /// Stored as the file's `appProperties`.
+    #[serde(default)]
+    pub app_properties: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UploadStatus {
+    pub upload_id: String,
+    pub file_name: String,
+    /// Bytes Drive has persisted; the next `PUT` must start at this offset.
+    pub committed: u64,
+    pub total_size: Option<u64>,
+    pub complete: bool,
+    pub file_id: Option<String>,
+}
+
+impl UploadStatus {
+    fn new(upload_id: &str, upload: &UploadSession) -> Self {
+        UploadStatus {
+            upload_id: upload_id.to_string(),
+            file_name: upload.file_name.clone(),
+            committed: upload.committed,
+            total_size: upload.total_size,
+            complete: upload.is_complete(),
+            file_id: upload.file_id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadId {
+    upload_id: String,
+}
+
+/// Asks Drive how many bytes it has persisted, so the stored offset survives
+/// crashes between a chunk being accepted and the store being updated.
+/// An empty chunk sent as `bytes */*` is Drive's status query.
+async fn sync_with_drive<T: DriveService>(
+    drive_service: &T,
+    token: &str,
+    mut upload: UploadSession,
+) -> anyhow::Result<UploadSession> {
+    if upload.is_complete() {
+        return Ok(upload);
+    }
+
+    match drive_service.upload_chunk(token, &upload.resumable_url, Vec::new(), 0, None).await? {
+        ChunkStatus::Incomplete { next_offset } => upload.committed = next_offset,
+        ChunkStatus::Complete { file_id, .. } => {
+            upload.committed = upload.total_size.unwrap_or(upload.committed);
+            upload.file_id = Some(file_id);
+        }
+    }
+
+    Ok(upload)
+}
+
+async fn load_upload(upload_store: &dyn UploadStore, upload_id: &str) -> Result<UploadSession, HttpResponse> {
+    match upload_store.get(upload_id).await {
+        Ok(Some(upload)) => Ok(upload),
+        Ok(None) => Err(HttpResponse::NotFound().body("Upload not found")),
+        Err(err) => {
+            eprintln!("Error loading upload session: {:?}", err);
+            Err(HttpResponse::InternalServerError().body("Error loading upload session"))
+        }
+    }
+}
+
+/// Cancels the Drive session, or deletes the file if Drive already created
+/// it, and forgets the upload.
+async fn discard_upload<T: DriveService>(
+    drive_service: &T,
+    token: &str,
+    upload_id: &str,
+    upload: &UploadSession,
+    upload_store: &dyn UploadStore,
+    upload_checks: &UploadChecks,
+    config: &Config,
+) {
+    let discarded = match &upload.file_id {
+        Some(file_id) => drive_service.delete_file(token, file_id, config).await,
+        None => drive_service.cancel_upload(token, &upload.resumable_url).await,
+    };
+    if let Err(err) = discarded {
+        eprintln!("Failed to discard upload {}: {:?}", upload_id, err);
+    }
+
+    upload_checks.remove(upload_id);
+    if let Err(err) = upload_store.remove(upload_id).await {
+        eprintln!("Error removing upload session: {:?}", err);
+    }
+}
+
+fn rejected(file_name: &str, rejection: &Rejection) -> HttpResponse {
+    eprintln!("Rejected upload of '{}': {}", file_name, rejection);
+    HttpResponse::build(rejection.status_code()).body(rejection.to_string())
+}
+
+/// Reads a finished upload back from Drive and checks its content, for
+/// uploads this process did not see from the first byte. The digest is then
+/// that of the content Drive serves.
+async fn check_stored_file<T: DriveService>(
+    drive_service: &T,
+    token: &str,
+    upload: &UploadSession,
+    validation: &UploadValidation,
+    config: &Config,
+) -> anyhow::Result<Result<FileDigest, Rejection>> {
+    let file_id = upload.file_id.as_deref().context("Upload has no file yet")?;
+    let mut progress = match validation.check(upload.mime_type.as_deref(), upload.total_size) {
+        Ok(check) => UploadProgress::new(check),
+        Err(rejection) => return Ok(Err(rejection)),
+    };
+
+    let mut body = drive_service.download_file(token, file_id, None, config).await?.body;
+    while let Some(chunk) = body.next().await {
+        if let Err(rejection) = progress.update(progress.offset, &chunk?) {
+            return Ok(Err(rejection));
+        }
+    }
+    Ok(progress.check.finish().map(|()| progress.checksums.finish()))
+}
+
+async fn save_upload(upload_store: &dyn UploadStore, upload_id: &str, upload: UploadSession) -> HttpResponse {
+    let status = UploadStatus::new(upload_id, &upload);
+
+    match upload_store.insert(upload_id, upload).await {
+        Ok(()) => HttpResponse::Ok().json(status),
+        Err(err) => {
+            eprintln!("Error storing upload session: {:?}", err);
+            HttpResponse::InternalServerError().body("Error storing upload session")
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/drive/uploads",
+    request_body = CreateUploadRequest,
+    responses(
+        (status = 201, description = "Upload session created", body = UploadStatus),
+        (status = 400, description = "Authorization token missing or invalid, or file name missing"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation"),
+        (status = 413, description = "total_size exceeds the upload limit"),
+        (status = 415, description = "mime_type is missing or not an allowed upload type"),
+        (status = 500, description = "Internal server error while creating the upload session")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn create_upload<T: DriveService>(
+    req: HttpRequest,
+    body: web::Json<CreateUploadRequest>,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+    upload_store: web::Data<dyn UploadStore>,
+    upload_checks: web::Data<UploadChecks>,
+) -> impl Responder {
+    let token_str = match access_token(&req) {
+        Some(token) => token,
+        None => return HttpResponse::BadRequest().body("Authorization token missing or invalid"),
+    };
+
+    if body.file_name.trim().is_empty() {
+        return HttpResponse::BadRequest().body("Missing file_name");
+    }
+
+    // The type and declared size are checked before a session is created,
+    // so a refused file never reaches Drive.
+    let check = match upload_checks.validation().check(body.mime_type.as_deref(), body.total_size) {
+        Ok(check) => check,
+        Err(rejection) => return rejected(&body.file_name, &rejection),
+    };
+
+    let metadata = UploadMetadata {
+        name: body.file_name.clone(),
+        mime_type: body.mime_type.clone(),
+        description: body.description.clone(),
+        app_properties: body.app_properties.clone(),
+        folder_id: body.folder_id.clone(),
+        size: body.total_size,
+    };
+
+    let resumable_url = match drive_service
+        .initialize_resumable_upload(&token_str, &metadata, &config)
+        .await
+        .context("Failed to initialize resumable upload")
+    {
+        Ok(url) => url,
+        Err(err) => {
+            eprintln!("Failed to initialize upload: {:?}", err);
+            return HttpResponse::InternalServerError().body(format!("Failed to initialize upload: {:?}", err));
+        }
+    };
+
+    let upload_id = new_session_id();
+    let mut upload = UploadSession::new(resumable_url, body.file_name.clone(), body.total_size);
+    upload.mime_type = body.mime_type.clone();
+    let status = UploadStatus::new(&upload_id, &upload);
+    let expires_at = upload.expires_at;
+
+    match upload_store.insert(&upload_id, upload).await {
+        Ok(()) => {
+            upload_checks.start(&upload_id, UploadProgress::new(check), expires_at);
+            HttpResponse::Created()
+                .insert_header(("Location", format!("/drive/uploads/{}", upload_id)))
+                .json(status)
+        }
+        Err(err) => {
+            eprintln!("Error storing upload session: {:?}", err);
+            HttpResponse::InternalServerError().body("Error storing upload session")
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/drive/uploads/{upload_id}",
+    params(
+        ("upload_id" = String, Path, description = "ID returned when the upload was created")
+    ),
+    responses(
+        (status = 200, description = "Bytes committed so far", body = UploadStatus),
+        (status = 400, description = "Authorization token missing or invalid"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation"),
+        (status = 404, description = "Unknown or expired upload"),
+        (status = 500, description = "Internal server error while checking the upload")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn get_upload<T: DriveService>(
+    upload_id: web::Path<UploadId>,
+    req: HttpRequest,
+    drive_service: web::Data<T>,
+    upload_store: web::Data<dyn UploadStore>,
+) -> impl Responder {
+    let token_str = match access_token(&req) {
+        Some(token) => token,
+        None => return HttpResponse::BadRequest().body("Authorization token missing or invalid"),
+    };
+
+    let upload = match load_upload(upload_store.get_ref(), &upload_id.upload_id).await {
+        Ok(upload) => upload,
+        Err(response) => return response,
+    };
+
+    match sync_with_drive(drive_service.get_ref(), &token_str, upload).await {
+        Ok(upload) => save_upload(upload_store.get_ref(), &upload_id.upload_id, upload).await,
+        Err(err) => {
+            eprintln!("Error checking upload status: {:?}", err);
+            HttpResponse::InternalServerError().body(format!("Error checking upload status: {:?}", err))
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/drive/uploads/{upload_id}",
+    params(
+        ("upload_id" = String, Path, description = "ID returned when the upload was created"),
+        ("Content-Range" = String, Header, description = "`bytes a-b/total`, or `bytes a-b/*` while the total is unknown")
+    ),
+    request_body(content = Vec<u8>, description = "Raw bytes for the given range", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Range accepted; `committed` tells where the next range must start", body = UploadStatus),
+        (status = 400, description = "Authorization token missing or invalid, bad Content-Range, or body length mismatch"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation"),
+        (status = 404, description = "Unknown or expired upload"),
+        (status = 413, description = "The file exceeds the upload limit; the upload is cancelled"),
+        (status = 415, description = "The content does not match its type; the upload is cancelled"),
+        (status = 416, description = "Range starts after the committed offset", body = UploadStatus),
+        (status = 500, description = "Internal server error while uploading, or the MD5 Drive stored does not match the content sent; the file is deleted")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn put_upload_range<T: DriveService>(
+    upload_id: web::Path<UploadId>,
+    req: HttpRequest,
+    mut payload: web::Payload,
+    config: web::Data<Config>,
+    drive_service: web::Data<T>,
+    upload_store: web::Data<dyn UploadStore>,
+    upload_checks: web::Data<UploadChecks>,
+) -> impl Responder {
+    let token_str = match access_token(&req) {
+        Some(token) => token,
+        None => return HttpResponse::BadRequest().body("Authorization token missing or invalid"),
+    };
+    let upload_id = &upload_id.upload_id;
+    // Held until the response, so the ranges of one upload are checked in order.
+    let mut progress = upload_checks.lock(upload_id).await;
+
+    let upload = match load_upload(upload_store.get_ref(), upload_id).await {
+        Ok(upload) => upload,
+        Err(response) => return response,
+    };
+    if upload.is_complete() {
+        return HttpResponse::Ok().json(UploadStatus::new(upload_id, &upload));
+    }
+
+    let content_range = match req
+        .headers()
+        .get("Content-Range")
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_content_range)
+    {
+        Some(content_range) => content_range,
+        None => return HttpResponse::BadRequest().body("Missing or invalid Content-Range header"),
+    };
+
+    if let (Some(declared), Some(total)) = (upload.total_size, content_range.total) {
+        if declared != total {
+            return HttpResponse::BadRequest().body(format!("Total size {} does not match the declared {}", total, declared));
+        }
+    }
+
+    let mut upload = match sync_with_drive(drive_service.get_ref(), &token_str, upload).await {
+        Ok(upload) => upload,
+        Err(err) => {
+            eprintln!("Error checking upload status: {:?}", err);
+            return HttpResponse::InternalServerError().body(format!("Error checking upload status: {:?}", err));
+        }
+    };
+    upload.total_size = content_range.total.or(upload.total_size);
+    if upload.is_complete() {
+        return save_upload(upload_store.get_ref(), upload_id, upload).await;
+    }
+
+    // Half-open byte range [start, end) carried by this request.
+    let (start, end) = match content_range.range {
+        Some((first, last)) => (first, last + 1),
+        // `bytes */total` ends an upload whose bytes have all been sent already.
+        None => match upload.total_size {
+            Some(total) if total == upload.committed => (total, total),
+            Some(_) => return HttpResponse::BadRequest().body("Not every byte has been uploaded yet"),
+            None => return save_upload(upload_store.get_ref(), upload_id, upload).await,
+        },
+    };
+
+    if start > upload.committed {
+        return HttpResponse::RangeNotSatisfiable().json(UploadStatus::new(upload_id, &upload));
+    }
+
+    // Each chunk passes the same checks as a multipart upload before it is
+    // forwarded. Until Drive has any byte, checking can start over here;
+    // after that, an upload this process has not followed from the start
+    // (one begun before a restart) is checked once Drive has the whole file.
+    if progress.is_none() && upload.committed == 0 {
+        match upload_checks.validation().check(upload.mime_type.as_deref(), upload.total_size) {
+            Ok(check) => {
+                upload_checks.start(upload_id, UploadProgress::new(check), upload.expires_at);
+                progress = upload_checks.lock(upload_id).await;
+            }
+            Err(rejection) => {
+                discard_upload(drive_service.get_ref(), &token_str, upload_id, &upload, upload_store.get_ref(), &upload_checks, &config).await;
+                return rejected(&upload.file_name, &rejection);
+            }
+        }
+    }
+
+    let expected_len = end - start;
+    let is_last = upload.total_size == Some(end);
+    // Bytes the client resends that Drive already has are skipped.
+    let mut skip = upload.committed - start;
+    let mut received: u64 = 0;
+    let mut resumable = ResumableUpload::resume(
+        drive_service.get_ref(),
+        &token_str,
+        upload.resumable_url.clone(),
+        config.upload_chunk_size,
+        upload.committed,
+    );
+
+    // On failure the stored offset is left as is; the next request re-syncs it with Drive.
+    while let Some(chunk) = payload.next().await {
+        let data = match chunk {
+            Ok(data) => data,
+            Err(_) => {
+                eprintln!("Error reading upload body");
+                return HttpResponse::InternalServerError().body("Error reading upload body");
+            }
+        };
+
+        let position = start + received;
+        received += data.len() as u64;
+        if received > expected_len {
+            return HttpResponse::BadRequest().body("Body is longer than the Content-Range");
+        }
+
+        if let Some(Err(rejection)) = progress.as_mut().map(|progress| progress.update(position, &data)) {
+            discard_upload(drive_service.get_ref(), &token_str, upload_id, &upload, upload_store.get_ref(), &upload_checks, &config).await;
+            return rejected(&upload.file_name, &rejection);
+        }
+
+        let skipped = skip.min(data.len() as u64) as usize;
+        skip -= skipped as u64;
+        if let Err(err) = resumable.write(&data[skipped..]).await.context("Failed to upload file chunk") {
+            eprintln!("Error uploading file chunk: {:?}", err);
+            return HttpResponse::InternalServerError().body("Error uploading file chunk");
+        }
+    }
+
+    if received != expected_len {
+        return HttpResponse::BadRequest().body("Body is shorter than the Content-Range");
+    }
+
+    if !is_last {
+        match resumable.flush().await.context("Failed to upload file chunk") {
+            Ok(committed) => upload.committed = committed,
+            Err(err) => {
+                eprintln!("Error uploading file chunk: {:?}", err);
+                return HttpResponse::InternalServerError().body("Error uploading file chunk");
+            }
+        }
+        return save_upload(upload_store.get_ref(), upload_id, upload).await;
+    }
+
+    // The trailer is checked before the last chunk, so Drive never creates a truncated file.
+    if let Some(Err(rejection)) = progress.as_mut().map(|progress| progress.check.finish()) {
+        discard_upload(drive_service.get_ref(), &token_str, upload_id, &upload, upload_store.get_ref(), &upload_checks, &config).await;
+        return rejected(&upload.file_name, &rejection);
+    }
+
+    let file = match resumable.finish().await.context("Failed to upload file chunk") {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("Error uploading file chunk: {:?}", err);
+            return HttpResponse::InternalServerError().body("Error uploading file chunk");
+        }
+    };
+    upload.committed = end;
+    upload.file_id = Some(file.file_id);
+
+    let digest = match progress.as_mut() {
+        Some(progress) => std::mem::take(&mut progress.checksums).finish(),
+        None => match check_stored_file(drive_service.get_ref(), &token_str, &upload, upload_checks.validation(), &config)
+            .await
+            .context("Failed to check uploaded file")
+        {
+            Ok(Ok(digest)) => digest,
+            Ok(Err(rejection)) => {
+                discard_upload(drive_service.get_ref(), &token_str, upload_id, &upload, upload_store.get_ref(), &upload_checks, &config).await;
+                return rejected(&upload.file_name, &rejection);
+            }
+            Err(err) => {
+                // A file that could not be checked is not kept.
+                eprintln!("Error checking uploaded file: {:?}", err);
+                discard_upload(drive_service.get_ref(), &token_str, upload_id, &upload, upload_store.get_ref(), &upload_checks, &config).await;
+                return HttpResponse::InternalServerError().body("Error checking uploaded file");
+            }
+        },
+    };
+
+    // Drive's MD5 of the stored file must match the bytes that were sent.
+    if let Err(mismatch) = verify_stored_md5(file.md5_checksum.as_deref(), &digest) {
+        eprintln!("Error uploading '{}': {}", upload.file_name, mismatch);
+        discard_upload(drive_service.get_ref(), &token_str, upload_id, &upload, upload_store.get_ref(), &upload_checks, &config).await;
+        return HttpResponse::InternalServerError().body(mismatch);
+    }
+    upload_checks.remove(upload_id);
+
+    save_upload(upload_store.get_ref(), upload_id, upload).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/drive/uploads/{upload_id}",
+    params(
+        ("upload_id" = String, Path, description = "ID returned when the upload was created")
+    ),
+    responses(
+        (status = 204, description = "Upload cancelled"),
+        (status = 400, description = "Authorization token missing or invalid"),
+        (status = 403, description = "Token lacks the Drive scope required for this operation"),
+        (status = 404, description = "Unknown or expired upload"),
+        (status = 500, description = "Internal server error while cancelling the upload")
+    ),
+    security(
+        ("bearerAuth" = [])
+    ),
+    tag = "drive"
+)]
+pub async fn cancel_upload<T: DriveService>(
+    upload_id: web::Path<UploadId>,
+    req: HttpRequest,
+    drive_service: web::Data<T>,
+    upload_store: web::Data<dyn UploadStore>,
+    upload_checks: web::Data<UploadChecks>,
+) -> impl Responder {
+    let token_str = match access_token(&req) {
+        Some(token) => token,
+        None => return HttpResponse::BadRequest().body("Authorization token missing or invalid"),
+    };
+
+    let upload = match load_upload(upload_store.get_ref(), &upload_id.upload_id).await {
+        Ok(upload) => upload,
+        Err(response) => return response,
+    };
+
+    upload_checks.remove(&upload_id.upload_id);
+    if !upload.is_complete() {
+        if let Err(err) = drive_service.cancel_upload(&token_str, &upload.resumable_url).await {
+            eprintln!("Error cancelling upload: {:?}", err);
+            return HttpResponse::InternalServerError().body(format!("Error cancelling upload: {:?}", err));
+        }
+    }
+
+    match upload_store.remove(&upload_id.upload_id).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => {
+            eprintln!("Error removing upload session: {:?}", err);
+            HttpResponse::InternalServerError().body("Error removing upload session")
+        }
+    }
+}
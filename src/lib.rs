@@ -4,3 +4,5 @@ pub mod services;
 pub mod handlers;
 pub mod middlewares;
 pub mod api;
+pub mod server;
+pub mod swagger_config;
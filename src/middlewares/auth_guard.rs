@@ -1,12 +1,32 @@
 use actix_service::{Service, Transform};
-use actix_web::{dev::{ServiceRequest, ServiceResponse}, Error};
+use actix_web::{dev::{ServiceRequest, ServiceResponse}, web, Error, HttpMessage, HttpRequest};
 use futures::future::{ok, Ready};
 use futures::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
-use crate::api::auth::validate_token;
+use std::time::Duration;
+use crate::api::auth::{refresh_access_token, validate_token, TokenInfo};
+use crate::config::Config;
+use crate::middlewares::session::{bearer_token, session_id_from_cookie};
+use crate::middlewares::token_cache::TokenCache;
+use crate::services::auth_service::ServiceAccountTokenProvider;
+use crate::services::session_store::{now_secs, Session, SessionStore};
 
+/// Google access token resolved by [`AuthGuard`] for the current request.
+#[derive(Clone)]
+pub struct AccessToken(pub String);
+
+/// Returns the Google access token for the request, preferring the one resolved by
+/// [`AuthGuard`] and falling back to the raw `Authorization` header.
+pub fn access_token(req: &HttpRequest) -> Option<String> {
+    if let Some(token) = req.extensions().get::<AccessToken>() {
+        return Some(token.0.clone());
+    }
+    bearer_token(req)
+}
+
+#[derive(Default)]
 pub struct AuthGuard;
 
 impl AuthGuard {
@@ -49,20 +69,57 @@ where
     }
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        let token_opt = req.headers().get("Authorization").and_then(|header| {
-            header.to_str().ok().map(|value| value.replace("Bearer ", ""))
-        });
+        let config = req.app_data::<web::Data<Config>>().cloned();
+        let store = req.app_data::<web::Data<dyn SessionStore>>().cloned();
+        let token_cache = req.app_data::<web::Data<TokenCache>>().cloned();
+        let service_account = req.app_data::<web::Data<ServiceAccountTokenProvider>>().cloned();
+        let api_key = req
+            .headers()
+            .get("X-Api-Key")
+            .and_then(|header| header.to_str().ok())
+            .map(str::to_string);
+        let session_id = config
+            .as_ref()
+            .and_then(|config| session_id_from_cookie(req.request(), config));
+        let token_opt = bearer_token(req.request());
 
         let service = Arc::clone(&self.service);
 
         Box::pin(async move {
+            if let (Some(config), Some(service_account)) = (config.as_ref(), service_account) {
+                let access_token = authorize_service_account(&service_account, api_key, config).await?;
+                req.extensions_mut().insert(AccessToken(access_token));
+                req.extensions_mut().insert(service_account_token_info(config));
+                return service.call(req).await;
+            }
+
+            if let (Some(config), Some(store)) = (config.as_ref(), store.as_ref()) {
+                if let Some(session_id) = session_id.as_ref().or(token_opt.as_ref()) {
+                    if let Some(session) = resolve_session(session_id, store, config).await? {
+                        let token_info = session_token_info(&session, config);
+                        req.extensions_mut().insert(AccessToken(session.access_token));
+                        req.extensions_mut().insert(token_info);
+                        return service.call(req).await;
+                    }
+                }
+            }
+
             if let Some(token) = token_opt {
-                match validate_token(token).await {
-                    Ok(valid) if valid => {
+                let config = match &config {
+                    Some(config) => config,
+                    None => return Err(actix_web::error::ErrorInternalServerError("Configuration missing")),
+                };
+                match check_token(&token, token_cache.as_ref().map(|cache| cache.get_ref()), config).await {
+                    Ok(Some(token_info)) => {
+                        if token_info.aud.as_deref() != Some(config.client_id.as_str()) {
+                            return Err(actix_web::error::ErrorUnauthorized("Token was not issued for this application"));
+                        }
+                        req.extensions_mut().insert(AccessToken(token));
+                        req.extensions_mut().insert(token_info);
                         let res = service.call(req).await?;
                         Ok(res)
                     }
-                    Ok(_) => Err(actix_web::error::ErrorUnauthorized("Invalid token")),
+                    Ok(None) => Err(actix_web::error::ErrorUnauthorized("Invalid token")),
                     Err(_) => Err(actix_web::error::ErrorUnauthorized("Token validation failed")),
                 }
             } else {
@@ -71,3 +128,116 @@ where
         })
     }
 }
+
+/// Checks the caller's API key and returns the server-held service-account token.
+async fn authorize_service_account(
+    service_account: &ServiceAccountTokenProvider,
+    api_key: Option<String>,
+    config: &Config,
+) -> Result<String, Error> {
+    match (&config.service_api_key, api_key) {
+        (Some(expected), Some(provided)) if constant_time_eq(expected.as_bytes(), provided.as_bytes()) => {}
+        (_, None) => return Err(actix_web::error::ErrorUnauthorized("API key missing")),
+        _ => return Err(actix_web::error::ErrorUnauthorized("Invalid API key")),
+    }
+
+    service_account.access_token(config).await.map_err(|err| {
+        eprintln!("Error obtaining service account token: {:?}", err);
+        actix_web::error::ErrorBadGateway("Failed to obtain service account token")
+    })
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Service-account tokens are minted for the configured scope, so no tokeninfo round trip is needed.
+fn service_account_token_info(config: &Config) -> TokenInfo {
+    TokenInfo {
+        aud: Some(config.client_id.clone()),
+        scopes: config.scope.split_whitespace().map(str::to_string).collect(),
+        exp: None,
+        expires_in: None,
+        email: config.service_account_subject.clone(),
+    }
+}
+
+/// Validates a bearer token, consulting the shared cache before calling Google's tokeninfo endpoint.
+async fn check_token(token: &str, token_cache: Option<&TokenCache>, config: &Config) -> anyhow::Result<Option<TokenInfo>> {
+    if let Some(token_info) = token_cache.and_then(|cache| cache.get(token)) {
+        return Ok(token_info);
+    }
+
+    let token_info = validate_token(token, config).await?;
+
+    if let Some(cache) = token_cache {
+        match &token_info {
+            Some(token_info) => cache.insert_valid(token, token_info.clone(), token_lifetime(token_info)),
+            None => cache.insert_invalid(token),
+        }
+    }
+
+    Ok(token_info)
+}
+
+fn token_lifetime(token_info: &TokenInfo) -> Duration {
+    let secs = token_info
+        .expires_in
+        .or_else(|| token_info.exp.map(|exp| exp.saturating_sub(now_secs())))
+        .unwrap_or(0);
+    Duration::from_secs(secs)
+}
+
+/// Describes a session token the way tokeninfo would; it was issued to us, so the audience is our client id.
+fn session_token_info(session: &Session, config: &Config) -> TokenInfo {
+    TokenInfo {
+        aud: Some(config.client_id.clone()),
+        scopes: session.scope
+            .as_deref()
+            .map(|scope| scope.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default(),
+        exp: Some(session.expires_at),
+        expires_in: Some(session.expires_in()),
+        email: None,
+    }
+}
+
+/// Looks up a session and returns it with a live access token, transparently renewing
+/// expired tokens with the stored refresh token.
+async fn resolve_session(
+    session_id: &str,
+    store: &web::Data<dyn SessionStore>,
+    config: &Config,
+) -> Result<Option<Session>, Error> {
+    let session = match store.get(session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return Ok(None),
+        Err(err) => {
+            eprintln!("Error loading session: {:?}", err);
+            return Err(actix_web::error::ErrorInternalServerError("Failed to load session"));
+        }
+    };
+
+    if !session.is_expired() {
+        return Ok(Some(session));
+    }
+
+    let refresh_token = match &session.refresh_token {
+        Some(refresh_token) => refresh_token.clone(),
+        None => return Err(actix_web::error::ErrorUnauthorized("Session expired")),
+    };
+
+    let refreshed: Session = match refresh_access_token(&refresh_token, config).await {
+        Ok(token_response) => session.refreshed(token_response),
+        Err(err) => {
+            eprintln!("Error refreshing session token: {:?}", err);
+            return Err(actix_web::error::ErrorUnauthorized("Session expired"));
+        }
+    };
+
+    if let Err(err) = store.insert(session_id, refreshed.clone()).await {
+        eprintln!("Error storing refreshed session: {:?}", err);
+    }
+
+    Ok(Some(refreshed))
+}
@@ -1 +1,4 @@
-pub mod auth_guard;
\ No newline at end of file
+pub mod auth_guard;
+pub mod require_scopes;
+pub mod session;
+pub mod token_cache;
\ No newline at end of file
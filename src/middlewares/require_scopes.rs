@@ -0,0 +1,86 @@
+use actix_service::{Service, Transform};
+use actix_web::{dev::{ServiceRequest, ServiceResponse}, Error, HttpMessage};
+use futures::future::{ok, Ready};
+use futures::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use crate::api::auth::TokenInfo;
+
+pub const DRIVE: &str = "https://www.googleapis.com/auth/drive";
+pub const DRIVE_FILE: &str = "https://www.googleapis.com/auth/drive.file";
+pub const DRIVE_READONLY: &str = "https://www.googleapis.com/auth/drive.readonly";
+pub const DRIVE_METADATA_READONLY: &str = "https://www.googleapis.com/auth/drive.metadata.readonly";
+
+/// Scopes that allow reading file metadata, such as listing folders and files.
+pub const METADATA_READ_SCOPES: &[&str] = &[DRIVE, DRIVE_FILE, DRIVE_READONLY, DRIVE_METADATA_READONLY];
+/// Scopes that allow reading file contents.
+pub const READ_SCOPES: &[&str] = &[DRIVE, DRIVE_FILE, DRIVE_READONLY];
+/// Scopes that allow creating or modifying files.
+pub const WRITE_SCOPES: &[&str] = &[DRIVE, DRIVE_FILE];
+/// Scopes that allow changing every file in the Drive, not only the ones this app opened or created.
+pub const FULL_SCOPES: &[&str] = &[DRIVE];
+
+/// Route middleware rejecting requests with 403 unless the token resolved by
+/// [`AuthGuard`](crate::middlewares::auth_guard::AuthGuard) carries one of the given scopes.
+pub struct RequireScopes {
+    scopes: &'static [&'static str],
+}
+
+impl RequireScopes {
+    pub fn any_of(scopes: &'static [&'static str]) -> Self {
+        RequireScopes { scopes }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequireScopes
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequireScopesImpl<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequireScopesImpl { service: Rc::new(service), scopes: self.scopes })
+    }
+}
+
+pub struct RequireScopesImpl<S> {
+    service: Rc<S>,
+    scopes: &'static [&'static str],
+}
+
+impl<S, B> Service<ServiceRequest> for RequireScopesImpl<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let allowed = req
+            .extensions()
+            .get::<TokenInfo>()
+            .map(|token_info| token_info.has_any_scope(self.scopes));
+
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            match allowed {
+                Some(true) => service.call(req).await,
+                Some(false) => Err(actix_web::error::ErrorForbidden("Token lacks the scope required for this operation")),
+                None => Err(actix_web::error::ErrorUnauthorized("Authorization token missing")),
+            }
+        })
+    }
+}
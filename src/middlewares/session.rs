@@ -0,0 +1,82 @@
+use actix_web::cookie::{time::Duration, Cookie, CookieJar, SameSite};
+use actix_web::HttpRequest;
+use crate::config::Config;
+use crate::services::session_store::PENDING_LOGIN_TTL_SECS;
+
+pub const SESSION_COOKIE: &str = "session_id";
+
+/// Carries the OAuth `state` from `/auth` to the callback, so a login can only
+/// be completed by the browser that started it.
+pub const LOGIN_STATE_COOKIE: &str = "login_state";
+
+/// Signs an HttpOnly, SameSite=Lax cookie with the session key.
+fn signed_cookie(name: &'static str, value: &str, max_age: Option<Duration>, config: &Config) -> Cookie<'static> {
+    let mut cookie = Cookie::build(name, value.to_string())
+        .path("/")
+        .http_only(true)
+        .secure(config.session_cookie_secure)
+        .same_site(SameSite::Lax)
+        .finish();
+    if let Some(max_age) = max_age {
+        cookie.set_max_age(max_age);
+    }
+
+    let mut jar = CookieJar::new();
+    jar.signed_mut(&config.session_key).add(cookie);
+    jar.get(name)
+        .cloned()
+        .expect("Signed cookie was just added to the jar")
+}
+
+/// Returns the value of a signed cookie, ignoring cookies whose signature does not verify.
+fn signed_cookie_value(req: &HttpRequest, name: &str, config: &Config) -> Option<String> {
+    let cookie = req.cookie(name)?;
+
+    let mut jar = CookieJar::new();
+    jar.add_original(cookie);
+    jar.signed(&config.session_key)
+        .get(name)
+        .map(|cookie| cookie.value().to_string())
+}
+
+fn removal_cookie(name: &'static str) -> Cookie<'static> {
+    let mut cookie = Cookie::build(name, "").path("/").finish();
+    cookie.make_removal();
+    cookie
+}
+
+/// Builds the signed, HttpOnly cookie that carries the opaque session id.
+pub fn session_cookie(session_id: &str, config: &Config) -> Cookie<'static> {
+    signed_cookie(SESSION_COOKIE, session_id, None, config)
+}
+
+/// Builds a cookie that makes the browser drop the session cookie.
+pub fn session_removal_cookie() -> Cookie<'static> {
+    removal_cookie(SESSION_COOKIE)
+}
+
+/// Builds the signed pre-login cookie, which lives as long as the pending login.
+pub fn login_state_cookie(state: &str, config: &Config) -> Cookie<'static> {
+    signed_cookie(LOGIN_STATE_COOKIE, state, Some(Duration::seconds(PENDING_LOGIN_TTL_SECS as i64)), config)
+}
+
+/// Builds a cookie that makes the browser drop the pre-login cookie.
+pub fn login_state_removal_cookie() -> Cookie<'static> {
+    removal_cookie(LOGIN_STATE_COOKIE)
+}
+
+/// Returns the state from the signed pre-login cookie.
+pub fn login_state_from_cookie(req: &HttpRequest, config: &Config) -> Option<String> {
+    signed_cookie_value(req, LOGIN_STATE_COOKIE, config)
+}
+
+/// Returns the session id from the signed session cookie, ignoring cookies whose signature does not verify.
+pub fn session_id_from_cookie(req: &HttpRequest, config: &Config) -> Option<String> {
+    signed_cookie_value(req, SESSION_COOKIE, config)
+}
+
+pub fn bearer_token(req: &HttpRequest) -> Option<String> {
+    req.headers().get("Authorization").and_then(|header| {
+        header.to_str().ok().map(|value| value.replace("Bearer ", ""))
+    })
+}
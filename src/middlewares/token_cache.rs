@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+use crate::api::auth::TokenInfo;
+
+type TokenHash = [u8; 32];
+
+struct CacheEntry {
+    token_info: Option<TokenInfo>,
+    expires_at: Instant,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TokenCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+/// Bounded cache of tokeninfo results, keyed by the SHA-256 of the token so raw tokens are never kept in memory.
+///
+/// Valid tokens are cached until their real expiry; invalid tokens are cached for a short negative TTL.
+pub struct TokenCache {
+    entries: RwLock<HashMap<TokenHash, CacheEntry>>,
+    capacity: usize,
+    negative_ttl: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl TokenCache {
+    pub fn new(capacity: usize, negative_ttl: Duration) -> Self {
+        TokenCache {
+            entries: RwLock::new(HashMap::new()),
+            capacity,
+            negative_ttl,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the cached validation result for `token` (`Some(None)` for a token known to be invalid),
+    /// or `None` when it has to be validated with Google.
+    pub fn get(&self, token: &str) -> Option<Option<TokenInfo>> {
+        let key = hash_token(token);
+        let now = Instant::now();
+
+        let cached = self.entries
+            .read()
+            .ok()
+            .and_then(|entries| entries.get(&key).filter(|entry| entry.expires_at > now).map(|entry| entry.token_info.clone()));
+
+        match cached {
+            Some(token_info) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(token_info)
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    pub fn insert_valid(&self, token: &str, token_info: TokenInfo, ttl: Duration) {
+        self.insert(token, Some(token_info), ttl);
+    }
+
+    pub fn insert_invalid(&self, token: &str) {
+        self.insert(token, None, self.negative_ttl);
+    }
+
+    pub fn invalidate(&self, token: &str) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.remove(&hash_token(token));
+        }
+    }
+
+    pub fn stats(&self) -> TokenCacheStats {
+        TokenCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.read().map(|entries| entries.len()).unwrap_or(0),
+            capacity: self.capacity,
+        }
+    }
+
+    fn insert(&self, token: &str, token_info: Option<TokenInfo>, ttl: Duration) {
+        if self.capacity == 0 || ttl.is_zero() {
+            return;
+        }
+
+        let now = Instant::now();
+        let key = hash_token(token);
+
+        if let Ok(mut entries) = self.entries.write() {
+            if entries.len() >= self.capacity && !entries.contains_key(&key) {
+                entries.retain(|_, entry| entry.expires_at > now);
+            }
+            if entries.len() >= self.capacity && !entries.contains_key(&key) {
+                let soonest = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.expires_at)
+                    .map(|(key, _)| *key);
+                if let Some(soonest) = soonest {
+                    entries.remove(&soonest);
+                }
+            }
+            entries.insert(key, CacheEntry { token_info, expires_at: now + ttl });
+        }
+    }
+}
+
+fn hash_token(token: &str) -> TokenHash {
+    Sha256::digest(token.as_bytes()).into()
+}
@@ -1,11 +1,13 @@
-use crate::{config::Config, handlers::auth_handler::{auth_callback, get_auth_url}, services::auth_service:: AuthTokenService};
+use crate::{handlers::auth_handler::{auth_callback, get_auth_url, logout, refresh_token}, services::auth_service:: AuthTokenService};
 use actix_web::web;
 
 pub fn auth_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/auth")
             .app_data(web::Data::new(AuthTokenService))
-            .route("", web::get().to(|config: web::Data<Config>, _token_service: web::Data<AuthTokenService>| get_auth_url(config)))
+            .route("", web::get().to(get_auth_url))
             .route("/callback", web::get().to(auth_callback::<AuthTokenService>))
+            .route("/refresh", web::post().to(refresh_token::<AuthTokenService>))
+            .route("/logout", web::post().to(logout::<AuthTokenService>))
     );
 }
@@ -1,13 +1,31 @@
-use crate::{handlers::google_drive_handler::{download_pdf_file_by_id, get_list_files_in_folder, get_list_folders, upload_pdf_file}, services::google_drive_service::GoogleDriveService};
+use crate::{handlers::{file_handler::{copy_file, get_file_metadata, update_file}, folder_handler::create_folder, google_drive_handler::{download_pdf_file_by_id, get_folder_tree, get_list_files_in_folder, get_list_folders, search_files, upload_pdf_file, verify_file}, permission_handler::{create_permission, delete_permission, list_permissions, update_permission}, trash_handler::{delete_file, empty_trash, restore_file}, upload_handler::{cancel_upload, create_upload, get_upload, put_upload_range}}, middlewares::require_scopes::{RequireScopes, FULL_SCOPES, METADATA_READ_SCOPES, READ_SCOPES, WRITE_SCOPES}, services::google_drive_service::GoogleDriveService};
 use actix_web::web;
 
 pub fn drive_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/drive")
             .app_data(web::Data::new(GoogleDriveService))
-            .route("/list-folders", web::get().to(get_list_folders::<GoogleDriveService>))
-            .route("/files", web::get().to(get_list_files_in_folder::<GoogleDriveService>))
-            .route("/files/{file_id}", web::get().to(download_pdf_file_by_id::<GoogleDriveService>))
-            .route("/files", web::post().to(upload_pdf_file::<GoogleDriveService>))
+            .route("/list-folders", web::get().to(get_list_folders::<GoogleDriveService>).wrap(RequireScopes::any_of(METADATA_READ_SCOPES)))
+            .route("/folders", web::post().to(create_folder::<GoogleDriveService>).wrap(RequireScopes::any_of(WRITE_SCOPES)))
+            .route("/folders/{folder_id}/tree", web::get().to(get_folder_tree::<GoogleDriveService>).wrap(RequireScopes::any_of(METADATA_READ_SCOPES)))
+            .route("/search", web::get().to(search_files::<GoogleDriveService>).wrap(RequireScopes::any_of(METADATA_READ_SCOPES)))
+            .route("/files", web::get().to(get_list_files_in_folder::<GoogleDriveService>).wrap(RequireScopes::any_of(METADATA_READ_SCOPES)))
+            .route("/files/{file_id}", web::get().to(download_pdf_file_by_id::<GoogleDriveService>).wrap(RequireScopes::any_of(READ_SCOPES)))
+            .route("/files/{file_id}", web::delete().to(delete_file::<GoogleDriveService>).wrap(RequireScopes::any_of(WRITE_SCOPES)))
+            .route("/files/{file_id}/metadata", web::get().to(get_file_metadata::<GoogleDriveService>).wrap(RequireScopes::any_of(METADATA_READ_SCOPES)))
+            .route("/files/{file_id}/verify", web::get().to(verify_file::<GoogleDriveService>).wrap(RequireScopes::any_of(READ_SCOPES)))
+            .route("/files/{file_id}", web::patch().to(update_file::<GoogleDriveService>).wrap(RequireScopes::any_of(WRITE_SCOPES)))
+            .route("/files/{file_id}/copy", web::post().to(copy_file::<GoogleDriveService>).wrap(RequireScopes::any_of(WRITE_SCOPES)))
+            .route("/files/{file_id}/permissions", web::get().to(list_permissions::<GoogleDriveService>).wrap(RequireScopes::any_of(METADATA_READ_SCOPES)))
+            .route("/files/{file_id}/permissions", web::post().to(create_permission::<GoogleDriveService>).wrap(RequireScopes::any_of(WRITE_SCOPES)))
+            .route("/files/{file_id}/permissions/{permission_id}", web::patch().to(update_permission::<GoogleDriveService>).wrap(RequireScopes::any_of(WRITE_SCOPES)))
+            .route("/files/{file_id}/permissions/{permission_id}", web::delete().to(delete_permission::<GoogleDriveService>).wrap(RequireScopes::any_of(WRITE_SCOPES)))
+            .route("/files/{file_id}/restore", web::post().to(restore_file::<GoogleDriveService>).wrap(RequireScopes::any_of(WRITE_SCOPES)))
+            .route("/trash/empty", web::post().to(empty_trash::<GoogleDriveService>).wrap(RequireScopes::any_of(FULL_SCOPES)))
+            .route("/files", web::post().to(upload_pdf_file::<GoogleDriveService>).wrap(RequireScopes::any_of(WRITE_SCOPES)))
+            .route("/uploads", web::post().to(create_upload::<GoogleDriveService>).wrap(RequireScopes::any_of(WRITE_SCOPES)))
+            .route("/uploads/{upload_id}", web::get().to(get_upload::<GoogleDriveService>).wrap(RequireScopes::any_of(WRITE_SCOPES)))
+            .route("/uploads/{upload_id}", web::put().to(put_upload_range::<GoogleDriveService>).wrap(RequireScopes::any_of(WRITE_SCOPES)))
+            .route("/uploads/{upload_id}", web::delete().to(cancel_upload::<GoogleDriveService>).wrap(RequireScopes::any_of(WRITE_SCOPES)))
     );
 }
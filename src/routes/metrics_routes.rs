@@ -0,0 +1,9 @@
+use crate::handlers::metrics_handler::get_token_cache_stats;
+use actix_web::web;
+
+pub fn metrics_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/metrics")
+            .route("/token-cache", web::get().to(get_token_cache_stats))
+    );
+}
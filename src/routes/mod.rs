@@ -1,2 +1,3 @@
 pub mod auth_routes;
-pub mod drive_routes;
\ No newline at end of file
+pub mod drive_routes;
+pub mod metrics_routes;
\ No newline at end of file
@@ -0,0 +1,85 @@
+use crate::{config::{AuthMode, Config}, routes, swagger_config};
+use actix_web::{dev::Server, web, App, HttpServer};
+use actix_cors::Cors;
+use crate::middlewares::{auth_guard::AuthGuard, token_cache::TokenCache};
+use crate::services::{auth_service::{ServiceAccountKey, ServiceAccountTokenProvider}, session_store::{InMemorySessionStore, SessionStore}, sqlite_session_store::SqliteSessionStore, sqlite_upload_store::SqliteUploadStore, upload_progress::UploadChecks, upload_store::{InMemoryUploadStore, UploadStore}, upload_validation::UploadValidation};
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+/// Builds the HTTP server on an already bound listener, so tests can run it on an ephemeral port.
+pub fn run(config: Config, listener: TcpListener) -> std::io::Result<Server> {
+    let config_data = web::Data::new(config.clone());
+
+    let session_store: Arc<dyn SessionStore> = match config.session_store.as_str() {
+        "sqlite" => Arc::new(
+            SqliteSessionStore::open(&config.session_db_path)
+                .map_err(|err| std::io::Error::other(format!("Failed to open SQLite session store: {:?}", err)))?
+        ),
+        _ => Arc::new(InMemorySessionStore::new()),
+    };
+    let session_store_data: web::Data<dyn SessionStore> = web::Data::from(session_store);
+    // Upload sessions live in the same backend as login sessions.
+    let upload_store: Arc<dyn UploadStore> = match config.session_store.as_str() {
+        "sqlite" => Arc::new(
+            SqliteUploadStore::open(&config.session_db_path)
+                .map_err(|err| std::io::Error::other(format!("Failed to open SQLite upload store: {:?}", err)))?
+        ),
+        _ => Arc::new(InMemoryUploadStore::new()),
+    };
+    let upload_store_data: web::Data<dyn UploadStore> = web::Data::from(upload_store);
+    let upload_validation_data = web::Data::new(
+        UploadValidation::from_config(&config)
+            .map_err(|err| std::io::Error::other(format!("Invalid UPLOAD_ALLOWED_TYPES: {}", err)))?
+    );
+    let upload_checks_data = web::Data::new(UploadChecks::new(upload_validation_data.clone().into_inner()));
+    let token_cache_data = web::Data::new(TokenCache::new(
+        config.token_cache_capacity,
+        Duration::from_secs(config.token_cache_negative_ttl_secs),
+    ));
+    let service_account_data = match (config.auth_mode, &config.service_account_key_path) {
+        (AuthMode::ServiceAccount, Some(key_path)) => {
+            let key = ServiceAccountKey::from_file(key_path)
+                .map_err(|err| std::io::Error::other(format!("Failed to load service account key: {:?}", err)))?;
+            Some(web::Data::new(ServiceAccountTokenProvider::new(key, config.service_account_subject.clone())))
+        }
+        _ => None,
+    };
+
+    let server = HttpServer::new(move || {
+        let cors = Cors::default()
+            .allow_any_origin()
+            .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE"])
+            .allow_any_header();
+
+        App::new()
+            .app_data(config_data.clone())
+            .app_data(session_store_data.clone())
+            .app_data(token_cache_data.clone())
+            .app_data(upload_store_data.clone())
+            .app_data(upload_validation_data.clone())
+            .app_data(upload_checks_data.clone())
+            .wrap(cors)
+            .configure(routes::auth_routes::auth_routes)
+            .service(
+                SwaggerUi::new("/swagger-ui/{_:.*}")
+                    .url("/api-docs/openapi.json", swagger_config::ApiDoc::openapi())
+            )
+            .service(web::scope("")
+                .wrap(AuthGuard::new())
+                .configure(|cfg| {
+                    if let Some(service_account_data) = &service_account_data {
+                        cfg.app_data(service_account_data.clone());
+                    }
+                })
+                .configure(routes::metrics_routes::metrics_routes)
+                .configure(routes::drive_routes::drive_routes)
+            )
+    })
+    .listen(listener)?
+    .run();
+
+    Ok(server)
+}
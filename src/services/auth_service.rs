@@ -1,22 +1,46 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
-use crate::api::auth::{get_access_token, TokenResponse};
+use crate::api::auth::{get_access_token, get_service_account_token, refresh_access_token, revoke_token, TokenResponse};
+use crate::services::session_store::{now_secs, EXPIRY_SKEW_SECS};
 use crate::config::Config;
 use std::future::Future;
 use std::pin::Pin;
 use anyhow::{Context, Result};
+use tokio::sync::Mutex;
 
 #[derive(Deserialize, IntoParams, ToSchema)]
 pub struct AuthCallbackQuery {
-    pub code: String,
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SessionResponse {
+    pub session_id: String,
+    pub expires_in: u64,
 }
 
 pub trait AuthService {
     fn get_access_token<'a>(
         &'a self,
         code: &'a str,
+        code_verifier: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<TokenResponse>> + Send + 'a>>;
+
+    fn refresh_access_token<'a>(
+        &'a self,
+        refresh_token: &'a str,
         config: &'a Config
     ) -> Pin<Box<dyn Future<Output = Result<TokenResponse>> + Send + 'a>>;
+
+    fn revoke_token<'a>(
+        &'a self,
+        token: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
 }
 
 pub struct AuthTokenService;
@@ -25,12 +49,103 @@ impl AuthService for AuthTokenService {
     fn get_access_token<'a>(
         &'a self,
         code: &'a str,
+        code_verifier: &'a str,
         config: &'a Config
     ) -> Pin<Box<dyn Future<Output = Result<TokenResponse>> + Send + 'a>> {
         Box::pin(async move {
-            get_access_token(code, config)
+            get_access_token(code, code_verifier, config)
                 .await
                 .context("Failed to get access token")
         })
     }
+
+    fn refresh_access_token<'a>(
+        &'a self,
+        refresh_token: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<TokenResponse>> + Send + 'a>> {
+        Box::pin(async move {
+            refresh_access_token(refresh_token, config)
+                .await
+                .context("Failed to refresh access token")
+        })
+    }
+
+    fn revoke_token<'a>(
+        &'a self,
+        token: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            revoke_token(token, config)
+                .await
+                .context("Failed to revoke token")
+        })
+    }
+}
+
+/// Fields of a Google service-account JSON key used to sign JWT assertions.
+#[derive(Deserialize)]
+pub struct ServiceAccountKey {
+    pub client_email: String,
+    pub private_key: String,
+    pub private_key_id: Option<String>,
+}
+
+impl ServiceAccountKey {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read service account key from '{}'", path))?;
+        serde_json::from_str(&json).context("Failed to parse service account key")
+    }
+}
+
+struct CachedServiceToken {
+    access_token: String,
+    expires_at: u64,
+}
+
+/// Supplies server-held access tokens obtained with a service-account key, reusing each token until shortly before it expires.
+pub struct ServiceAccountTokenProvider {
+    key: ServiceAccountKey,
+    subject: Option<String>,
+    cached: Mutex<Option<CachedServiceToken>>,
+}
+
+impl ServiceAccountTokenProvider {
+    pub fn new(key: ServiceAccountKey, subject: Option<String>) -> Self {
+        ServiceAccountTokenProvider {
+            key,
+            subject,
+            cached: Mutex::new(None),
+        }
+    }
+
+    pub async fn access_token(&self, config: &Config) -> Result<String> {
+        let mut cached = self.cached.lock().await;
+
+        if let Some(token) = cached.as_ref() {
+            if now_secs() + EXPIRY_SKEW_SECS < token.expires_at {
+                return Ok(token.access_token.clone());
+            }
+        }
+
+        let token_response = get_service_account_token(
+            &self.key.client_email,
+            self.key.private_key_id.as_deref(),
+            &self.key.private_key,
+            self.subject.as_deref(),
+            config,
+        )
+        .await
+        .context("Failed to obtain service account token")?;
+
+        let access_token = token_response.access_token.clone();
+        *cached = Some(CachedServiceToken {
+            access_token: token_response.access_token,
+            expires_at: now_secs() + token_response.expires_in,
+        });
+
+        Ok(access_token)
+    }
 }
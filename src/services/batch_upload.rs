@@ -0,0 +1,159 @@
+use actix_web::web::Bytes;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use utoipa::ToSchema;
+use crate::api::google_drive::UploadMetadata;
+use crate::config::Config;
+use crate::services::google_drive_service::DriveService;
+use crate::services::checksum::{verify_stored_md5, Checksums, FileDigest};
+use crate::services::resumable_upload::{ResumableUpload, UploadedFile};
+use crate::services::upload_validation::Rejection;
+
+/// Files of one request uploaded to Drive at the same time.
+pub const UPLOAD_CONCURRENCY: usize = 4;
+/// Chunks of a file part buffered ahead of its upload.
+pub const UPLOAD_CHANNEL_CAPACITY: usize = 8;
+
+/// Outcome of uploading one file part.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UploadResult {
+    pub file_name: String,
+    pub mime_type: Option<String>,
+    /// Set once Drive has created the file with the content that was sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_id: Option<String>,
+    /// MD5 of the uploaded content in hex, as computed while streaming it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub md5_checksum: Option<String>,
+    /// SHA-256 of the uploaded content in hex.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256_checksum: Option<String>,
+    /// Set when the upload failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Set when the file was refused by validation rather than by Drive.
+    #[serde(skip)]
+    pub rejection: Option<Rejection>,
+}
+
+impl UploadResult {
+    /// A file refused before its upload started.
+    pub fn rejected(metadata: &UploadMetadata, rejection: Rejection) -> Self {
+        UploadResult {
+            file_name: metadata.name.clone(),
+            mime_type: metadata.mime_type.clone(),
+            file_id: None,
+            md5_checksum: None,
+            sha256_checksum: None,
+            error: Some(rejection.to_string()),
+            rejection: Some(rejection),
+        }
+    }
+
+    /// A file can be created and still fail, when its checksum does not match
+    /// and it could not be deleted again.
+    pub fn is_success(&self) -> bool {
+        self.file_id.is_some() && self.error.is_none()
+    }
+}
+
+/// Why a file part stopped being fed to its upload.
+#[derive(Debug)]
+pub enum PartError {
+    /// The part could not be read from the request.
+    Read(String),
+    /// The content failed validation.
+    Rejected(Rejection),
+}
+
+/// Uploads one file into its own resumable session.
+///
+/// `head` holds bytes already read from the part (e.g. to sniff its type);
+/// the rest arrives through `chunks` until the sender is dropped. An `Err`
+/// on the channel means the part could not be read or was rejected, and the
+/// session is cancelled instead of being completed.
+///
+/// The content is hashed on its way to Drive, and the upload fails if the
+/// MD5 Drive computed for the stored file differs or is missing; the file is
+/// then deleted, so no unverified copy is left behind.
+pub async fn upload_file<T: DriveService + ?Sized>(
+    drive_service: &T,
+    token: &str,
+    metadata: &UploadMetadata,
+    head: Vec<u8>,
+    mut chunks: mpsc::Receiver<Result<Bytes, PartError>>,
+    config: &Config,
+) -> UploadResult {
+    let mut result = UploadResult {
+        file_name: metadata.name.clone(),
+        mime_type: metadata.mime_type.clone(),
+        file_id: None,
+        md5_checksum: None,
+        sha256_checksum: None,
+        error: None,
+        rejection: None,
+    };
+
+    let session_url = match drive_service.initialize_resumable_upload(token, metadata, config).await {
+        Ok(url) => url,
+        Err(err) => {
+            eprintln!("Failed to initialize upload for '{}': {:?}", metadata.name, err);
+            result.error = Some(format!("Failed to initialize upload: {:#}", err));
+            return result;
+        }
+    };
+
+    let outcome: Result<(UploadedFile, FileDigest)> = async {
+        let mut upload = ResumableUpload::new(drive_service, token, session_url.clone(), config.upload_chunk_size);
+        let mut checksums = Checksums::new();
+        checksums.update(&head);
+        upload.write(&head).await?;
+        while let Some(chunk) = chunks.recv().await {
+            match chunk {
+                Ok(data) => {
+                    checksums.update(&data);
+                    upload.write(&data).await?;
+                }
+                Err(PartError::Read(err)) => return Err(anyhow!(err)),
+                Err(PartError::Rejected(rejection)) => {
+                    result.rejection = Some(rejection.clone());
+                    return Err(anyhow!(rejection));
+                }
+            }
+        }
+        Ok((upload.finish().await?, checksums.finish()))
+    }
+    .await;
+
+    match outcome {
+        Ok((file, digest)) => {
+            result.md5_checksum = Some(digest.md5_hex());
+            result.sha256_checksum = Some(digest.sha256_hex());
+            match verify_stored_md5(file.md5_checksum.as_deref(), &digest) {
+                Ok(()) => result.file_id = Some(file.file_id),
+                Err(mismatch) => {
+                    eprintln!("Error uploading '{}': {}", metadata.name, mismatch);
+                    match drive_service.delete_file(token, &file.file_id, config).await {
+                        Ok(()) => result.error = Some(mismatch),
+                        Err(err) => {
+                            // The file stays reported, so the caller can remove it.
+                            eprintln!("Failed to delete '{}' after a checksum mismatch: {:?}", metadata.name, err);
+                            result.file_id = Some(file.file_id);
+                            result.error = Some(format!("{}; the file could not be deleted", mismatch));
+                        }
+                    }
+                }
+            }
+        }
+        Err(err) => {
+            eprintln!("Error uploading '{}': {:?}", metadata.name, err);
+            if let Err(cancel_err) = drive_service.cancel_upload(token, &session_url).await {
+                eprintln!("Failed to cancel upload for '{}': {:?}", metadata.name, cancel_err);
+            }
+            result.error = Some(format!("{:#}", err));
+        }
+    }
+
+    result
+}
@@ -0,0 +1,127 @@
+use rand::RngCore;
+
+/// Ranges of one `Range` header beyond which the header is ignored and the
+/// whole file is served, so a client cannot split a download into thousands
+/// of Drive requests.
+pub const MAX_RANGES: usize = 16;
+
+/// An inclusive byte range that lies within the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    pub fn size(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// The `Content-Range` value for this range of a `total`-byte file.
+    pub fn content_range(&self, total: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, total)
+    }
+}
+
+/// What a `Range` header asks for, resolved against the file size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// The header is malformed, uses another unit or asks for too many
+    /// ranges; it is ignored and the whole file is served.
+    Full,
+    /// The satisfiable ranges, in the order they were requested.
+    Partial(Vec<ByteRange>),
+    /// No range overlaps the file: `416 Range Not Satisfiable`.
+    Unsatisfiable,
+}
+
+/// Resolves a `Range` header such as `bytes=0-499,-500` against a file of
+/// `total` bytes, following RFC 9110 §14.1.2: `a-b` is clamped to the end of
+/// the file, `a-` runs to the end and `-n` is the last `n` bytes. Ranges
+/// that start past the end are dropped.
+pub fn parse_range(header: &str, total: u64) -> RangeRequest {
+    let Some(specs) = header.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Full;
+    };
+
+    let mut ranges = Vec::new();
+    for spec in specs.split(',').map(str::trim).filter(|spec| !spec.is_empty()) {
+        let Some((first, last)) = spec.split_once('-') else {
+            return RangeRequest::Full;
+        };
+        let parse = |value: &str| value.trim().parse::<u64>().ok();
+
+        let range = match (first.trim().is_empty(), last.trim().is_empty()) {
+            (false, false) => match (parse(first), parse(last)) {
+                (Some(start), Some(end)) if start <= end => resolve(start, Some(end), total),
+                _ => return RangeRequest::Full,
+            },
+            (false, true) => match parse(first) {
+                Some(start) => resolve(start, None, total),
+                None => return RangeRequest::Full,
+            },
+            (true, false) => match parse(last) {
+                Some(0) => None,
+                Some(suffix) => resolve(total.saturating_sub(suffix), None, total),
+                None => return RangeRequest::Full,
+            },
+            (true, true) => return RangeRequest::Full,
+        };
+
+        ranges.extend(range);
+        if ranges.len() > MAX_RANGES {
+            return RangeRequest::Full;
+        }
+    }
+
+    if ranges.is_empty() {
+        // `bytes=` with no ranges at all is malformed rather than unsatisfiable.
+        if specs.trim().is_empty() {
+            RangeRequest::Full
+        } else {
+            RangeRequest::Unsatisfiable
+        }
+    } else {
+        RangeRequest::Partial(ranges)
+    }
+}
+
+fn resolve(start: u64, end: Option<u64>, total: u64) -> Option<ByteRange> {
+    if start >= total {
+        return None;
+    }
+    let last = total - 1;
+    Some(ByteRange { start, end: end.map_or(last, |end| end.min(last)) })
+}
+
+/// A random boundary for a `multipart/byteranges` body.
+pub fn new_boundary() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Header of one part of a `multipart/byteranges` body.
+pub fn part_header(boundary: &str, content_type: &str, range: &ByteRange, total: u64) -> String {
+    format!(
+        "--{}\r\nContent-Type: {}\r\nContent-Range: {}\r\n\r\n",
+        boundary,
+        content_type,
+        range.content_range(total)
+    )
+}
+
+/// Closes a `multipart/byteranges` body.
+pub fn closing_boundary(boundary: &str) -> String {
+    format!("--{}--\r\n", boundary)
+}
+
+/// Exact length of a `multipart/byteranges` body, so it can be sent with
+/// `Content-Length` instead of chunked encoding.
+pub fn multipart_length(boundary: &str, content_type: &str, ranges: &[ByteRange], total: u64) -> u64 {
+    let parts: u64 = ranges
+        .iter()
+        .map(|range| part_header(boundary, content_type, range, total).len() as u64 + range.size() + 2)
+        .sum();
+    parts + closing_boundary(boundary).len() as u64
+}
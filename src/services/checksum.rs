@@ -0,0 +1,109 @@
+use actix_web::web::Bytes;
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::stream::{BoxStream, StreamExt};
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+/// MD5 and SHA-256 of a file, computed as its bytes stream past.
+#[derive(Clone, Default)]
+pub struct Checksums {
+    md5: Md5,
+    sha256: Sha256,
+    size: u64,
+}
+
+impl Checksums {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.md5.update(data);
+        self.sha256.update(data);
+        self.size += data.len() as u64;
+    }
+
+    pub fn finish(self) -> FileDigest {
+        FileDigest {
+            md5: self.md5.finalize().into(),
+            sha256: self.sha256.finalize().into(),
+            size: self.size,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileDigest {
+    pub md5: [u8; 16],
+    pub sha256: [u8; 32],
+    pub size: u64,
+}
+
+impl FileDigest {
+    /// Lowercase hex, the format of Drive's `md5Checksum`.
+    pub fn md5_hex(&self) -> String {
+        to_hex(&self.md5)
+    }
+
+    /// Lowercase hex, the format of Drive's `sha256Checksum`.
+    pub fn sha256_hex(&self) -> String {
+        to_hex(&self.sha256)
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok()).collect()
+}
+
+/// Whether two hex checksums are equal, ignoring case.
+pub fn checksums_match(expected: &str, actual: &str) -> bool {
+    expected.trim().eq_ignore_ascii_case(actual.trim())
+}
+
+/// Checks the MD5 Drive reports for a file it stored against the one computed
+/// while sending it. Without a checksum from Drive the content is unverified,
+/// which counts as a failure too.
+pub fn verify_stored_md5(stored: Option<&str>, sent: &FileDigest) -> Result<(), String> {
+    match stored {
+        Some(stored) if checksums_match(stored, &sent.md5_hex()) => Ok(()),
+        Some(stored) => Err(format!("Checksum mismatch: sent MD5 {}, Drive stored {}", sent.md5_hex(), stored)),
+        None => Err(format!("Checksum mismatch: sent MD5 {}, Drive reported none", sent.md5_hex())),
+    }
+}
+
+/// Converts a hex checksum, as Drive reports it, to the base64 used by the
+/// `Content-MD5` and `Digest` headers.
+pub fn hex_to_base64(hex: &str) -> Option<String> {
+    from_hex(hex.trim()).map(|bytes| STANDARD.encode(bytes))
+}
+
+/// The RFC 3230 `Digest` header value for the checksums Drive knows.
+pub fn digest_header(md5_hex: Option<&str>, sha256_hex: Option<&str>) -> Option<String> {
+    let digests: Vec<String> = [("md5", md5_hex), ("sha-256", sha256_hex)]
+        .into_iter()
+        .filter_map(|(algorithm, hex)| Some(format!("{}={}", algorithm, hex_to_base64(hex?)?)))
+        .collect();
+    (!digests.is_empty()).then(|| digests.join(","))
+}
+
+/// Reads a download to the end and returns the checksums of its content.
+pub async fn digest_stream(mut body: BoxStream<'static, Result<Bytes>>) -> Result<FileDigest> {
+    let mut checksums = Checksums::new();
+    while let Some(chunk) = body.next().await {
+        checksums.update(&chunk?);
+    }
+    Ok(checksums.finish())
+}
+
+/// Whether `value` is a hex checksum of `len` bytes.
+pub fn is_hex_checksum(value: &str, len: usize) -> bool {
+    value.len() == 2 * len && value.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::api::google_drive::{CopyMetadata, FolderMetadata};
+use crate::config::Config;
+use crate::services::folder_tree::{walk_tree, TreeNode, MAX_DEPTH_LIMIT};
+use crate::services::google_drive_service::DriveService;
+
+/// File copies allowed in flight at once within one folder.
+pub const COPY_CONCURRENCY: usize = 4;
+
+/// An item of the source tree that was not copied.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CopyFailure {
+    pub source_id: Option<String>,
+    pub name: Option<String>,
+    pub error: String,
+}
+
+/// The new top-level item of a copy, with what was copied beneath it.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CopyResult {
+    pub id: String,
+    pub name: Option<String>,
+    pub mime_type: Option<String>,
+    /// Folders created, including the top-level one for a folder copy.
+    pub folders_copied: usize,
+    pub files_copied: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<CopyFailure>,
+}
+
+impl CopyFailure {
+    fn new(node: &TreeNode, error: impl Into<String>) -> Self {
+        CopyFailure { source_id: node.id.clone(), name: node.name.clone(), error: error.into() }
+    }
+}
+
+/// Rebuilds the subtree under `source_id` as a new folder called `name` in
+/// `target_parent_id`: folders are recreated and files copied on Drive's
+/// side, so no content passes through this server.
+///
+/// Only creating the top-level folder is fatal. Anything else that cannot
+/// be copied, including folders beyond the depth limit or reached through
+/// a second parent, is reported in `failures` and the copy carries on.
+pub async fn copy_folder<T: DriveService + ?Sized>(
+    drive_service: &T,
+    token: &str,
+    source_id: &str,
+    name: &str,
+    target_parent_id: &str,
+    config: &Config,
+) -> Result<CopyResult> {
+    let tree = walk_tree(drive_service, token, source_id, MAX_DEPTH_LIMIT, COPY_CONCURRENCY, config).await;
+    if let Some(err) = &tree.error {
+        return Err(anyhow::anyhow!("{}", err)).context("Failed to list the folder to copy");
+    }
+
+    let mut root = FolderMetadata::new(name);
+    root.parent_id = Some(target_parent_id.to_string());
+    let created = drive_service.create_folder(token, &root, config).await.context("Failed to create the folder copy")?;
+    let root_id = created.id.context("Drive returned no ID for the folder copy")?;
+
+    let mut result = CopyResult {
+        id: root_id.clone(),
+        name: created.name,
+        mime_type: tree.mime_type.clone(),
+        folders_copied: 1,
+        files_copied: 0,
+        failures: Vec::new(),
+    };
+
+    let mut pending = vec![(&tree, root_id)];
+    while let Some((folder, copy_id)) = pending.pop() {
+        let (folders, files): (Vec<&TreeNode>, Vec<&TreeNode>) = folder.children.iter().partition(|child| child.is_folder);
+
+        let copies: Vec<_> = stream::iter(files)
+            .map(|file| {
+                let copy = CopyMetadata { name: file.name.clone(), parent_id: Some(copy_id.clone()) };
+                async move {
+                    let source_id = file.id.as_deref().unwrap_or_default();
+                    (file, drive_service.copy_file(token, source_id, &copy, config).await)
+                }
+            })
+            .buffer_unordered(COPY_CONCURRENCY)
+            .collect()
+            .await;
+        for (file, copied) in copies {
+            match copied {
+                Ok(_) => result.files_copied += 1,
+                Err(err) => result.failures.push(CopyFailure::new(file, format!("{:#}", err))),
+            }
+        }
+
+        for child in folders {
+            if child.cycle {
+                result.failures.push(CopyFailure::new(child, "Folder already copied through another parent"));
+                continue;
+            }
+            if child.truncated {
+                result.failures.push(CopyFailure::new(child, format!("Folder is deeper than {} levels", MAX_DEPTH_LIMIT)));
+                continue;
+            }
+            if let Some(err) = &child.error {
+                result.failures.push(CopyFailure::new(child, err.clone()));
+                continue;
+            }
+
+            let mut folder = FolderMetadata::new(child.name.clone().unwrap_or_default());
+            folder.parent_id = Some(copy_id.clone());
+            match drive_service.create_folder(token, &folder, config).await {
+                Ok(created) => match created.id {
+                    Some(id) => {
+                        result.folders_copied += 1;
+                        pending.push((child, id));
+                    }
+                    None => result.failures.push(CopyFailure::new(child, "Drive returned no ID for the folder copy")),
+                },
+                Err(err) => result.failures.push(CopyFailure::new(child, format!("{:#}", err))),
+            }
+        }
+    }
+
+    Ok(result)
+}
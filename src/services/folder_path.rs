@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::api::drive_query::DriveQuery;
+use crate::api::google_drive::FolderMetadata;
+use crate::config::Config;
+use crate::services::google_drive_service::{DriveService, PageRequest};
+
+/// Deepest path a single request may resolve.
+pub const MAX_PATH_SEGMENTS: usize = 20;
+
+/// One folder along a resolved path.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PathSegment {
+    pub id: String,
+    pub name: String,
+    /// The folder did not exist and was created by this request.
+    pub created: bool,
+}
+
+/// Splits `Invoices/2026/Q3` into its folder names. Empty segments, such as
+/// a leading or doubled `/`, are ignored.
+pub fn parse_folder_path(path: &str) -> Result<Vec<String>, String> {
+    let segments: Vec<String> = path
+        .split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    if segments.is_empty() {
+        return Err("path must contain at least one folder name".to_string());
+    }
+    if segments.len() > MAX_PATH_SEGMENTS {
+        return Err(format!("path must have at most {} folders", MAX_PATH_SEGMENTS));
+    }
+    if segments.iter().any(|segment| segment == "." || segment == "..") {
+        return Err("path must not contain '.' or '..'".to_string());
+    }
+    Ok(segments)
+}
+
+/// Resolves each segment under `parent_id`, creating the ones that do not
+/// exist yet, like `mkdir -p`. `app_properties` are only set on the last
+/// folder, and only if this call creates it.
+///
+/// Drive allows several folders with the same name in one parent; the first
+/// match is used. Two concurrent calls for a new path may both create it.
+pub async fn ensure_folder_path<T: DriveService + ?Sized>(
+    drive_service: &T,
+    token: &str,
+    parent_id: &str,
+    segments: &[String],
+    app_properties: &BTreeMap<String, String>,
+    config: &Config,
+) -> Result<Vec<PathSegment>> {
+    let mut resolved = Vec::with_capacity(segments.len());
+    let mut parent = parent_id.to_string();
+    let page = PageRequest { page_size: Some(1), ..PageRequest::default() };
+
+    for (index, name) in segments.iter().enumerate() {
+        // Once a folder had to be created, nothing below it can exist yet.
+        let existing = if resolved.last().is_some_and(|segment: &PathSegment| segment.created) {
+            None
+        } else {
+            let query = DriveQuery::new().folders_only().in_parents(&parent).name_equals(name).trashed(false);
+            drive_service
+                .search_files(token, &query, &page, config)
+                .await
+                .with_context(|| format!("Failed to look up folder '{}'", name))?
+                .files
+                .into_iter()
+                .find_map(|file| file.id)
+        };
+
+        let segment = match existing {
+            Some(id) => PathSegment { id, name: name.clone(), created: false },
+            None => {
+                let mut folder = FolderMetadata::new(name.clone());
+                folder.parent_id = Some(parent.clone());
+                if index == segments.len() - 1 {
+                    folder.app_properties = app_properties.clone();
+                }
+                let created = drive_service.create_folder(token, &folder, config).await?;
+                let id = created.id.with_context(|| format!("Drive returned no ID for folder '{}'", name))?;
+                PathSegment { id, name: name.clone(), created: true }
+            }
+        };
+
+        parent = segment.id.clone();
+        resolved.push(segment);
+    }
+
+    Ok(resolved)
+}
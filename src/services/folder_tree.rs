@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::api::drive_query::FOLDER_MIME_TYPE;
+use crate::config::Config;
+use crate::services::google_drive_service::{DriveService, FileInfo, PageRequest};
+
+pub const DEFAULT_MAX_DEPTH: u32 = 5;
+pub const MAX_DEPTH_LIMIT: u32 = 20;
+/// Folder listings allowed in flight at once while walking a tree.
+pub const TREE_CONCURRENCY: usize = 8;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TreeNode {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub mime_type: Option<String>,
+    pub is_folder: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(no_recursion)]
+    pub children: Vec<TreeNode>,
+    /// The folder sits at the depth limit and was not listed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// The folder was already reached through another parent and is not expanded again.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cycle: bool,
+    /// Listing this folder failed; the rest of the tree is still returned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl TreeNode {
+    fn from_file(file: &FileInfo) -> Self {
+        TreeNode {
+            id: file.id.clone(),
+            name: file.name.clone(),
+            mime_type: file.mime_type.clone(),
+            is_folder: is_folder(file),
+            children: Vec::new(),
+            truncated: false,
+            cycle: false,
+            error: None,
+        }
+    }
+}
+
+fn is_folder(file: &FileInfo) -> bool {
+    file.mime_type.as_deref() == Some(FOLDER_MIME_TYPE)
+}
+
+/// Result of the breadth-first walk, before it is assembled into a tree.
+struct Walk {
+    listings: HashMap<String, Result<Vec<FileInfo>, String>>,
+    /// Parent through which each folder was first reached.
+    discovered_by: HashMap<String, String>,
+}
+
+/// Walks the subtree under `root_id`, one level at a time, listing at most
+/// `concurrency` folders concurrently and stopping at `max_depth` levels.
+///
+/// A folder reached through more than one parent (or pointing back at an
+/// ancestor) is expanded once and marked as a `cycle` elsewhere. Folders
+/// that fail to list carry their error and do not abort the walk.
+pub async fn walk_tree<T: DriveService + ?Sized>(
+    drive_service: &T,
+    token: &str,
+    root_id: &str,
+    max_depth: u32,
+    concurrency: usize,
+    config: &Config,
+) -> TreeNode {
+    let page = PageRequest { all: true, ..PageRequest::default() };
+    let mut walk = Walk { listings: HashMap::new(), discovered_by: HashMap::new() };
+    let mut frontier = vec![root_id.to_string()];
+
+    for _ in 0..max_depth {
+        if frontier.is_empty() {
+            break;
+        }
+
+        let results: Vec<_> = stream::iter(std::mem::take(&mut frontier))
+            .map(|folder_id| {
+                let page = &page;
+                async move {
+                    let result = drive_service.list_files_in_folder(token, &folder_id, page, config).await;
+                    (folder_id, result)
+                }
+            })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await;
+
+        for (folder_id, result) in results {
+            match result {
+                Ok(listing) => {
+                    for child in listing.files.iter().filter(|file| is_folder(file)) {
+                        if let Some(child_id) = &child.id {
+                            if child_id != root_id && !walk.discovered_by.contains_key(child_id) {
+                                walk.discovered_by.insert(child_id.clone(), folder_id.clone());
+                                frontier.push(child_id.clone());
+                            }
+                        }
+                    }
+                    walk.listings.insert(folder_id, Ok(listing.files));
+                }
+                Err(err) => {
+                    walk.listings.insert(folder_id, Err(format!("{:#}", err)));
+                }
+            }
+        }
+    }
+
+    let mut root = TreeNode {
+        id: Some(root_id.to_string()),
+        name: None,
+        mime_type: Some(FOLDER_MIME_TYPE.to_string()),
+        is_folder: true,
+        children: Vec::new(),
+        truncated: false,
+        cycle: false,
+        error: None,
+    };
+    expand(&mut root, root_id, &walk);
+    root
+}
+
+fn expand(node: &mut TreeNode, folder_id: &str, walk: &Walk) {
+    match walk.listings.get(folder_id) {
+        Some(Ok(files)) => {
+            node.children = files
+                .iter()
+                .map(|file| {
+                    let mut child = TreeNode::from_file(file);
+                    if !child.is_folder {
+                        return child;
+                    }
+                    match file.id.as_deref() {
+                        Some(child_id) if walk.discovered_by.get(child_id).map(String::as_str) == Some(folder_id) => {
+                            expand(&mut child, child_id, walk);
+                        }
+                        _ => child.cycle = true,
+                    }
+                    child
+                })
+                .collect();
+        }
+        Some(Err(err)) => node.error = Some(err.clone()),
+        None => node.truncated = true,
+    }
+}
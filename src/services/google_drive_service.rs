@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
-use crate::api::google_drive::{download_pdf, list_files_from_folder, list_folders, upload_pdf_file, initialize_resumable_upload};
+use crate::api::drive_query::DriveQuery;
+use crate::api::file_metadata::{FieldSelection, FileMetadata};
+use crate::api::permissions::{create_permission, delete_permission, list_permissions, update_permission, NewPermission, Permission, PermissionOptions, PermissionUpdate};
+use crate::api::google_drive::{copy_file, create_folder, delete_file, empty_trash, set_trashed, update_file, download_file, export_file, get_download_metadata, get_file_metadata, list_all_file_metadata, list_file_metadata, list_all_files_from_folder, list_all_folders, list_files_from_folder, list_folders, search_all_files, search_files, upload_chunk, initialize_resumable_upload, cancel_resumable_upload, ChunkStatus, DownloadMetadata, DownloadStream, File, FileList, FileUpdate, CopyMetadata, FolderMetadata, UploadMetadata};
 use crate::config::Config;
 use anyhow::{Result, Context};
 use std::future::Future;
@@ -20,42 +23,256 @@ pub struct FileInfo {
     pub created_time: Option<String>,
 }
 
+/// Pagination options shared by the listing endpoints.
+#[derive(Debug, Default, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageRequest {
+    /// Maximum number of items per page (1-1000).
+    pub page_size: Option<u32>,
+    /// `next_page_token` returned by a previous call.
+    pub page_token: Option<String>,
+    /// Follow every page server-side and return the complete listing.
+    #[serde(default)]
+    pub all: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct FolderPage {
+    pub folders: Vec<FolderInfo>,
+    pub next_page_token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct FilePage {
+    pub files: Vec<FileInfo>,
+    pub next_page_token: Option<String>,
+}
+
+/// A page of files with the metadata fields selected by `fields=`.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct FileMetadataPage {
+    pub files: Vec<FileMetadata>,
+    pub next_page_token: Option<String>,
+}
+
+impl From<File> for FolderInfo {
+    fn from(folder: File) -> Self {
+        FolderInfo {
+            id: folder.id,
+            name: folder.name,
+        }
+    }
+}
+
+impl From<File> for FileInfo {
+    fn from(file: File) -> Self {
+        FileInfo {
+            id: file.id,
+            name: file.name,
+            mime_type: file.mime_type,
+            created_time: file.created_time,
+        }
+    }
+}
+
+impl From<FileList> for FolderPage {
+    fn from(list: FileList) -> Self {
+        FolderPage {
+            folders: list.files.into_iter().map(FolderInfo::from).collect(),
+            next_page_token: list.next_page_token,
+        }
+    }
+}
+
+impl From<FileList> for FilePage {
+    fn from(list: FileList) -> Self {
+        FilePage {
+            files: list.files.into_iter().map(FileInfo::from).collect(),
+            next_page_token: list.next_page_token,
+        }
+    }
+}
+
 pub trait DriveService {
     fn list_folders<'a>(
         &'a self,
         token: &'a str,
+        page: &'a PageRequest,
         config: &'a Config
-    ) -> Pin<Box<dyn Future<Output = Result<Vec<FolderInfo>>> + Send + 'a>>;
+    ) -> Pin<Box<dyn Future<Output = Result<FolderPage>> + Send + 'a>>;
 
     fn list_files_in_folder<'a>(
         &'a self,
         token: &'a str,
         folder_id: &'a str,
+        page: &'a PageRequest,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<FilePage>> + Send + 'a>>;
+
+    fn search_files<'a>(
+        &'a self,
+        token: &'a str,
+        query: &'a DriveQuery,
+        page: &'a PageRequest,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<FilePage>> + Send + 'a>>;
+
+    /// Lists the files matching `query` with only the selected metadata fields.
+    fn list_file_metadata<'a>(
+        &'a self,
+        token: &'a str,
+        query: &'a DriveQuery,
+        fields: &'a FieldSelection,
+        page: &'a PageRequest,
         config: &'a Config
-    ) -> Pin<Box<dyn Future<Output = Result<Vec<FileInfo>>> + Send + 'a>>;
+    ) -> Pin<Box<dyn Future<Output = Result<FileMetadataPage>> + Send + 'a>>;
 
-    fn download_pdf<'a>(
+    fn get_file_metadata<'a>(
         &'a self,
         token: &'a str,
         file_id: &'a str,
+        fields: &'a FieldSelection,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<FileMetadata>> + Send + 'a>>;
+
+    fn create_folder<'a>(
+        &'a self,
+        token: &'a str,
+        folder: &'a FolderMetadata,
         config: &'a Config
-    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>>> + Send + 'a>>;
+    ) -> Pin<Box<dyn Future<Output = Result<FolderInfo>> + Send + 'a>>;
 
-    fn upload_pdf<'a>(
+    /// Renames a file and/or moves it between parents.
+    fn update_file<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        update: &'a FileUpdate,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<FileInfo>> + Send + 'a>>;
+
+    /// Copies a single file; folders are copied with `folder_copy::copy_folder`.
+    fn copy_file<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        copy: &'a CopyMetadata,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<FileInfo>> + Send + 'a>>;
+
+    /// Moves a file to the trash, from where it can still be restored.
+    fn trash_file<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<FileInfo>> + Send + 'a>>;
+
+    fn restore_file<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<FileInfo>> + Send + 'a>>;
+
+    /// Deletes a file permanently, skipping the trash.
+    fn delete_file<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+    fn empty_trash<'a>(
+        &'a self,
+        token: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+    /// Lists who the file is shared with.
+    fn list_permissions<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<Permission>>> + Send + 'a>>;
+
+    /// Shares the file; `options` carry the notification email and
+    /// ownership transfer.
+    fn create_permission<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        permission: &'a NewPermission,
+        options: &'a PermissionOptions,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<Permission>> + Send + 'a>>;
+
+    fn update_permission<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        permission_id: &'a str,
+        update: &'a PermissionUpdate,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<Permission>> + Send + 'a>>;
+
+    fn delete_permission<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        permission_id: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+    fn get_download_metadata<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<DownloadMetadata>> + Send + 'a>>;
+
+    /// Opens a streaming download of the whole file, or of the inclusive
+    /// byte `range`; the body is not buffered in memory.
+    fn download_file<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        range: Option<(u64, u64)>,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<DownloadStream>> + Send + 'a>>;
+
+    /// Opens a streaming export of a Google Workspace document to `mime_type`.
+    fn export_file<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        mime_type: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<DownloadStream>> + Send + 'a>>;
+
+    /// Sends one chunk of a resumable upload; `total` is only known for the last chunk.
+    fn upload_chunk<'a>(
         &'a self,
         token: &'a str,
         resumable_url: &'a str,
-        file_content: Vec<u8>,
-        start_byte: Option<u64>
-    ) -> Pin<Box<dyn Future<Output = Result<String>> + Send + 'a>>;
+        chunk: Vec<u8>,
+        start: u64,
+        total: Option<u64>
+    ) -> Pin<Box<dyn Future<Output = Result<ChunkStatus>> + Send + 'a>>;
 
     fn initialize_resumable_upload<'a>(
         &'a self,
         token: &'a str,
-        folder_id: &'a str,
-        file_name: &'a str,
+        metadata: &'a UploadMetadata,
         config: &'a Config,
     ) -> Pin<Box<dyn Future<Output = Result<String>> + Send + 'a>>;
+
+    fn cancel_upload<'a>(
+        &'a self,
+        token: &'a str,
+        resumable_url: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
 }
 
 pub struct GoogleDriveService;
@@ -64,21 +281,25 @@ impl DriveService for GoogleDriveService {
     fn list_folders<'a>(
         &'a self,
         token: &'a str,
+        page: &'a PageRequest,
         config: &'a Config
-    ) -> Pin<Box<dyn Future<Output = Result<Vec<FolderInfo>>> + Send + 'a>> {
+    ) -> Pin<Box<dyn Future<Output = Result<FolderPage>> + Send + 'a>> {
         Box::pin(async move {
-            list_folders(token, config)
-                .await
-                .with_context(|| "Failed to list folders")
-                .map(|folders| {
-                    folders
-                        .into_iter()
-                        .map(|folder| FolderInfo {
-                            id: folder.id,
-                            name: folder.name,
-                        })
-                        .collect()
+            if page.all {
+                let folders = list_all_folders(token, page.page_size, config)
+                    .await
+                    .with_context(|| "Failed to list folders")?;
+
+                Ok(FolderPage {
+                    folders: folders.into_iter().map(FolderInfo::from).collect(),
+                    next_page_token: None,
                 })
+            } else {
+                list_folders(token, page.page_size, page.page_token.as_deref(), config)
+                    .await
+                    .with_context(|| "Failed to list folders")
+                    .map(FolderPage::from)
+            }
         })
     }
 
@@ -86,48 +307,297 @@ impl DriveService for GoogleDriveService {
         &'a self,
         token: &'a str,
         folder_id: &'a str,
+        page: &'a PageRequest,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<FilePage>> + Send + 'a>> {
+        Box::pin(async move {
+            if page.all {
+                let files = list_all_files_from_folder(token, folder_id, page.page_size, config)
+                    .await
+                    .with_context(|| format!("Failed to list files in folder: {}", folder_id))?;
+
+                Ok(FilePage {
+                    files: files.into_iter().map(FileInfo::from).collect(),
+                    next_page_token: None,
+                })
+            } else {
+                list_files_from_folder(token, folder_id, page.page_size, page.page_token.as_deref(), config)
+                    .await
+                    .with_context(|| format!("Failed to list files in folder: {}", folder_id))
+                    .map(FilePage::from)
+            }
+        })
+    }
+
+    fn search_files<'a>(
+        &'a self,
+        token: &'a str,
+        query: &'a DriveQuery,
+        page: &'a PageRequest,
         config: &'a Config
-    ) -> Pin<Box<dyn Future<Output = Result<Vec<FileInfo>>> + Send + 'a>> {
-        Box::pin(async move {
-            list_files_from_folder(token, folder_id, config)
-                .await
-                .with_context(|| format!("Failed to list files in folder: {}", folder_id))
-                .map(|files| {
-                    files
-                        .into_iter()
-                        .map(|file| FileInfo {
-                            id: file.id,
-                            name: file.name,
-                            mime_type: file.mime_type,
-                            created_time: file.created_time,
-                        })
-                        .collect()
+    ) -> Pin<Box<dyn Future<Output = Result<FilePage>> + Send + 'a>> {
+        Box::pin(async move {
+            if page.all {
+                let files = search_all_files(token, query, page.page_size, config)
+                    .await
+                    .with_context(|| format!("Failed to search files with query: {}", query.build()))?;
+
+                Ok(FilePage {
+                    files: files.into_iter().map(FileInfo::from).collect(),
+                    next_page_token: None,
                 })
+            } else {
+                search_files(token, query, page.page_size, page.page_token.as_deref(), config)
+                    .await
+                    .with_context(|| format!("Failed to search files with query: {}", query.build()))
+                    .map(FilePage::from)
+            }
+        })
+    }
+
+    fn list_file_metadata<'a>(
+        &'a self,
+        token: &'a str,
+        query: &'a DriveQuery,
+        fields: &'a FieldSelection,
+        page: &'a PageRequest,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<FileMetadataPage>> + Send + 'a>> {
+        Box::pin(async move {
+            if page.all {
+                let files = list_all_file_metadata(token, query, fields, page.page_size, config)
+                    .await
+                    .with_context(|| format!("Failed to list files with query: {}", query.build()))?;
+
+                Ok(FileMetadataPage { files, next_page_token: None })
+            } else {
+                list_file_metadata(token, query, fields, page.page_size, page.page_token.as_deref(), config)
+                    .await
+                    .with_context(|| format!("Failed to list files with query: {}", query.build()))
+                    .map(|list| FileMetadataPage { files: list.files, next_page_token: list.next_page_token })
+            }
         })
     }
 
-    fn download_pdf<'a>(
+    fn get_file_metadata<'a>(
         &'a self,
         token: &'a str,
         file_id: &'a str,
+        fields: &'a FieldSelection,
         config: &'a Config
-    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>>> + Send + 'a>> {
+    ) -> Pin<Box<dyn Future<Output = Result<FileMetadata>> + Send + 'a>> {
         Box::pin(async move {
-            download_pdf(token, file_id, config)
+            get_file_metadata(token, file_id, fields, config)
                 .await
-                .with_context(|| format!("Failed to download PDF with ID: {}", file_id))
+                .with_context(|| format!("Failed to get metadata of file with ID: {}", file_id))
         })
     }
 
-    fn upload_pdf<'a>(
+    fn create_folder<'a>(
+        &'a self,
+        token: &'a str,
+        folder: &'a FolderMetadata,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<FolderInfo>> + Send + 'a>> {
+        Box::pin(async move {
+            create_folder(token, folder, config)
+                .await
+                .with_context(|| format!("Failed to create folder: {}", folder.name))
+                .map(FolderInfo::from)
+        })
+    }
+
+    fn update_file<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        update: &'a FileUpdate,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<FileInfo>> + Send + 'a>> {
+        Box::pin(async move {
+            update_file(token, file_id, update, config)
+                .await
+                .with_context(|| format!("Failed to update file with ID: {}", file_id))
+                .map(FileInfo::from)
+        })
+    }
+
+    fn copy_file<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        copy: &'a CopyMetadata,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<FileInfo>> + Send + 'a>> {
+        Box::pin(async move {
+            copy_file(token, file_id, copy, config)
+                .await
+                .with_context(|| format!("Failed to copy file with ID: {}", file_id))
+                .map(FileInfo::from)
+        })
+    }
+
+    fn trash_file<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<FileInfo>> + Send + 'a>> {
+        Box::pin(async move {
+            set_trashed(token, file_id, true, config)
+                .await
+                .with_context(|| format!("Failed to trash file with ID: {}", file_id))
+                .map(FileInfo::from)
+        })
+    }
+
+    fn restore_file<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<FileInfo>> + Send + 'a>> {
+        Box::pin(async move {
+            set_trashed(token, file_id, false, config)
+                .await
+                .with_context(|| format!("Failed to restore file with ID: {}", file_id))
+                .map(FileInfo::from)
+        })
+    }
+
+    fn delete_file<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            delete_file(token, file_id, config)
+                .await
+                .with_context(|| format!("Failed to delete file with ID: {}", file_id))
+        })
+    }
+
+    fn empty_trash<'a>(
+        &'a self,
+        token: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            empty_trash(token, config)
+                .await
+                .context("Failed to empty the trash")
+        })
+    }
+
+    fn list_permissions<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<Permission>>> + Send + 'a>> {
+        Box::pin(async move {
+            list_permissions(token, file_id, config)
+                .await
+                .with_context(|| format!("Failed to list permissions of file with ID: {}", file_id))
+        })
+    }
+
+    fn create_permission<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        permission: &'a NewPermission,
+        options: &'a PermissionOptions,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<Permission>> + Send + 'a>> {
+        Box::pin(async move {
+            create_permission(token, file_id, permission, options, config)
+                .await
+                .with_context(|| format!("Failed to share file with ID: {}", file_id))
+        })
+    }
+
+    fn update_permission<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        permission_id: &'a str,
+        update: &'a PermissionUpdate,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<Permission>> + Send + 'a>> {
+        Box::pin(async move {
+            update_permission(token, file_id, permission_id, update, config)
+                .await
+                .with_context(|| format!("Failed to update permission {} of file with ID: {}", permission_id, file_id))
+        })
+    }
+
+    fn delete_permission<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        permission_id: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            delete_permission(token, file_id, permission_id, config)
+                .await
+                .with_context(|| format!("Failed to delete permission {} of file with ID: {}", permission_id, file_id))
+        })
+    }
+
+    fn get_download_metadata<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<DownloadMetadata>> + Send + 'a>> {
+        Box::pin(async move {
+            get_download_metadata(token, file_id, config)
+                .await
+                .with_context(|| format!("Failed to get metadata of file with ID: {}", file_id))
+        })
+    }
+
+    fn download_file<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        range: Option<(u64, u64)>,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<DownloadStream>> + Send + 'a>> {
+        Box::pin(async move {
+            download_file(token, file_id, range, config)
+                .await
+                .with_context(|| format!("Failed to download file with ID: {}", file_id))
+        })
+    }
+
+    fn export_file<'a>(
+        &'a self,
+        token: &'a str,
+        file_id: &'a str,
+        mime_type: &'a str,
+        config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<DownloadStream>> + Send + 'a>> {
+        Box::pin(async move {
+            export_file(token, file_id, mime_type, config)
+                .await
+                .with_context(|| format!("Failed to export file with ID: {} to {}", file_id, mime_type))
+        })
+    }
+
+    fn upload_chunk<'a>(
         &'a self,
         token: &'a str,
         resumable_url: &'a str,
-        file_content: Vec<u8>,
-        start_byte: Option<u64>
-    ) -> Pin<Box<dyn Future<Output = Result<String>> + Send + 'a>> {
+        chunk: Vec<u8>,
+        start: u64,
+        total: Option<u64>
+    ) -> Pin<Box<dyn Future<Output = Result<ChunkStatus>> + Send + 'a>> {
         Box::pin(async move {
-            upload_pdf_file(token, resumable_url, file_content, start_byte)
+            upload_chunk(token, resumable_url, chunk, start, total)
                 .await
                 .with_context(|| format!("Failed to upload file chunk to URL: {}", resumable_url))
         })
@@ -136,14 +606,25 @@ impl DriveService for GoogleDriveService {
     fn initialize_resumable_upload<'a>(
         &'a self,
         token: &'a str,
-        folder_id: &'a str,
-        file_name: &'a str,
+        metadata: &'a UploadMetadata,
         config: &'a Config,
     ) -> Pin<Box<dyn Future<Output = Result<String>> + Send + 'a>> {
         Box::pin(async move {
-            initialize_resumable_upload(token, folder_id, file_name, config)
+            initialize_resumable_upload(token, metadata, config)
+                .await
+                .with_context(|| format!("Failed to initialize resumable upload for file: {}", metadata.name))
+        })
+    }
+
+    fn cancel_upload<'a>(
+        &'a self,
+        token: &'a str,
+        resumable_url: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            cancel_resumable_upload(token, resumable_url)
                 .await
-                .with_context(|| format!("Failed to initialize resumable upload for file: {}", file_name))
+                .with_context(|| format!("Failed to cancel resumable upload at URL: {}", resumable_url))
         })
     }
 }
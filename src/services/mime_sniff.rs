@@ -0,0 +1,31 @@
+/// Bytes of a file inspected to guess its type.
+pub const SNIFF_LEN: usize = 512;
+
+/// Guesses a MIME type from the leading bytes of a file.
+///
+/// ZIP containers are refined by extension, since Office documents are ZIP
+/// archives with no distinguishing prefix.
+pub fn sniff_mime_type(bytes: &[u8], file_name: &str) -> Option<&'static str> {
+    let extension = file_name.rsplit_once('.').map(|(_, extension)| extension.to_ascii_lowercase());
+
+    if bytes.starts_with(b"%PDF-") {
+        Some("application/pdf")
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else if bytes.starts_with(b"PK\x03\x04") {
+        match extension.as_deref() {
+            Some("docx") => Some("application/vnd.openxmlformats-officedocument.wordprocessingml.document"),
+            Some("xlsx") => Some("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+            Some("pptx") => Some("application/vnd.openxmlformats-officedocument.presentationml.presentation"),
+            _ => Some("application/zip"),
+        }
+    } else {
+        None
+    }
+}
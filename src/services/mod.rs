@@ -1,2 +1,17 @@
 pub mod auth_service;
-pub mod google_drive_service;
\ No newline at end of file
+pub mod batch_upload;
+pub mod byte_range;
+pub mod checksum;
+pub mod folder_copy;
+pub mod folder_path;
+pub mod folder_tree;
+pub mod google_drive_service;
+pub mod mime_sniff;
+pub mod pkce;
+pub mod resumable_upload;
+pub mod session_store;
+pub mod sqlite_session_store;
+pub mod sqlite_upload_store;
+pub mod upload_progress;
+pub mod upload_store;
+pub mod upload_validation;
\ No newline at end of file
@@ -0,0 +1,24 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+fn random_token(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Generates an unguessable `state` value binding an authorization request to its callback.
+pub fn generate_state() -> String {
+    random_token(32)
+}
+
+/// Generates a PKCE code verifier (RFC 7636, section 4.1): 43 characters from the unreserved set.
+pub fn generate_code_verifier() -> String {
+    random_token(32)
+}
+
+/// Derives the S256 code challenge for a verifier: `BASE64URL(SHA256(verifier))`.
+pub fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
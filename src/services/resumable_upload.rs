@@ -0,0 +1,116 @@
+use anyhow::{bail, Result};
+use crate::api::google_drive::{ChunkStatus, UPLOAD_CHUNK_ALIGNMENT};
+use crate::services::google_drive_service::DriveService;
+
+/// Rounds a configured chunk size up to the 256 KiB multiple Drive requires.
+pub fn aligned_chunk_size(chunk_size: usize) -> usize {
+    chunk_size.max(1).div_ceil(UPLOAD_CHUNK_ALIGNMENT) * UPLOAD_CHUNK_ALIGNMENT
+}
+
+/// The file a completed upload created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadedFile {
+    pub file_id: String,
+    /// Drive's MD5 of the content it stored, in hex.
+    pub md5_checksum: Option<String>,
+}
+
+/// Streams a file into a Drive resumable upload session.
+///
+/// Bytes are buffered into aligned chunks and sent with `Content-Range`.
+/// The total size is only declared on the last chunk, sent by [`finish`](Self::finish).
+/// After each `308 Resume Incomplete` the upload continues from the offset
+/// Drive reports, resending anything it did not persist.
+pub struct ResumableUpload<'a, T: DriveService + ?Sized> {
+    drive_service: &'a T,
+    token: &'a str,
+    session_url: String,
+    chunk_size: usize,
+    buffer: Vec<u8>,
+    offset: u64,
+}
+
+impl<'a, T: DriveService + ?Sized> ResumableUpload<'a, T> {
+    pub fn new(drive_service: &'a T, token: &'a str, session_url: String, chunk_size: usize) -> Self {
+        ResumableUpload {
+            drive_service,
+            token,
+            session_url,
+            chunk_size: aligned_chunk_size(chunk_size),
+            buffer: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Continues a session in which Drive has already persisted `offset` bytes.
+    pub fn resume(drive_service: &'a T, token: &'a str, session_url: String, chunk_size: usize, offset: u64) -> Self {
+        ResumableUpload { offset, ..Self::new(drive_service, token, session_url, chunk_size) }
+    }
+
+    /// Bytes Drive has acknowledged so far.
+    pub fn bytes_uploaded(&self) -> u64 {
+        self.offset
+    }
+
+    pub async fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.buffer.extend_from_slice(data);
+
+        // Hold back at least one byte: until the input ends, any full chunk
+        // might turn out to be the last one, which must carry the total size.
+        while self.buffer.len() > self.chunk_size {
+            let chunk = self.buffer[..self.chunk_size].to_vec();
+            match self.drive_service.upload_chunk(self.token, &self.session_url, chunk, self.offset, None).await? {
+                ChunkStatus::Incomplete { next_offset } => self.advance(next_offset)?,
+                ChunkStatus::Complete { .. } => bail!("Drive completed the upload before the last chunk was sent"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends every whole 256 KiB block still buffered without ending the upload.
+    /// A shorter tail is dropped and has to be sent again by the caller,
+    /// starting from [`bytes_uploaded`](Self::bytes_uploaded).
+    pub async fn flush(mut self) -> Result<u64> {
+        let aligned_len = self.buffer.len() / UPLOAD_CHUNK_ALIGNMENT * UPLOAD_CHUNK_ALIGNMENT;
+
+        if aligned_len > 0 {
+            let chunk = self.buffer[..aligned_len].to_vec();
+            match self.drive_service.upload_chunk(self.token, &self.session_url, chunk, self.offset, None).await? {
+                ChunkStatus::Incomplete { next_offset } => self.advance(next_offset)?,
+                ChunkStatus::Complete { .. } => bail!("Drive completed the upload before the last chunk was sent"),
+            }
+        }
+
+        Ok(self.offset)
+    }
+
+    /// Sends the remaining bytes with the total size and returns the new file.
+    pub async fn finish(mut self) -> Result<UploadedFile> {
+        loop {
+            let total = self.offset + self.buffer.len() as u64;
+            let chunk = self.buffer.clone();
+
+            match self.drive_service.upload_chunk(self.token, &self.session_url, chunk, self.offset, Some(total)).await? {
+                ChunkStatus::Complete { file_id, md5_checksum } => return Ok(UploadedFile { file_id, md5_checksum }),
+                ChunkStatus::Incomplete { next_offset } => self.advance(next_offset)?,
+            }
+        }
+    }
+
+    fn advance(&mut self, next_offset: u64) -> Result<()> {
+        let buffered_end = self.offset + self.buffer.len() as u64;
+        if next_offset <= self.offset || next_offset > buffered_end {
+            bail!(
+                "Resumable upload made no progress: sent bytes {}-{}, Drive acknowledged up to {}",
+                self.offset,
+                buffered_end,
+                next_offset
+            );
+        }
+
+        self.buffer.drain(..(next_offset - self.offset) as usize);
+        self.offset = next_offset;
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use crate::api::auth::TokenResponse;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds before the real expiry at which an access token is treated as expired,
+/// so that a request never reaches Google with a token that dies in flight.
+pub const EXPIRY_SKEW_SECS: u64 = 60;
+
+/// How long a user has to complete the Google consent screen after requesting `/auth`.
+pub const PENDING_LOGIN_TTL_SECS: u64 = 600;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub scope: Option<String>,
+    pub expires_at: u64,
+}
+
+impl Session {
+    pub fn from_token_response(token_response: TokenResponse) -> Self {
+        Session {
+            access_token: token_response.access_token,
+            refresh_token: token_response.refresh_token,
+            scope: token_response.scope,
+            expires_at: now_secs() + token_response.expires_in,
+        }
+    }
+
+    /// Applies a refreshed token, keeping the current refresh token when Google does not rotate it.
+    pub fn refreshed(self, token_response: TokenResponse) -> Self {
+        Session {
+            access_token: token_response.access_token,
+            refresh_token: token_response.refresh_token.or(self.refresh_token),
+            scope: token_response.scope.or(self.scope),
+            expires_at: now_secs() + token_response.expires_in,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        now_secs() + EXPIRY_SKEW_SECS >= self.expires_at
+    }
+
+    pub fn expires_in(&self) -> u64 {
+        self.expires_at.saturating_sub(now_secs())
+    }
+}
+
+/// Authorization request awaiting its callback, keyed by the `state` parameter.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PendingLogin {
+    pub code_verifier: String,
+    pub expires_at: u64,
+}
+
+impl PendingLogin {
+    pub fn new(code_verifier: String) -> Self {
+        PendingLogin {
+            code_verifier,
+            expires_at: now_secs() + PENDING_LOGIN_TTL_SECS,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        now_secs() >= self.expires_at
+    }
+}
+
+pub trait SessionStore: Send + Sync {
+    fn insert<'a>(
+        &'a self,
+        session_id: &'a str,
+        session: Session
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+    fn get<'a>(
+        &'a self,
+        session_id: &'a str
+    ) -> Pin<Box<dyn Future<Output = Result<Option<Session>>> + Send + 'a>>;
+
+    fn remove<'a>(
+        &'a self,
+        session_id: &'a str
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+    fn insert_login<'a>(
+        &'a self,
+        state: &'a str,
+        login: PendingLogin
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+    /// Removes and returns the pending login for `state`, so each state can be used only once.
+    fn take_login<'a>(
+        &'a self,
+        state: &'a str
+    ) -> Pin<Box<dyn Future<Output = Result<Option<PendingLogin>>> + Send + 'a>>;
+}
+
+#[derive(Default)]
+pub struct InMemorySessionStore {
+    sessions: RwLock<HashMap<String, Session>>,
+    logins: RwLock<HashMap<String, PendingLogin>>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SessionStore for InMemorySessionStore {
+    fn insert<'a>(
+        &'a self,
+        session_id: &'a str,
+        session: Session
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            self.sessions
+                .write()
+                .map_err(|_| anyhow::anyhow!("Session store lock poisoned"))?
+                .insert(session_id.to_string(), session);
+            Ok(())
+        })
+    }
+
+    fn get<'a>(
+        &'a self,
+        session_id: &'a str
+    ) -> Pin<Box<dyn Future<Output = Result<Option<Session>>> + Send + 'a>> {
+        Box::pin(async move {
+            let sessions = self.sessions
+                .read()
+                .map_err(|_| anyhow::anyhow!("Session store lock poisoned"))?;
+            Ok(sessions.get(session_id).cloned())
+        })
+    }
+
+    fn remove<'a>(
+        &'a self,
+        session_id: &'a str
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            self.sessions
+                .write()
+                .map_err(|_| anyhow::anyhow!("Session store lock poisoned"))?
+                .remove(session_id);
+            Ok(())
+        })
+    }
+
+    fn insert_login<'a>(
+        &'a self,
+        state: &'a str,
+        login: PendingLogin
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut logins = self.logins
+                .write()
+                .map_err(|_| anyhow::anyhow!("Session store lock poisoned"))?;
+            logins.retain(|_, pending| !pending.is_expired());
+            logins.insert(state.to_string(), login);
+            Ok(())
+        })
+    }
+
+    fn take_login<'a>(
+        &'a self,
+        state: &'a str
+    ) -> Pin<Box<dyn Future<Output = Result<Option<PendingLogin>>> + Send + 'a>> {
+        Box::pin(async move {
+            let login = self.logins
+                .write()
+                .map_err(|_| anyhow::anyhow!("Session store lock poisoned"))?
+                .remove(state);
+            Ok(login.filter(|pending| !pending.is_expired()))
+        })
+    }
+}
+
+/// Generates an opaque, URL-safe session identifier with 256 bits of entropy.
+pub fn new_session_id() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,174 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use crate::services::session_store::{now_secs, PendingLogin, Session, SessionStore};
+use anyhow::{Context, Result};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+pub struct SqliteSessionStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteSessionStore {
+    pub fn open(path: &str) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open session database at '{}'", path))?;
+        Self::from_connection(conn)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory().context("Failed to open in-memory session database")?;
+        Self::from_connection(conn)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS sessions (
+                id TEXT PRIMARY KEY,
+                access_token TEXT NOT NULL,
+                refresh_token TEXT,
+                scope TEXT,
+                expires_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS pending_logins (
+                state TEXT PRIMARY KEY,
+                code_verifier TEXT NOT NULL,
+                expires_at INTEGER NOT NULL
+            );"
+        )
+        .context("Failed to create session tables")?;
+
+        Ok(SqliteSessionStore { conn: Arc::new(Mutex::new(conn)) })
+    }
+
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().map_err(|_| anyhow::anyhow!("Session database lock poisoned"))?;
+            f(&conn)
+        })
+        .await
+        .context("Session database task panicked")?
+    }
+}
+
+impl SessionStore for SqliteSessionStore {
+    fn insert<'a>(
+        &'a self,
+        session_id: &'a str,
+        session: Session
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        let session_id = session_id.to_string();
+        Box::pin(async move {
+            self.with_conn(move |conn| {
+                conn.execute(
+                    "INSERT INTO sessions (id, access_token, refresh_token, scope, expires_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5)
+                     ON CONFLICT(id) DO UPDATE SET
+                        access_token = excluded.access_token,
+                        refresh_token = excluded.refresh_token,
+                        scope = excluded.scope,
+                        expires_at = excluded.expires_at",
+                    params![session_id, session.access_token, session.refresh_token, session.scope, session.expires_at as i64],
+                )
+                .context("Failed to store session")?;
+                Ok(())
+            })
+            .await
+        })
+    }
+
+    fn get<'a>(
+        &'a self,
+        session_id: &'a str
+    ) -> Pin<Box<dyn Future<Output = Result<Option<Session>>> + Send + 'a>> {
+        let session_id = session_id.to_string();
+        Box::pin(async move {
+            self.with_conn(move |conn| {
+                conn.query_row(
+                    "SELECT access_token, refresh_token, scope, expires_at FROM sessions WHERE id = ?1",
+                    params![session_id],
+                    |row| {
+                        Ok(Session {
+                            access_token: row.get(0)?,
+                            refresh_token: row.get(1)?,
+                            scope: row.get(2)?,
+                            expires_at: row.get::<_, i64>(3)? as u64,
+                        })
+                    },
+                )
+                .optional()
+                .context("Failed to load session")
+            })
+            .await
+        })
+    }
+
+    fn remove<'a>(
+        &'a self,
+        session_id: &'a str
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        let session_id = session_id.to_string();
+        Box::pin(async move {
+            self.with_conn(move |conn| {
+                conn.execute("DELETE FROM sessions WHERE id = ?1", params![session_id])
+                    .context("Failed to remove session")?;
+                Ok(())
+            })
+            .await
+        })
+    }
+
+    fn insert_login<'a>(
+        &'a self,
+        state: &'a str,
+        login: PendingLogin
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        let state = state.to_string();
+        Box::pin(async move {
+            self.with_conn(move |conn| {
+                conn.execute("DELETE FROM pending_logins WHERE expires_at <= ?1", params![now_secs() as i64])
+                    .context("Failed to prune pending logins")?;
+                conn.execute(
+                    "INSERT OR REPLACE INTO pending_logins (state, code_verifier, expires_at) VALUES (?1, ?2, ?3)",
+                    params![state, login.code_verifier, login.expires_at as i64],
+                )
+                .context("Failed to store pending login")?;
+                Ok(())
+            })
+            .await
+        })
+    }
+
+    fn take_login<'a>(
+        &'a self,
+        state: &'a str
+    ) -> Pin<Box<dyn Future<Output = Result<Option<PendingLogin>>> + Send + 'a>> {
+        let state = state.to_string();
+        Box::pin(async move {
+            self.with_conn(move |conn| {
+                let login = conn
+                    .query_row(
+                        "SELECT code_verifier, expires_at FROM pending_logins WHERE state = ?1",
+                        params![state],
+                        |row| {
+                            Ok(PendingLogin {
+                                code_verifier: row.get(0)?,
+                                expires_at: row.get::<_, i64>(1)? as u64,
+                            })
+                        },
+                    )
+                    .optional()
+                    .context("Failed to load pending login")?;
+                conn.execute("DELETE FROM pending_logins WHERE state = ?1", params![state])
+                    .context("Failed to remove pending login")?;
+                Ok(login.filter(|pending| !pending.is_expired()))
+            })
+            .await
+        })
+    }
+}
@@ -0,0 +1,144 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use crate::services::session_store::now_secs;
+use crate::services::upload_store::{UploadSession, UploadStore};
+use anyhow::{Context, Result};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+pub struct SqliteUploadStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteUploadStore {
+    pub fn open(path: &str) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open upload database at '{}'", path))?;
+        Self::from_connection(conn)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory().context("Failed to open in-memory upload database")?;
+        Self::from_connection(conn)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS upload_sessions (
+                id TEXT PRIMARY KEY,
+                resumable_url TEXT NOT NULL,
+                file_name TEXT NOT NULL,
+                mime_type TEXT,
+                total_size INTEGER,
+                committed INTEGER NOT NULL,
+                file_id TEXT,
+                expires_at INTEGER NOT NULL
+            );"
+        )
+        .context("Failed to create upload session table")?;
+
+        Ok(SqliteUploadStore { conn: Arc::new(Mutex::new(conn)) })
+    }
+
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().map_err(|_| anyhow::anyhow!("Upload database lock poisoned"))?;
+            f(&conn)
+        })
+        .await
+        .context("Upload database task panicked")?
+    }
+}
+
+impl UploadStore for SqliteUploadStore {
+    fn insert<'a>(
+        &'a self,
+        upload_id: &'a str,
+        upload: UploadSession
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        let upload_id = upload_id.to_string();
+        Box::pin(async move {
+            self.with_conn(move |conn| {
+                conn.execute("DELETE FROM upload_sessions WHERE expires_at <= ?1", params![now_secs() as i64])
+                    .context("Failed to prune expired uploads")?;
+                conn.execute(
+                    "INSERT INTO upload_sessions (id, resumable_url, file_name, mime_type, total_size, committed, file_id, expires_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                     ON CONFLICT(id) DO UPDATE SET
+                        resumable_url = excluded.resumable_url,
+                        file_name = excluded.file_name,
+                        mime_type = excluded.mime_type,
+                        total_size = excluded.total_size,
+                        committed = excluded.committed,
+                        file_id = excluded.file_id,
+                        expires_at = excluded.expires_at",
+                    params![
+                        upload_id,
+                        upload.resumable_url,
+                        upload.file_name,
+                        upload.mime_type,
+                        upload.total_size.map(|size| size as i64),
+                        upload.committed as i64,
+                        upload.file_id,
+                        upload.expires_at as i64
+                    ],
+                )
+                .context("Failed to store upload session")?;
+                Ok(())
+            })
+            .await
+        })
+    }
+
+    fn get<'a>(
+        &'a self,
+        upload_id: &'a str
+    ) -> Pin<Box<dyn Future<Output = Result<Option<UploadSession>>> + Send + 'a>> {
+        let upload_id = upload_id.to_string();
+        Box::pin(async move {
+            self.with_conn(move |conn| {
+                let upload = conn
+                    .query_row(
+                        "SELECT resumable_url, file_name, mime_type, total_size, committed, file_id, expires_at
+                         FROM upload_sessions WHERE id = ?1",
+                        params![upload_id],
+                        |row| {
+                            Ok(UploadSession {
+                                resumable_url: row.get(0)?,
+                                file_name: row.get(1)?,
+                                mime_type: row.get(2)?,
+                                total_size: row.get::<_, Option<i64>>(3)?.map(|size| size as u64),
+                                committed: row.get::<_, i64>(4)? as u64,
+                                file_id: row.get(5)?,
+                                expires_at: row.get::<_, i64>(6)? as u64,
+                            })
+                        },
+                    )
+                    .optional()
+                    .context("Failed to load upload session")?;
+                Ok(upload.filter(|upload| !upload.is_expired()))
+            })
+            .await
+        })
+    }
+
+    fn remove<'a>(
+        &'a self,
+        upload_id: &'a str
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        let upload_id = upload_id.to_string();
+        Box::pin(async move {
+            self.with_conn(move |conn| {
+                conn.execute("DELETE FROM upload_sessions WHERE id = ?1", params![upload_id])
+                    .context("Failed to remove upload session")?;
+                Ok(())
+            })
+            .await
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+use crate::services::checksum::Checksums;
+use crate::services::session_store::now_secs;
+use crate::services::upload_validation::{FileCheck, Rejection, UploadValidation};
+
+/// Validation and hashing of a client-driven upload, fed every byte of the
+/// file once and in order.
+pub struct UploadProgress {
+    pub check: FileCheck,
+    pub checksums: Checksums,
+    /// Bytes checked so far; may run ahead of what Drive has committed.
+    pub offset: u64,
+}
+
+impl UploadProgress {
+    pub fn new(check: FileCheck) -> Self {
+        UploadProgress { check, checksums: Checksums::new(), offset: 0 }
+    }
+
+    /// Checks and hashes the bytes of `data`, which starts at byte `start` of
+    /// the file, that have not been seen yet. Resent bytes are skipped.
+    pub fn update(&mut self, start: u64, data: &[u8]) -> Result<(), Rejection> {
+        let seen = self.offset.saturating_sub(start).min(data.len() as u64) as usize;
+        let unseen = &data[seen..];
+        self.check.update(unseen)?;
+        self.checksums.update(unseen);
+        self.offset += unseen.len() as u64;
+        Ok(())
+    }
+}
+
+struct Tracked {
+    progress: Arc<AsyncMutex<UploadProgress>>,
+    expires_at: u64,
+}
+
+/// Progress of the uploads this process has followed from their first byte.
+///
+/// Content checks cannot be persisted with the upload session, so an upload
+/// continued by another instance, or after a restart, is not found here.
+pub struct UploadChecks {
+    validation: Arc<UploadValidation>,
+    uploads: Mutex<HashMap<String, Tracked>>,
+}
+
+impl UploadChecks {
+    pub fn new(validation: Arc<UploadValidation>) -> Self {
+        UploadChecks { validation, uploads: Mutex::new(HashMap::new()) }
+    }
+
+    /// The checks uploads are started with.
+    pub fn validation(&self) -> &UploadValidation {
+        &self.validation
+    }
+
+    /// A panic while holding the lock leaves the map itself intact.
+    fn tracked(&self) -> MutexGuard<'_, HashMap<String, Tracked>> {
+        self.uploads.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Starts following `upload_id`, dropping uploads that have expired.
+    pub fn start(&self, upload_id: &str, progress: UploadProgress, expires_at: u64) {
+        let mut uploads = self.tracked();
+        let now = now_secs();
+        uploads.retain(|_, tracked| tracked.expires_at > now);
+        uploads.insert(upload_id.to_string(), Tracked { progress: Arc::new(AsyncMutex::new(progress)), expires_at });
+    }
+
+    /// Locks the progress of `upload_id`, so requests for the same upload are
+    /// checked one at a time.
+    pub async fn lock(&self, upload_id: &str) -> Option<OwnedMutexGuard<UploadProgress>> {
+        let progress = self.tracked().get(upload_id).map(|tracked| Arc::clone(&tracked.progress))?;
+        Some(progress.lock_owned().await)
+    }
+
+    pub fn remove(&self, upload_id: &str) {
+        self.tracked().remove(upload_id);
+    }
+}
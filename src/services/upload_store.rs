@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use crate::services::session_store::now_secs;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::RwLock;
+
+/// Google keeps a resumable upload session alive for one week.
+const UPLOAD_SESSION_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// A client-resumable upload, mapping our upload id to Google's session URL.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UploadSession {
+    pub resumable_url: String,
+    pub file_name: String,
+    /// Type the content is checked against.
+    pub mime_type: Option<String>,
+    pub total_size: Option<u64>,
+    /// Bytes Drive has persisted.
+    pub committed: u64,
+    /// Set once Drive has created the file.
+    pub file_id: Option<String>,
+    pub expires_at: u64,
+}
+
+impl UploadSession {
+    pub fn new(resumable_url: String, file_name: String, total_size: Option<u64>) -> Self {
+        UploadSession {
+            resumable_url,
+            file_name,
+            mime_type: None,
+            total_size,
+            committed: 0,
+            file_id: None,
+            expires_at: now_secs() + UPLOAD_SESSION_TTL_SECS,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.file_id.is_some()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        now_secs() >= self.expires_at
+    }
+}
+
+pub trait UploadStore: Send + Sync {
+    /// Inserts or replaces the upload stored under `upload_id`.
+    fn insert<'a>(
+        &'a self,
+        upload_id: &'a str,
+        upload: UploadSession
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+    fn get<'a>(
+        &'a self,
+        upload_id: &'a str
+    ) -> Pin<Box<dyn Future<Output = Result<Option<UploadSession>>> + Send + 'a>>;
+
+    fn remove<'a>(
+        &'a self,
+        upload_id: &'a str
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+}
+
+#[derive(Default)]
+pub struct InMemoryUploadStore {
+    uploads: RwLock<HashMap<String, UploadSession>>,
+}
+
+impl InMemoryUploadStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl UploadStore for InMemoryUploadStore {
+    fn insert<'a>(
+        &'a self,
+        upload_id: &'a str,
+        upload: UploadSession
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut uploads = self.uploads
+                .write()
+                .map_err(|_| anyhow::anyhow!("Upload store lock poisoned"))?;
+            uploads.retain(|_, upload| !upload.is_expired());
+            uploads.insert(upload_id.to_string(), upload);
+            Ok(())
+        })
+    }
+
+    fn get<'a>(
+        &'a self,
+        upload_id: &'a str
+    ) -> Pin<Box<dyn Future<Output = Result<Option<UploadSession>>> + Send + 'a>> {
+        Box::pin(async move {
+            let uploads = self.uploads
+                .read()
+                .map_err(|_| anyhow::anyhow!("Upload store lock poisoned"))?;
+            Ok(uploads.get(upload_id).filter(|upload| !upload.is_expired()).cloned())
+        })
+    }
+
+    fn remove<'a>(
+        &'a self,
+        upload_id: &'a str
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            self.uploads
+                .write()
+                .map_err(|_| anyhow::anyhow!("Upload store lock poisoned"))?
+                .remove(upload_id);
+            Ok(())
+        })
+    }
+}
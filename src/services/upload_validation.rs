@@ -0,0 +1,402 @@
+use actix_web::http::StatusCode;
+use std::collections::HashSet;
+use std::fmt;
+use crate::config::Config;
+
+/// Why a file part was refused before it reached Drive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejection {
+    /// No validator accepts the file's type, or its content does not match it.
+    UnsupportedType(String),
+    /// The file is larger than the configured limit, in bytes.
+    TooLarge(u64),
+}
+
+impl Rejection {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Rejection::UnsupportedType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Rejection::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+        }
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::UnsupportedType(reason) => write!(f, "Unsupported file: {}", reason),
+            Rejection::TooLarge(limit) => write!(f, "File exceeds the upload limit of {} bytes", limit),
+        }
+    }
+}
+
+/// Checks that uploads of one file type really are of that type.
+pub trait UploadValidator: Send + Sync {
+    /// Name used in `UPLOAD_ALLOWED_TYPES`.
+    fn name(&self) -> &'static str;
+
+    fn accepts(&self, mime_type: &str) -> bool;
+
+    /// Starts inspecting one file; its content is fed in order as it arrives.
+    fn inspector(&self) -> Box<dyn ContentInspector>;
+}
+
+/// Inspects the content of one file without buffering all of it.
+pub trait ContentInspector: Send {
+    /// Called with each chunk of the file; fails as soon as the content is
+    /// known to be invalid.
+    fn update(&mut self, chunk: &[u8]) -> Result<(), String>;
+
+    /// Called once the whole file has been read.
+    fn finish(&mut self) -> Result<(), String>;
+}
+
+/// Builds the validator registered under `name`.
+pub fn validator_by_name(name: &str, config: &Config) -> Option<Box<dyn UploadValidator>> {
+    match name {
+        "pdf" => Some(Box::new(PdfValidator { check_xref: config.validate_pdf_xref })),
+        "png" => Some(Box::new(PngValidator)),
+        "docx" => Some(Box::new(DocxValidator)),
+        _ => None,
+    }
+}
+
+/// The validators uploads must pass, and the size limit they share.
+pub struct UploadValidation {
+    validators: Vec<Box<dyn UploadValidator>>,
+    max_size: u64,
+}
+
+impl UploadValidation {
+    pub fn new(max_size: u64) -> Self {
+        UploadValidation { validators: Vec::new(), max_size }
+    }
+
+    pub fn with_validator(mut self, validator: Box<dyn UploadValidator>) -> Self {
+        self.validators.push(validator);
+        self
+    }
+
+    /// Enables the validators named in `UPLOAD_ALLOWED_TYPES`.
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        config.upload_allowed_types.iter().try_fold(Self::new(config.max_upload_size), |validation, name| {
+            validator_by_name(name, config)
+                .map(|validator| validation.with_validator(validator))
+                .ok_or_else(|| format!("Unknown upload type '{}'", name))
+        })
+    }
+
+    /// Starts checking a file of `mime_type`. `size` is the length the
+    /// client declared for it, if any.
+    pub fn check(&self, mime_type: Option<&str>, size: Option<u64>) -> Result<FileCheck, Rejection> {
+        let allowed: Vec<&str> = self.validators.iter().map(|validator| validator.name()).collect();
+        let validator = mime_type
+            .and_then(|mime_type| self.validators.iter().find(|validator| validator.accepts(mime_type)))
+            .ok_or_else(|| Rejection::UnsupportedType(format!(
+                "type {} is not allowed; expected one of: {}",
+                mime_type.unwrap_or("unknown"),
+                allowed.join(", ")
+            )))?;
+
+        if size.is_some_and(|size| size > self.max_size) {
+            return Err(Rejection::TooLarge(self.max_size));
+        }
+
+        Ok(FileCheck { inspector: validator.inspector(), size: 0, max_size: self.max_size })
+    }
+}
+
+/// Validation of one file in progress.
+pub struct FileCheck {
+    inspector: Box<dyn ContentInspector>,
+    size: u64,
+    max_size: u64,
+}
+
+impl FileCheck {
+    pub fn update(&mut self, chunk: &[u8]) -> Result<(), Rejection> {
+        self.size += chunk.len() as u64;
+        if self.size > self.max_size {
+            return Err(Rejection::TooLarge(self.max_size));
+        }
+        self.inspector.update(chunk).map_err(Rejection::UnsupportedType)
+    }
+
+    pub fn finish(&mut self) -> Result<(), Rejection> {
+        self.inspector.finish().map_err(Rejection::UnsupportedType)
+    }
+}
+
+/// The first and last bytes of a file, kept while it streams past.
+struct Edges {
+    head: Vec<u8>,
+    head_len: usize,
+    tail: Vec<u8>,
+    tail_len: usize,
+}
+
+impl Edges {
+    fn new(head_len: usize, tail_len: usize) -> Self {
+        Edges { head: Vec::new(), head_len, tail: Vec::new(), tail_len }
+    }
+
+    fn update(&mut self, chunk: &[u8]) {
+        let missing = self.head_len.saturating_sub(self.head.len());
+        self.head.extend_from_slice(&chunk[..missing.min(chunk.len())]);
+
+        self.tail.extend_from_slice(chunk);
+        // Trim lazily so long files do not shift the buffer on every chunk.
+        if self.tail.len() > 2 * self.tail_len {
+            self.tail.drain(..self.tail.len() - self.tail_len);
+        }
+    }
+
+    fn head_complete(&self) -> bool {
+        self.head.len() == self.head_len
+    }
+
+    fn tail(&self) -> &[u8] {
+        &self.tail[self.tail.len().saturating_sub(self.tail_len)..]
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|window| window == needle)
+}
+
+/// Checks a file's leading magic bytes as soon as enough of it has arrived.
+fn check_magic(edges: &Edges, checked: &mut bool, magic: &[u8], kind: &str) -> Result<(), String> {
+    if !*checked && edges.head_complete() {
+        *checked = true;
+        if !edges.head.starts_with(magic) {
+            return Err(format!("content is not a {} file", kind));
+        }
+    }
+    Ok(())
+}
+
+const PDF_MAGIC: &[u8] = b"%PDF-";
+/// Readers look for `%%EOF` within the last 1024 bytes.
+const PDF_TRAILER_LEN: usize = 1024;
+/// Longest line prefix kept to recognise `xref` and `N G obj` lines.
+const PDF_LINE_PREFIX_LEN: usize = 32;
+
+/// Accepts PDFs: `%PDF-` header, `%%EOF` trailer and, optionally, a
+/// `startxref` that points at a cross-reference table or stream.
+pub struct PdfValidator {
+    pub check_xref: bool,
+}
+
+impl UploadValidator for PdfValidator {
+    fn name(&self) -> &'static str {
+        "pdf"
+    }
+
+    fn accepts(&self, mime_type: &str) -> bool {
+        mime_type == "application/pdf"
+    }
+
+    fn inspector(&self) -> Box<dyn ContentInspector> {
+        Box::new(PdfInspector {
+            edges: Edges::new(PDF_MAGIC.len(), PDF_TRAILER_LEN),
+            head_checked: false,
+            xref: self.check_xref.then(XrefIndex::default),
+        })
+    }
+}
+
+struct PdfInspector {
+    edges: Edges,
+    head_checked: bool,
+    xref: Option<XrefIndex>,
+}
+
+impl ContentInspector for PdfInspector {
+    fn update(&mut self, chunk: &[u8]) -> Result<(), String> {
+        self.edges.update(chunk);
+        if let Some(xref) = &mut self.xref {
+            xref.update(chunk);
+        }
+        check_magic(&self.edges, &mut self.head_checked, PDF_MAGIC, "PDF")
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        if !self.head_checked {
+            return Err("content is not a PDF file".to_string());
+        }
+        let tail = self.edges.tail();
+        if find(tail, b"%%EOF").is_none() {
+            return Err("PDF is truncated: %%EOF trailer not found".to_string());
+        }
+
+        if let Some(xref) = &mut self.xref {
+            xref.finish();
+            let offset = rfind(tail, b"startxref")
+                .and_then(|start| {
+                    let digits: Vec<u8> = tail[start + b"startxref".len()..]
+                        .iter()
+                        .skip_while(|byte| byte.is_ascii_whitespace())
+                        .take_while(|byte| byte.is_ascii_digit())
+                        .copied()
+                        .collect();
+                    std::str::from_utf8(&digits).ok()?.parse::<u64>().ok()
+                })
+                .ok_or_else(|| "PDF has no startxref offset".to_string())?;
+            if !xref.line_starts.contains(&offset) {
+                return Err(format!("PDF startxref offset {} does not point to a cross-reference section", offset));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Offsets of the lines that can start a cross-reference section: an
+/// `xref` table or an `N G obj` holding an xref stream.
+#[derive(Default)]
+struct XrefIndex {
+    offset: u64,
+    line_start: u64,
+    line: Vec<u8>,
+    line_starts: HashSet<u64>,
+}
+
+impl XrefIndex {
+    fn update(&mut self, chunk: &[u8]) {
+        for &byte in chunk {
+            if byte == b'\r' || byte == b'\n' {
+                self.end_line();
+                self.line_start = self.offset + 1;
+            } else if self.line.len() < PDF_LINE_PREFIX_LEN {
+                self.line.push(byte);
+            }
+            self.offset += 1;
+        }
+    }
+
+    fn finish(&mut self) {
+        self.end_line();
+    }
+
+    fn end_line(&mut self) {
+        if self.line.starts_with(b"xref") || is_object_header(&self.line) {
+            self.line_starts.insert(self.line_start);
+        }
+        self.line.clear();
+    }
+}
+
+/// Whether `line` starts with `N G obj`.
+fn is_object_header(line: &[u8]) -> bool {
+    let mut words = line.split(|byte| *byte == b' ').filter(|word| !word.is_empty());
+    let mut number = || words.next().is_some_and(|word| word.iter().all(u8::is_ascii_digit));
+    number() && number() && words.next().is_some_and(|word| word.starts_with(b"obj"))
+}
+
+const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// The `IEND` chunk, with its zero length and CRC, that ends every PNG.
+const PNG_END: &[u8] = b"\0\0\0\0IEND\xaeB`\x82";
+
+/// Accepts PNG images with their signature and closing `IEND` chunk.
+pub struct PngValidator;
+
+impl UploadValidator for PngValidator {
+    fn name(&self) -> &'static str {
+        "png"
+    }
+
+    fn accepts(&self, mime_type: &str) -> bool {
+        mime_type == "image/png"
+    }
+
+    fn inspector(&self) -> Box<dyn ContentInspector> {
+        Box::new(PngInspector { edges: Edges::new(PNG_MAGIC.len(), PNG_END.len()), head_checked: false })
+    }
+}
+
+struct PngInspector {
+    edges: Edges,
+    head_checked: bool,
+}
+
+impl ContentInspector for PngInspector {
+    fn update(&mut self, chunk: &[u8]) -> Result<(), String> {
+        self.edges.update(chunk);
+        check_magic(&self.edges, &mut self.head_checked, PNG_MAGIC, "PNG")
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        if !self.head_checked {
+            return Err("content is not a PNG file".to_string());
+        }
+        if self.edges.tail() != PNG_END {
+            return Err("PNG is truncated: IEND chunk not found".to_string());
+        }
+        Ok(())
+    }
+}
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const ZIP_END_OF_CENTRAL_DIRECTORY: &[u8] = b"PK\x05\x06";
+/// The end of central directory record is 22 bytes plus a comment of up to 64 KiB.
+const ZIP_TRAILER_LEN: usize = 22 + 0xFFFF;
+const DOCX_MAIN_PART: &[u8] = b"word/document.xml";
+
+/// Accepts Word documents: a complete ZIP archive with a `word/document.xml` part.
+pub struct DocxValidator;
+
+impl UploadValidator for DocxValidator {
+    fn name(&self) -> &'static str {
+        "docx"
+    }
+
+    fn accepts(&self, mime_type: &str) -> bool {
+        mime_type == "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+    }
+
+    fn inspector(&self) -> Box<dyn ContentInspector> {
+        Box::new(DocxInspector {
+            edges: Edges::new(ZIP_MAGIC.len(), ZIP_TRAILER_LEN),
+            head_checked: false,
+            carry: Vec::new(),
+            has_main_part: false,
+        })
+    }
+}
+
+struct DocxInspector {
+    edges: Edges,
+    head_checked: bool,
+    /// End of the previous chunk, so a part name split across chunks is still found.
+    carry: Vec<u8>,
+    has_main_part: bool,
+}
+
+impl ContentInspector for DocxInspector {
+    fn update(&mut self, chunk: &[u8]) -> Result<(), String> {
+        self.edges.update(chunk);
+        if !self.has_main_part {
+            self.carry.extend_from_slice(chunk);
+            self.has_main_part = find(&self.carry, DOCX_MAIN_PART).is_some();
+            let keep = DOCX_MAIN_PART.len() - 1;
+            self.carry.drain(..self.carry.len().saturating_sub(keep));
+        }
+        check_magic(&self.edges, &mut self.head_checked, ZIP_MAGIC, "DOCX")
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        if !self.head_checked {
+            return Err("content is not a DOCX file".to_string());
+        }
+        if rfind(self.edges.tail(), ZIP_END_OF_CENTRAL_DIRECTORY).is_none() {
+            return Err("DOCX is truncated: ZIP central directory not found".to_string());
+        }
+        if !self.has_main_part {
+            return Err("ZIP archive is not a Word document".to_string());
+        }
+        Ok(())
+    }
+}
@@ -1,21 +1,47 @@
 use utoipa::{openapi::security::{Http, HttpAuthScheme, SecurityScheme}, Modify, OpenApi};
-use crate::services::{auth_service::AuthCallbackQuery, google_drive_service::{FolderInfo, FileInfo}};
+use crate::api::file_metadata::{Capabilities, FileMetadata, Owner};
+use crate::api::permissions::{Permission, PermissionList, PermissionRole, PermissionType};
+use crate::handlers::{file_handler::{CopyFileRequest, UpdateFileRequest}, folder_handler::{CreateFolderRequest, CreatedFolder}, google_drive_handler::VerifyResult, permission_handler::{CreatePermissionRequest, UpdatePermissionRequest}, upload_handler::{CreateUploadRequest, UploadStatus}};
+use crate::middlewares::token_cache::TokenCacheStats;
+use crate::services::{auth_service::{AuthCallbackQuery, SessionResponse}, batch_upload::UploadResult, folder_copy::{CopyFailure, CopyResult}, folder_path::PathSegment, folder_tree::TreeNode, google_drive_service::{FolderInfo, FileInfo, FolderPage, FilePage, FileMetadataPage}};
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         crate::handlers::auth_handler::get_auth_url,
         crate::handlers::auth_handler::auth_callback,
+        crate::handlers::auth_handler::refresh_token,
+        crate::handlers::auth_handler::logout,
+        crate::handlers::metrics_handler::get_token_cache_stats,
         crate::handlers::google_drive_handler::get_list_folders,
         crate::handlers::google_drive_handler::get_list_files_in_folder,
+        crate::handlers::google_drive_handler::search_files,
+        crate::handlers::google_drive_handler::get_folder_tree,
+        crate::handlers::folder_handler::create_folder,
+        crate::handlers::file_handler::get_file_metadata,
+        crate::handlers::file_handler::update_file,
+        crate::handlers::file_handler::copy_file,
+        crate::handlers::permission_handler::list_permissions,
+        crate::handlers::permission_handler::create_permission,
+        crate::handlers::permission_handler::update_permission,
+        crate::handlers::permission_handler::delete_permission,
+        crate::handlers::trash_handler::delete_file,
+        crate::handlers::trash_handler::restore_file,
+        crate::handlers::trash_handler::empty_trash,
         crate::handlers::google_drive_handler::download_pdf_file_by_id,
+        crate::handlers::google_drive_handler::verify_file,
         crate::handlers::google_drive_handler::upload_pdf_file,
+        crate::handlers::upload_handler::create_upload,
+        crate::handlers::upload_handler::get_upload,
+        crate::handlers::upload_handler::put_upload_range,
+        crate::handlers::upload_handler::cancel_upload,
     ),
     modifiers(&SecurityAddon),
-    components(schemas(AuthCallbackQuery, FolderInfo, FileInfo)),
+    components(schemas(AuthCallbackQuery, SessionResponse, TokenCacheStats, FolderInfo, FileInfo, FolderPage, FilePage, TreeNode, CreateUploadRequest, UploadStatus, UploadResult, VerifyResult, CreateFolderRequest, CreatedFolder, PathSegment, UpdateFileRequest, CopyFileRequest, CopyResult, CopyFailure, FileMetadata, FileMetadataPage, Owner, Capabilities, Permission, PermissionList, PermissionType, PermissionRole, CreatePermissionRequest, UpdatePermissionRequest)),
     tags(
         (name = "auth", description = "Authentication related endpoints"),
-        (name = "drive", description = "Google Drive API related endpoints")
+        (name = "drive", description = "Google Drive API related endpoints"),
+        (name = "metrics", description = "Operational counters")
     ),
     info(description = "This API allows users to interact with their Google Drive account through secure transactions authenticated with OAuth 2.0.")
 )]
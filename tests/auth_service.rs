@@ -4,53 +4,86 @@ mod auth_service_mock;
 mod config_mock;
 
 use actix_web::{test, web, App};
-use api_drive::handlers::auth_handler::{auth_callback, get_auth_url};
+use actix_web::http::header;
+use api_drive::api::auth::TokenInfo;
+use api_drive::handlers::auth_handler::{auth_callback, get_auth_url, logout, refresh_token};
+use actix_web::cookie::Cookie;
+use api_drive::middlewares::session::{login_state_cookie, session_cookie, LOGIN_STATE_COOKIE, SESSION_COOKIE};
+use api_drive::middlewares::token_cache::TokenCache;
+use api_drive::services::auth_service::{AuthService, SessionResponse};
+use api_drive::services::pkce::code_challenge;
+use api_drive::services::session_store::{now_secs, InMemorySessionStore, PendingLogin, Session, SessionStore};
 use auth_service_mock::{MockAuthService, MockAuthServiceError};
 use config_mock::mock_config;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[actix_rt::test]
 async fn test_auth_callback_success() {
     let config_data = web::Data::new(mock_config());
+    let store = Arc::new(InMemorySessionStore::new());
+    store.insert_login("test_state", PendingLogin::new("test_verifier".to_string())).await.unwrap();
 
-    let mut app = test::init_service(
+    let app = test::init_service(
         App::new()
             .app_data(config_data.clone())
             .app_data(web::Data::new(MockAuthService))
+            .app_data(web::Data::from(store.clone() as Arc<dyn SessionStore>))
             .route("/auth/callback", web::get().to(auth_callback::<MockAuthService>)),
     )
     .await;
 
     let req = test::TestRequest::get()
-        .uri("/auth/callback?code=test_code")
+        .uri("/auth/callback?code=test_code&state=test_state")
+        .cookie(login_state_cookie("test_state", &config_data))
         .to_request();
 
-    let resp = test::call_service(&mut app, req).await;
+    let resp = test::call_service(&app, req).await;
 
     assert!(resp.status().is_success());
 
-    let body = test::read_body(resp).await;
-    let body_str = std::str::from_utf8(&body).unwrap();
-    assert!(body_str.contains("Access Token: mock_access_token"));
-    assert!(body_str.contains("Expires in: 3600"));
+    let cookie = resp
+        .response()
+        .cookies()
+        .find(|cookie| cookie.name() == SESSION_COOKIE)
+        .expect("Session cookie should be set");
+    assert_eq!(cookie.http_only(), Some(true));
+    let login_state = resp
+        .response()
+        .cookies()
+        .find(|cookie| cookie.name() == LOGIN_STATE_COOKIE)
+        .expect("Login state cookie should be cleared");
+    assert_eq!(login_state.value(), "");
+
+    let body: SessionResponse = test::read_body_json(resp).await;
+    assert!(!body.session_id.contains("mock_access_token"));
+
+    let session = store.get(&body.session_id).await.unwrap().expect("Session should be stored");
+    assert_eq!(session.access_token, "mock_access_token");
+    assert_eq!(session.refresh_token, Some("mock_refresh_token".to_string()));
 }
 
 #[actix_rt::test]
 async fn test_auth_callback_error() {
     let config_data = web::Data::new(mock_config());
+    let store = Arc::new(InMemorySessionStore::new());
+    store.insert_login("test_state", PendingLogin::new("test_verifier".to_string())).await.unwrap();
 
-    let mut app = test::init_service(
+    let app = test::init_service(
         App::new()
             .app_data(config_data.clone())
             .app_data(web::Data::new(MockAuthServiceError))
+            .app_data(web::Data::from(store as Arc<dyn SessionStore>))
             .route("/auth/callback", web::get().to(auth_callback::<MockAuthServiceError>)),
     )
     .await;
 
     let req = test::TestRequest::get()
-        .uri("/auth/callback?code=invalid_code")
+        .uri("/auth/callback?code=invalid_code&state=test_state")
+        .cookie(login_state_cookie("test_state", &config_data))
         .to_request();
 
-    let resp = test::call_service(&mut app, req).await;
+    let resp = test::call_service(&app, req).await;
 
     assert!(resp.status().is_server_error());
 
@@ -59,17 +92,308 @@ async fn test_auth_callback_error() {
     assert!(body_str.contains("Error obtaining token"));
 }
 
+/// Calls the callback from a browser holding `login_state`, if any.
+async fn callback_status(uri: &str, login_state: Option<Cookie<'static>>, store: Arc<InMemorySessionStore>) -> (u16, String) {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(mock_config()))
+            .app_data(web::Data::new(MockAuthService))
+            .app_data(web::Data::from(store as Arc<dyn SessionStore>))
+            .route("/auth/callback", web::get().to(auth_callback::<MockAuthService>)),
+    )
+    .await;
+
+    let mut req = test::TestRequest::get().uri(uri);
+    if let Some(login_state) = login_state {
+        req = req.cookie(login_state);
+    }
+    let resp = test::call_service(&app, req.to_request()).await;
+    let status = resp.status().as_u16();
+    let body = test::read_body(resp).await;
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+fn state_cookie(state: &str) -> Cookie<'static> {
+    login_state_cookie(state, &mock_config())
+}
+
+#[actix_rt::test]
+async fn test_auth_callback_rejects_missing_state() {
+    let (status, body) = callback_status("/auth/callback?code=test_code", None, Arc::new(InMemorySessionStore::new())).await;
+
+    assert_eq!(status, 400);
+    assert_eq!(body, "Missing state parameter");
+}
+
+#[actix_rt::test]
+async fn test_auth_callback_rejects_unknown_state() {
+    let store = Arc::new(InMemorySessionStore::new());
+    store.insert_login("test_state", PendingLogin::new("test_verifier".to_string())).await.unwrap();
+
+    let (status, body) = callback_status("/auth/callback?code=test_code&state=forged_state", Some(state_cookie("forged_state")), store).await;
+
+    assert_eq!(status, 400);
+    assert_eq!(body, "Invalid or expired state parameter");
+}
+
+#[actix_rt::test]
+async fn test_auth_callback_rejects_replayed_state() {
+    let store = Arc::new(InMemorySessionStore::new());
+    store.insert_login("test_state", PendingLogin::new("test_verifier".to_string())).await.unwrap();
+
+    let (status, _) = callback_status("/auth/callback?code=test_code&state=test_state", Some(state_cookie("test_state")), store.clone()).await;
+    assert_eq!(status, 200);
+
+    let (status, body) = callback_status("/auth/callback?code=test_code&state=test_state", Some(state_cookie("test_state")), store).await;
+    assert_eq!(status, 400);
+    assert_eq!(body, "Invalid or expired state parameter");
+}
+
+#[actix_rt::test]
+async fn test_auth_callback_rejects_state_from_another_browser() {
+    let uri = "/auth/callback?code=test_code&state=test_state";
+    let unsigned = Cookie::new(LOGIN_STATE_COOKIE, "test_state");
+
+    for login_state in [None, Some(state_cookie("other_state")), Some(unsigned)] {
+        let store = Arc::new(InMemorySessionStore::new());
+        store.insert_login("test_state", PendingLogin::new("test_verifier".to_string())).await.unwrap();
+
+        let (status, body) = callback_status(uri, login_state, store).await;
+        assert_eq!(status, 400);
+        assert_eq!(body, "State does not match the login started in this browser");
+    }
+}
+
+#[actix_rt::test]
+async fn test_auth_callback_provider_error() {
+    let store = Arc::new(InMemorySessionStore::new());
+    store.insert_login("test_state", PendingLogin::new("test_verifier".to_string())).await.unwrap();
+
+    let (status, body) = callback_status(
+        "/auth/callback?error=access_denied&error_description=User%20denied%20access&state=test_state",
+        Some(state_cookie("test_state")),
+        store.clone(),
+    )
+    .await;
+
+    assert_eq!(status, 400);
+    assert_eq!(body, "Authorization failed: access_denied: User denied access");
+    assert!(store.take_login("test_state").await.unwrap().is_none());
+}
+
 #[actix_rt::test]
 async fn test_build_auth_url_happy_path() {
     let config_data = web::Data::new(mock_config());
+    let store = Arc::new(InMemorySessionStore::new());
+
+    let app = test::init_service(
+        App::new()
+            .app_data(config_data.clone())
+            .app_data(web::Data::from(store.clone() as Arc<dyn SessionStore>))
+            .route("/auth", web::get().to(get_auth_url)),
+    )
+    .await;
 
-    let result = get_auth_url(config_data).await;
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/auth").to_request()).await;
+    assert!(resp.status().is_success());
+    let login_state = resp
+        .response()
+        .cookies()
+        .find(|cookie| cookie.name() == LOGIN_STATE_COOKIE)
+        .expect("Login state cookie should be set")
+        .into_owned();
+    assert_eq!(login_state.http_only(), Some(true));
+    assert_eq!(login_state.same_site(), Some(actix_web::cookie::SameSite::Lax));
+    assert!(login_state.max_age().is_some());
+
+    let body = test::read_body(resp).await;
+    let result = std::str::from_utf8(&body).unwrap();
 
     assert!(result.contains("client_id=test_client_id"));
 
     assert!(result.contains("redirect_uri=http://localhost:8080/callback"));
     
     assert!(result.contains("scope=https://www.googleapis.com/auth/drive"));
+
+    assert!(result.contains("access_type=offline"));
+
+    assert!(result.contains("code_challenge_method=S256"));
+
+    let state = result
+        .split('&')
+        .find_map(|param| param.strip_prefix("state="))
+        .expect("Auth URL should carry a state parameter");
+    assert_eq!(login_state.value(), login_state_cookie(state, &config_data).value(), "Cookie should carry the signed state");
+    let pending_login = store.take_login(state).await.unwrap().expect("State should be stored");
+
+    let challenge = result
+        .split('&')
+        .find_map(|param| param.strip_prefix("code_challenge="))
+        .expect("Auth URL should carry a code challenge");
+    assert_eq!(challenge, code_challenge(&pending_login.code_verifier));
 }
 
+fn stored_session() -> Session {
+    Session {
+        access_token: "google_access_token".to_string(),
+        refresh_token: Some("google_refresh_token".to_string()),
+        scope: None,
+        expires_at: now_secs() + 3600,
+    }
+}
+
+fn cached_token_info() -> TokenInfo {
+    TokenInfo {
+        aud: Some("test_client_id".to_string()),
+        scopes: vec![],
+        exp: None,
+        expires_in: Some(3600),
+        email: None,
+    }
+}
 
+#[actix_rt::test]
+async fn test_logout_revokes_and_clears_session() {
+    let config = mock_config();
+    let store = Arc::new(InMemorySessionStore::new());
+    store.insert("session-1", stored_session()).await.unwrap();
+    let cache = web::Data::new(TokenCache::new(10, Duration::from_secs(30)));
+    cache.insert_valid("google_access_token", cached_token_info(), Duration::from_secs(60));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(config.clone()))
+            .app_data(web::Data::new(MockAuthService))
+            .app_data(web::Data::from(store.clone() as Arc<dyn SessionStore>))
+            .app_data(cache.clone())
+            .route("/auth/logout", web::post().to(logout::<MockAuthService>)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/auth/logout")
+        .cookie(session_cookie("session-1", &config))
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+
+    assert!(resp.status().is_success());
+    let cookie = resp
+        .response()
+        .cookies()
+        .find(|cookie| cookie.name() == SESSION_COOKIE)
+        .expect("Session cookie should be cleared");
+    assert_eq!(cookie.value(), "");
+    assert!(store.get("session-1").await.unwrap().is_none());
+    assert!(cache.get("google_access_token").is_none());
+}
+
+#[actix_rt::test]
+async fn test_logout_reports_revocation_failure() {
+    let store = Arc::new(InMemorySessionStore::new());
+    store.insert("session-1", stored_session()).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(mock_config()))
+            .app_data(web::Data::new(MockAuthServiceError))
+            .app_data(web::Data::from(store.clone() as Arc<dyn SessionStore>))
+            .route("/auth/logout", web::post().to(logout::<MockAuthServiceError>)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/auth/logout")
+        .insert_header((header::AUTHORIZATION, "Bearer session-1"))
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), 502);
+    assert!(store.get("session-1").await.unwrap().is_none());
+}
+
+#[actix_rt::test]
+async fn test_logout_without_credentials() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(mock_config()))
+            .app_data(web::Data::new(MockAuthService))
+            .app_data(web::Data::from(Arc::new(InMemorySessionStore::new()) as Arc<dyn SessionStore>))
+            .route("/auth/logout", web::post().to(logout::<MockAuthService>)),
+    )
+    .await;
+
+    let resp = test::call_service(&app, test::TestRequest::post().uri("/auth/logout").to_request()).await;
+
+    assert_eq!(resp.status(), 401);
+}
+
+async fn refresh_request<T: AuthService + 'static>(
+    service: T,
+    store: Arc<InMemorySessionStore>,
+    cache: web::Data<TokenCache>,
+    req: test::TestRequest,
+) -> actix_web::dev::ServiceResponse {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(mock_config()))
+            .app_data(web::Data::new(service))
+            .app_data(web::Data::from(store as Arc<dyn SessionStore>))
+            .app_data(cache)
+            .route("/auth/refresh", web::post().to(refresh_token::<T>)),
+    )
+    .await;
+
+    test::call_service(&app, req.uri("/auth/refresh").to_request()).await
+}
+
+#[actix_rt::test]
+async fn test_refresh_token_renews_the_session() {
+    let config = mock_config();
+    let store = Arc::new(InMemorySessionStore::new());
+    store.insert("session-1", stored_session()).await.unwrap();
+    let cache = web::Data::new(TokenCache::new(10, Duration::from_secs(30)));
+    cache.insert_valid("google_access_token", cached_token_info(), Duration::from_secs(60));
+
+    let req = test::TestRequest::post().cookie(session_cookie("session-1", &config));
+    let resp = refresh_request(MockAuthService, store.clone(), cache.clone(), req).await;
+
+    assert!(resp.status().is_success());
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["session_id"], "session-1");
+    assert!(body["expires_in"].as_u64().unwrap() > 3000);
+    assert!(body.get("access_token").is_none());
+    assert!(body.get("refresh_token").is_none());
+
+    let session = store.get("session-1").await.unwrap().unwrap();
+    assert_eq!(session.access_token, "mock_refreshed_access_token");
+    assert_eq!(session.refresh_token, Some("google_refresh_token".to_string()));
+    assert!(cache.get("google_access_token").is_none());
+}
+
+#[actix_rt::test]
+async fn test_refresh_token_errors() {
+    let cache = web::Data::new(TokenCache::new(10, Duration::from_secs(30)));
+    let store = Arc::new(InMemorySessionStore::new());
+    store.insert("session-1", stored_session()).await.unwrap();
+    let mut without_refresh = stored_session();
+    without_refresh.refresh_token = None;
+    store.insert("session-2", without_refresh).await.unwrap();
+
+    let resp = refresh_request(MockAuthService, store.clone(), cache.clone(), test::TestRequest::post()).await;
+    assert_eq!(resp.status(), 401);
+
+    let unknown = test::TestRequest::post().insert_header((header::AUTHORIZATION, "Bearer unknown"));
+    assert_eq!(refresh_request(MockAuthService, store.clone(), cache.clone(), unknown).await.status(), 401);
+
+    let no_refresh_token = test::TestRequest::post().insert_header((header::AUTHORIZATION, "Bearer session-2"));
+    assert_eq!(refresh_request(MockAuthService, store.clone(), cache.clone(), no_refresh_token).await.status(), 400);
+
+    let revoked = test::TestRequest::post().insert_header((header::AUTHORIZATION, "Bearer session-1"));
+    let resp = refresh_request(MockAuthServiceError, store.clone(), cache, revoked).await;
+    assert!(resp.status().is_server_error());
+    let body = test::read_body(resp).await;
+    assert!(std::str::from_utf8(&body).unwrap().contains("Error refreshing token"));
+    assert_eq!(store.get("session-1").await.unwrap().unwrap().access_token, "google_access_token");
+}
@@ -0,0 +1,70 @@
+use api_drive::api::drive_query::parse_rfc3339;
+use api_drive::services::byte_range::{multipart_length, parse_range, part_header, closing_boundary, ByteRange, RangeRequest, MAX_RANGES};
+use std::time::{Duration, UNIX_EPOCH};
+
+fn range(start: u64, end: u64) -> ByteRange {
+    ByteRange { start, end }
+}
+
+#[test]
+fn test_parse_single_ranges() {
+    assert_eq!(parse_range("bytes=0-499", 1000), RangeRequest::Partial(vec![range(0, 499)]));
+    assert_eq!(parse_range("bytes=500-", 1000), RangeRequest::Partial(vec![range(500, 999)]));
+    assert_eq!(parse_range("bytes=-200", 1000), RangeRequest::Partial(vec![range(800, 999)]));
+    assert_eq!(parse_range("bytes=900-5000", 1000), RangeRequest::Partial(vec![range(900, 999)]));
+    assert_eq!(parse_range("bytes=-5000", 1000), RangeRequest::Partial(vec![range(0, 999)]));
+}
+
+#[test]
+fn test_parse_multiple_ranges_keeps_order_and_drops_unsatisfiable() {
+    assert_eq!(
+        parse_range("bytes=500-599, 0-99 ,2000-", 1000),
+        RangeRequest::Partial(vec![range(500, 599), range(0, 99)])
+    );
+}
+
+#[test]
+fn test_unsatisfiable_ranges() {
+    assert_eq!(parse_range("bytes=1000-", 1000), RangeRequest::Unsatisfiable);
+    assert_eq!(parse_range("bytes=-0", 1000), RangeRequest::Unsatisfiable);
+    assert_eq!(parse_range("bytes=0-10", 0), RangeRequest::Unsatisfiable);
+}
+
+#[test]
+fn test_malformed_headers_are_ignored() {
+    assert_eq!(parse_range("items=0-10", 1000), RangeRequest::Full);
+    assert_eq!(parse_range("bytes=", 1000), RangeRequest::Full);
+    assert_eq!(parse_range("bytes=10-5", 1000), RangeRequest::Full);
+    assert_eq!(parse_range("bytes=abc", 1000), RangeRequest::Full);
+    assert_eq!(parse_range("bytes=0-1,x-2", 1000), RangeRequest::Full);
+    assert_eq!(parse_range("bytes=-", 1000), RangeRequest::Full);
+}
+
+#[test]
+fn test_too_many_ranges_are_ignored() {
+    let many = (0..=MAX_RANGES).map(|i| format!("{}-{}", i * 2, i * 2)).collect::<Vec<_>>().join(",");
+    assert_eq!(parse_range(&format!("bytes={}", many), 1000), RangeRequest::Full);
+}
+
+#[test]
+fn test_multipart_length_matches_body() {
+    let ranges = [range(0, 9), range(100, 149)];
+    let body_len: usize = ranges
+        .iter()
+        .map(|r| part_header("b0undary", "application/pdf", r, 1000).len() + r.size() as usize + 2)
+        .sum::<usize>()
+        + closing_boundary("b0undary").len();
+
+    assert_eq!(multipart_length("b0undary", "application/pdf", &ranges, 1000), body_len as u64);
+    assert_eq!(range(100, 149).content_range(1000), "bytes 100-149/1000");
+}
+
+#[test]
+fn test_parse_rfc3339() {
+    assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(UNIX_EPOCH));
+    assert_eq!(parse_rfc3339("2024-10-23T10:00:00.123Z"), Some(UNIX_EPOCH + Duration::from_secs(1_729_677_600)));
+    assert_eq!(parse_rfc3339("2024-10-23T12:00:00+02:00"), Some(UNIX_EPOCH + Duration::from_secs(1_729_677_600)));
+    assert_eq!(parse_rfc3339("2000-02-29T00:00:00-00:30"), Some(UNIX_EPOCH + Duration::from_secs(951_784_200)));
+    assert_eq!(parse_rfc3339("2024-13-01T00:00:00Z"), None);
+    assert_eq!(parse_rfc3339("not a date"), None);
+}
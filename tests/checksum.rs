@@ -0,0 +1,58 @@
+use api_drive::services::checksum::{checksums_match, digest_header, hex_to_base64, is_hex_checksum, verify_stored_md5, Checksums};
+
+#[test]
+fn test_checksums_of_streamed_content() {
+    let mut checksums = Checksums::new();
+    for chunk in [&b"%P"[..], b"", b"DF"] {
+        checksums.update(chunk);
+    }
+    let digest = checksums.finish();
+
+    assert_eq!(digest.size, 4);
+    assert_eq!(digest.md5_hex(), "bfa4b10a76324b166cfdad5e02a63730");
+    assert_eq!(digest.sha256_hex(), "315d429b7714cedb6ad04ac31240145257692630457f3c88253c5beceac76027");
+}
+
+#[test]
+fn test_checksums_of_empty_content() {
+    let digest = Checksums::new().finish();
+    assert_eq!(digest.md5_hex(), "d41d8cd98f00b204e9800998ecf8427e");
+}
+
+#[test]
+fn test_hex_to_base64() {
+    assert_eq!(hex_to_base64("bfa4b10a76324b166cfdad5e02a63730").as_deref(), Some("v6SxCnYySxZs/a1eAqY3MA=="));
+    assert_eq!(hex_to_base64("BFA4B10A76324B166CFDAD5E02A63730").as_deref(), Some("v6SxCnYySxZs/a1eAqY3MA=="));
+    assert_eq!(hex_to_base64("abc"), None);
+    assert_eq!(hex_to_base64("zz"), None);
+}
+
+#[test]
+fn test_digest_header_lists_known_checksums() {
+    assert_eq!(
+        digest_header(Some("bfa4b10a76324b166cfdad5e02a63730"), Some("315d429b7714cedb6ad04ac31240145257692630457f3c88253c5beceac76027")).as_deref(),
+        Some("md5=v6SxCnYySxZs/a1eAqY3MA==,sha-256=MV1Cm3cUzttq0ErDEkAUUldpJjBFfzyIJTxb7OrHYCc=")
+    );
+    assert_eq!(digest_header(Some("bfa4b10a76324b166cfdad5e02a63730"), None).as_deref(), Some("md5=v6SxCnYySxZs/a1eAqY3MA=="));
+    assert_eq!(digest_header(None, None), None);
+}
+
+#[test]
+fn test_checksum_helpers() {
+    assert!(checksums_match("ABCDEF", "abcdef"));
+    assert!(!checksums_match("abcdef", "abcdee"));
+    assert!(is_hex_checksum("bfa4b10a76324b166cfdad5e02a63730", 16));
+    assert!(!is_hex_checksum("bfa4b10a76324b166cfdad5e02a6373", 16));
+    assert!(!is_hex_checksum("gfa4b10a76324b166cfdad5e02a63730", 16));
+}
+
+#[test]
+fn test_verify_stored_md5() {
+    let mut checksums = Checksums::new();
+    checksums.update(b"%PDF");
+    let digest = checksums.finish();
+
+    assert!(verify_stored_md5(Some("BFA4B10A76324B166CFDAD5E02A63730"), &digest).is_ok());
+    assert!(verify_stored_md5(Some("d41d8cd98f00b204e9800998ecf8427e"), &digest).unwrap_err().contains("Drive stored d41d8cd9"));
+    assert!(verify_stored_md5(None, &digest).unwrap_err().contains("Drive reported none"));
+}
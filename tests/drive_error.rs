@@ -0,0 +1,23 @@
+use actix_web::http::StatusCode;
+use anyhow::Context;
+use api_drive::api::drive_error::{drive_error_status, DriveApiError};
+
+#[test]
+fn test_status_maps_to_variant() {
+    assert_eq!(DriveApiError::new(404, "gone"), DriveApiError::NotFound("gone".to_string()));
+    assert_eq!(DriveApiError::new(403, "no"), DriveApiError::Forbidden("no".to_string()));
+    assert_eq!(DriveApiError::new(503, "busy"), DriveApiError::Status(503, "busy".to_string()));
+    assert_eq!(DriveApiError::new(503, "busy").status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[test]
+fn test_status_survives_added_context() {
+    let err = Err::<(), _>(DriveApiError::new(404, "File not found: abc."))
+        .context("Failed to trash file")
+        .context("Failed to trash file with ID: abc")
+        .unwrap_err();
+    assert_eq!(drive_error_status(&err), StatusCode::NOT_FOUND);
+    assert!(format!("{:?}", err).contains("File not found: abc."));
+
+    assert_eq!(drive_error_status(&anyhow::anyhow!("connection reset")), StatusCode::INTERNAL_SERVER_ERROR);
+}
@@ -0,0 +1,69 @@
+use api_drive::api::drive_query::{escape, is_rfc3339, DriveQuery};
+
+#[test]
+fn test_escape_quotes_and_backslashes() {
+    assert_eq!(escape("plain"), "plain");
+    assert_eq!(escape("O'Brien"), "O\\'Brien");
+    assert_eq!(escape("back\\slash"), "back\\\\slash");
+    assert_eq!(escape("\\'"), "\\\\\\'");
+}
+
+#[test]
+fn test_single_clauses() {
+    assert_eq!(DriveQuery::new().name_contains("report").build(), "name contains 'report'");
+    assert_eq!(DriveQuery::new().name_equals("Q3 'final'").build(), "name = 'Q3 \\'final\\''");
+    assert_eq!(DriveQuery::new().full_text_contains("invoice").build(), "fullText contains 'invoice'");
+    assert_eq!(DriveQuery::new().mime_type("application/pdf").build(), "mimeType = 'application/pdf'");
+    assert_eq!(DriveQuery::new().folders_only().build(), "mimeType = 'application/vnd.google-apps.folder'");
+    assert_eq!(DriveQuery::new().owner("me@example.com").build(), "'me@example.com' in owners");
+    assert_eq!(DriveQuery::new().in_parents("folder1").build(), "'folder1' in parents");
+    assert_eq!(DriveQuery::new().trashed(false).build(), "trashed = false");
+    assert_eq!(DriveQuery::new().starred(true).build(), "starred = true");
+    assert_eq!(
+        DriveQuery::new().modified_after("2024-01-01T00:00:00Z").build(),
+        "modifiedTime > '2024-01-01T00:00:00Z'"
+    );
+    assert_eq!(
+        DriveQuery::new().modified_before("2024-12-31T23:59:59Z").build(),
+        "modifiedTime < '2024-12-31T23:59:59Z'"
+    );
+}
+
+#[test]
+fn test_clauses_are_joined_with_and() {
+    let query = DriveQuery::new()
+        .name_contains("q3")
+        .mime_type("application/pdf")
+        .trashed(false);
+
+    assert_eq!(query.build(), "name contains 'q3' and mimeType = 'application/pdf' and trashed = false");
+}
+
+#[test]
+fn test_empty_query() {
+    let query = DriveQuery::new();
+
+    assert!(query.is_empty());
+    assert_eq!(query.build(), "");
+}
+
+#[test]
+fn test_injection_stays_inside_literal() {
+    let query = DriveQuery::new().in_parents("x' in parents or name contains '");
+
+    assert_eq!(query.build(), "'x\\' in parents or name contains \\'' in parents");
+}
+
+#[test]
+fn test_rfc3339_validation() {
+    assert!(is_rfc3339("2024-10-23T10:00:00Z"));
+    assert!(is_rfc3339("2024-10-23T10:00:00.123Z"));
+    assert!(is_rfc3339("2024-10-23T10:00:00-05:00"));
+    assert!(is_rfc3339("2024-10-23t10:00:00+01:30"));
+
+    assert!(!is_rfc3339("2024-10-23"));
+    assert!(!is_rfc3339("2024-10-23 10:00:00Z"));
+    assert!(!is_rfc3339("2024-10-23T10:00:00"));
+    assert!(!is_rfc3339("2024-10-23T10:00:00.Z"));
+    assert!(!is_rfc3339("2024-10-23T10:00:00Z' or trashed = true"));
+}
@@ -1,6 +1,12 @@
 use actix_web::{test, web, App, http::header};
-use api_drive::handlers::google_drive_handler::{download_pdf_file_by_id, get_list_folders};
-use api_drive::services::google_drive_service::FolderInfo;
+use api_drive::handlers::folder_handler::{create_folder, CreatedFolder};
+use api_drive::handlers::file_handler::{copy_file, get_file_metadata, update_file};
+use api_drive::services::folder_copy::CopyResult;
+use api_drive::handlers::trash_handler::{delete_file, empty_trash, restore_file};
+use api_drive::handlers::permission_handler::{create_permission, delete_permission, list_permissions, update_permission};
+use api_drive::api::permissions::{Permission, PermissionList, PermissionRole};
+use api_drive::handlers::google_drive_handler::{download_pdf_file_by_id, get_list_files_in_folder, get_list_folders, search_files, verify_file, VerifyResult};
+use api_drive::services::google_drive_service::{FileInfo, FileMetadataPage, FilePage, FolderPage};
 
 #[path = "mocks/google_drive_service_mock.rs"]
 mod google_drive_service_mock;
@@ -8,7 +14,7 @@ mod google_drive_service_mock;
 #[path = "mocks/config_mock.rs"]
 mod config_mock;
 
-use google_drive_service_mock::MockGoogleDriveService;
+use google_drive_service_mock::{MockGoogleDriveService, MOCK_CONTENT, MOCK_CORRUPT_ID, MOCK_DOC_ID, MOCK_LARGE_DOC_ID, MOCK_LOCKED_ID, MOCK_MD5, MOCK_MISSING_ID, MOCK_OWNER_PERMISSION_ID, MOCK_RESTRICTED_ID, MOCK_SHA256};
 use config_mock::mock_config;
 
 #[actix_web::test]
@@ -26,7 +32,7 @@ async fn test_get_list_folders_success() {
     ).await;
 
     let req = test::TestRequest::get()
-        .uri("/drive/list-folders")
+        .uri("/drive/list-folders?all=true")
         .insert_header((header::AUTHORIZATION, "Bearer mock_token"))
         .to_request();
 
@@ -34,11 +40,100 @@ async fn test_get_list_folders_success() {
 
     assert!(resp.status().is_success(), "Response was not successful");
 
-    let result: Vec<FolderInfo> = test::read_body_json(resp).await;
+    let result: FolderPage = test::read_body_json(resp).await;
+
+    assert_eq!(result.folders.len(), 2);
+    assert_eq!(result.folders[0].name, Some("Folder 1".to_string()));
+    assert_eq!(result.folders[1].name, Some("Folder 2".to_string()));
+    assert_eq!(result.next_page_token, None);
+}
+
+#[actix_web::test]
+async fn test_get_list_folders_follows_page_token() {
+    let mock_service = web::Data::new(MockGoogleDriveService);
+    let config_data = web::Data::new(mock_config());
+
+    let app = test::init_service(
+        App::new()
+            .app_data(mock_service.clone())
+            .app_data(config_data.clone())
+            .route("/drive/list-folders", web::get().to(get_list_folders::<MockGoogleDriveService>)),
+    ).await;
+
+    let req = test::TestRequest::get()
+        .uri("/drive/list-folders?page_size=1")
+        .insert_header((header::AUTHORIZATION, "Bearer mock_token"))
+        .to_request();
+    let first: FolderPage = test::call_and_read_body_json(&app, req).await;
+
+    assert_eq!(first.folders.len(), 1);
+    assert_eq!(first.folders[0].name, Some("Folder 1".to_string()));
+    let next_page_token = first.next_page_token.expect("First page should carry a next_page_token");
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/drive/list-folders?page_size=1&page_token={}", next_page_token))
+        .insert_header((header::AUTHORIZATION, "Bearer mock_token"))
+        .to_request();
+    let second: FolderPage = test::call_and_read_body_json(&app, req).await;
+
+    assert_eq!(second.folders.len(), 1);
+    assert_eq!(second.folders[0].name, Some("Folder 2".to_string()));
+    assert_eq!(second.next_page_token, None);
+}
+
+#[actix_web::test]
+async fn test_get_list_folders_rejects_invalid_page_size() {
+    let mock_service = web::Data::new(MockGoogleDriveService);
+    let config_data = web::Data::new(mock_config());
+
+    let app = test::init_service(
+        App::new()
+            .app_data(mock_service.clone())
+            .app_data(config_data.clone())
+            .route("/drive/list-folders", web::get().to(get_list_folders::<MockGoogleDriveService>)),
+    ).await;
+
+    for page_size in ["0", "1001"] {
+        let req = test::TestRequest::get()
+            .uri(&format!("/drive/list-folders?page_size={}", page_size))
+            .insert_header((header::AUTHORIZATION, "Bearer mock_token"))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), 400, "Expected 400 status for page_size={}", page_size);
+    }
+}
+
+#[actix_web::test]
+async fn test_get_list_files_in_folder_success() {
+    let mock_service = web::Data::new(MockGoogleDriveService);
+    let config_data = web::Data::new(mock_config());
+
+    let app = test::init_service(
+        App::new()
+            .app_data(mock_service.clone())
+            .app_data(config_data.clone())
+            .route("/drive/files", web::get().to(get_list_files_in_folder::<MockGoogleDriveService>)),
+    ).await;
+
+    let req = test::TestRequest::get()
+        .uri("/drive/files?folder_id=folder1&page_size=50")
+        .insert_header((header::AUTHORIZATION, "Bearer mock_token"))
+        .to_request();
+    let result: FilePage = test::call_and_read_body_json(&app, req).await;
+
+    assert_eq!(result.files.len(), 2);
+    assert_eq!(result.files[0].id, Some("file1".to_string()));
+    assert_eq!(result.next_page_token, None);
 
-    assert_eq!(result.len(), 2);
-    assert_eq!(result[0].name, Some("Folder 1".to_string()));
-    assert_eq!(result[1].name, Some("Folder 2".to_string()));
+    let req = test::TestRequest::get()
+        .uri("/drive/files?page_size=50")
+        .insert_header((header::AUTHORIZATION, "Bearer mock_token"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), 400, "Expected 400 status for missing folder_id");
 }
 
 #[actix_web::test]
@@ -91,11 +186,136 @@ async fn test_download_pdf_file_by_id_success() {
     let resp = test::call_service(&app, req).await;
 
     assert!(resp.status().is_success(), "Response was not successful");
+    assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), "application/pdf");
+    assert_eq!(resp.headers().get(header::CONTENT_LENGTH).unwrap(), "4");
+    assert_eq!(resp.headers().get(header::ETAG).unwrap(), format!("\"{}\"", MOCK_MD5).as_str());
+    assert_eq!(resp.headers().get(header::LAST_MODIFIED).unwrap(), "Wed, 23 Oct 2024 10:00:00 GMT");
+    assert_eq!(resp.headers().get(header::ACCEPT_RANGES).unwrap(), "bytes");
+    let disposition = resp.headers().get(header::CONTENT_DISPOSITION).unwrap().to_str().unwrap();
+    assert!(disposition.starts_with("attachment"));
+    assert!(disposition.contains("filename=\"Informe a_o.pdf\""));
+    assert!(disposition.contains("filename*=UTF-8''Informe%20a%C3%B1o.pdf"));
+    assert_eq!(resp.headers().get("Content-MD5").unwrap(), "v6SxCnYySxZs/a1eAqY3MA==");
+    assert_eq!(
+        resp.headers().get("Digest").unwrap(),
+        "md5=v6SxCnYySxZs/a1eAqY3MA==,sha-256=MV1Cm3cUzttq0ErDEkAUUldpJjBFfzyIJTxb7OrHYCc="
+    );
 
     let body = test::read_body(resp).await;
     assert_eq!(body, web::Bytes::from_static(&[0x25, 0x50, 0x44, 0x46]));
 }
 
+async fn download_with_headers(headers: &[(header::HeaderName, &str)]) -> actix_web::dev::ServiceResponse {
+    download("/drive/files/test_file_id", headers).await
+}
+
+async fn download(uri: &str, headers: &[(header::HeaderName, &str)]) -> actix_web::dev::ServiceResponse {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(MockGoogleDriveService))
+            .app_data(web::Data::new(mock_config()))
+            .route("/drive/files/{file_id}", web::get().to(download_pdf_file_by_id::<MockGoogleDriveService>)),
+    )
+    .await;
+
+    let mut req = test::TestRequest::get()
+        .uri(uri)
+        .insert_header((header::AUTHORIZATION, "Bearer mock_token"));
+    for (name, value) in headers {
+        req = req.insert_header((name.clone(), *value));
+    }
+
+    test::call_service(&app, req.to_request()).await
+}
+
+#[actix_web::test]
+async fn test_download_single_range() {
+    let resp = download_with_headers(&[(header::RANGE, "bytes=1-2")]).await;
+
+    assert_eq!(resp.status(), 206);
+    assert_eq!(resp.headers().get(header::CONTENT_RANGE).unwrap(), "bytes 1-2/4");
+    assert_eq!(resp.headers().get(header::CONTENT_LENGTH).unwrap(), "2");
+    assert!(resp.headers().get("Content-MD5").is_none());
+    assert!(resp.headers().get("Digest").is_some());
+    assert_eq!(test::read_body(resp).await, web::Bytes::from_static(&MOCK_CONTENT[1..3]));
+}
+
+#[actix_web::test]
+async fn test_download_multiple_ranges() {
+    let resp = download_with_headers(&[(header::RANGE, "bytes=0-0,-2")]).await;
+
+    assert_eq!(resp.status(), 206);
+    let content_type = resp.headers().get(header::CONTENT_TYPE).unwrap().to_str().unwrap().to_string();
+    let boundary = content_type.strip_prefix("multipart/byteranges; boundary=").expect("multipart content type");
+    let expected = format!(
+        "--{b}\r\nContent-Type: application/pdf\r\nContent-Range: bytes 0-0/4\r\n\r\n%\r\n\
+         --{b}\r\nContent-Type: application/pdf\r\nContent-Range: bytes 2-3/4\r\n\r\nDF\r\n\
+         --{b}--\r\n",
+        b = boundary
+    );
+    assert_eq!(resp.headers().get(header::CONTENT_LENGTH).unwrap(), expected.len().to_string().as_str());
+    assert_eq!(test::read_body(resp).await, web::Bytes::from(expected));
+}
+
+#[actix_web::test]
+async fn test_download_unsatisfiable_range() {
+    let resp = download_with_headers(&[(header::RANGE, "bytes=10-20")]).await;
+
+    assert_eq!(resp.status(), 416);
+    assert_eq!(resp.headers().get(header::CONTENT_RANGE).unwrap(), "bytes */4");
+}
+
+#[actix_web::test]
+async fn test_download_malformed_range_serves_whole_file() {
+    let resp = download_with_headers(&[(header::RANGE, "bytes=2-1")]).await;
+
+    assert_eq!(resp.status(), 200);
+    assert_eq!(test::read_body(resp).await, web::Bytes::from_static(MOCK_CONTENT));
+}
+
+#[actix_web::test]
+async fn test_download_not_modified() {
+    let etag = format!("\"{}\"", MOCK_MD5);
+    let resp = download_with_headers(&[(header::IF_NONE_MATCH, etag.as_str())]).await;
+    assert_eq!(resp.status(), 304);
+    assert_eq!(resp.headers().get(header::ETAG).unwrap(), etag.as_str());
+
+    let weak = format!("W/{}", etag);
+    let resp = download_with_headers(&[(header::IF_NONE_MATCH, weak.as_str())]).await;
+    assert_eq!(resp.status(), 304);
+
+    let resp = download_with_headers(&[(header::IF_MODIFIED_SINCE, "Thu, 24 Oct 2024 00:00:00 GMT")]).await;
+    assert_eq!(resp.status(), 304);
+}
+
+#[actix_web::test]
+async fn test_download_modified() {
+    let resp = download_with_headers(&[(header::IF_NONE_MATCH, "\"stale\"")]).await;
+    assert_eq!(resp.status(), 200);
+
+    let resp = download_with_headers(&[(header::IF_MODIFIED_SINCE, "Tue, 22 Oct 2024 00:00:00 GMT")]).await;
+    assert_eq!(resp.status(), 200);
+
+    // If-None-Match wins over If-Modified-Since.
+    let resp = download_with_headers(&[
+        (header::IF_NONE_MATCH, "\"stale\""),
+        (header::IF_MODIFIED_SINCE, "Thu, 24 Oct 2024 00:00:00 GMT"),
+    ])
+    .await;
+    assert_eq!(resp.status(), 200);
+}
+
+#[actix_web::test]
+async fn test_download_if_range() {
+    let etag = format!("\"{}\"", MOCK_MD5);
+    let resp = download_with_headers(&[(header::RANGE, "bytes=0-1"), (header::IF_RANGE, etag.as_str())]).await;
+    assert_eq!(resp.status(), 206);
+
+    let resp = download_with_headers(&[(header::RANGE, "bytes=0-1"), (header::IF_RANGE, "\"stale\"")]).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(test::read_body(resp).await, web::Bytes::from_static(MOCK_CONTENT));
+}
+
 #[actix_web::test]
 async fn test_download_pdf_file_by_id_unauthorized() {
     let mock_service = web::Data::new(MockGoogleDriveService);
@@ -121,4 +341,504 @@ async fn test_download_pdf_file_by_id_unauthorized() {
 
     let body = test::read_body(resp).await;
     assert_eq!(body, web::Bytes::from_static(b"Authorization token missing or invalid"));
-}
\ No newline at end of file
+}
+#[actix_web::test]
+async fn test_search_files_compiles_escaped_query() {
+    let mock_service = web::Data::new(MockGoogleDriveService);
+    let config_data = web::Data::new(mock_config());
+
+    let app = test::init_service(
+        App::new()
+            .app_data(mock_service.clone())
+            .app_data(config_data.clone())
+            .route("/drive/search", web::get().to(search_files::<MockGoogleDriveService>)),
+    ).await;
+
+    let req = test::TestRequest::get()
+        .uri("/drive/search?name=O%27Brien&mime_type=application%2Fpdf&modified_after=2024-01-01T00%3A00%3A00Z&starred=true")
+        .insert_header((header::AUTHORIZATION, "Bearer mock_token"))
+        .to_request();
+    let result: FilePage = test::call_and_read_body_json(&app, req).await;
+
+    assert_eq!(
+        result.files[0].name.as_deref(),
+        Some("name contains 'O\\'Brien' and mimeType = 'application/pdf' and modifiedTime > '2024-01-01T00:00:00Z' and trashed = false and starred = true")
+    );
+}
+
+#[actix_web::test]
+async fn test_search_files_rejects_malformed_timestamp() {
+    let mock_service = web::Data::new(MockGoogleDriveService);
+    let config_data = web::Data::new(mock_config());
+
+    let app = test::init_service(
+        App::new()
+            .app_data(mock_service.clone())
+            .app_data(config_data.clone())
+            .route("/drive/search", web::get().to(search_files::<MockGoogleDriveService>)),
+    ).await;
+
+    let req = test::TestRequest::get()
+        .uri("/drive/search?modified_before=yesterday")
+        .insert_header((header::AUTHORIZATION, "Bearer mock_token"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), 400, "Expected 400 status for malformed timestamp");
+}
+
+#[actix_web::test]
+async fn test_download_exports_google_doc_to_pdf_by_default() {
+    let resp = download(&format!("/drive/files/{}", MOCK_DOC_ID), &[]).await;
+
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), "application/pdf");
+    assert_eq!(resp.headers().get(header::CONTENT_DISPOSITION).unwrap(), "attachment; filename=\"Notas.pdf\"");
+    assert_eq!(resp.headers().get(header::ACCEPT_RANGES).unwrap(), "none");
+    assert!(resp.headers().get(header::ETAG).is_none());
+    assert_eq!(test::read_body(resp).await, web::Bytes::from_static(b"exported as application/pdf"));
+}
+
+#[actix_web::test]
+async fn test_download_exports_google_doc_to_requested_format() {
+    let resp = download(&format!("/drive/files/{}?format=DOCX", MOCK_DOC_ID), &[(header::RANGE, "bytes=0-1")]).await;
+
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get(header::CONTENT_TYPE).unwrap(),
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+    );
+    assert_eq!(resp.headers().get(header::CONTENT_DISPOSITION).unwrap(), "attachment; filename=\"Notas.docx\"");
+    assert!(resp.headers().get(header::CONTENT_RANGE).is_none());
+}
+
+#[actix_web::test]
+async fn test_download_rejects_format_unsupported_for_source_type() {
+    let resp = download(&format!("/drive/files/{}?format=xlsx", MOCK_DOC_ID), &[]).await;
+
+    assert_eq!(resp.status(), 400);
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("docx"), "{}", body);
+}
+
+#[actix_web::test]
+async fn test_download_rejects_format_for_binary_file() {
+    let resp = download("/drive/files/test_file_id?format=pdf", &[]).await;
+
+    assert_eq!(resp.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_export_not_modified() {
+    let resp = download(&format!("/drive/files/{}", MOCK_DOC_ID), &[(header::IF_MODIFIED_SINCE, "Wed, 23 Oct 2024 10:00:00 GMT")]).await;
+
+    assert_eq!(resp.status(), 304);
+}
+
+async fn verify(uri: &str) -> actix_web::dev::ServiceResponse {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(MockGoogleDriveService))
+            .app_data(web::Data::new(mock_config()))
+            .route("/drive/files/{file_id}/verify", web::get().to(verify_file::<MockGoogleDriveService>)),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri(uri)
+        .insert_header((header::AUTHORIZATION, "Bearer mock_token"))
+        .to_request();
+    test::call_service(&app, req).await
+}
+
+#[actix_web::test]
+async fn test_verify_intact_file() {
+    let resp = verify(&format!("/drive/files/test_file_id/verify?md5={}", MOCK_MD5.to_uppercase())).await;
+
+    assert_eq!(resp.status(), 200);
+    let result: VerifyResult = test::read_body_json(resp).await;
+    assert!(result.verified);
+    assert_eq!(result.size, MOCK_CONTENT.len() as u64);
+    assert_eq!(result.md5_checksum, MOCK_MD5);
+    assert_eq!(result.sha256_checksum, MOCK_SHA256);
+    assert!(result.mismatches.is_empty());
+}
+
+#[actix_web::test]
+async fn test_verify_reports_mismatches() {
+    let resp = verify(&format!("/drive/files/{}/verify", MOCK_CORRUPT_ID)).await;
+
+    assert_eq!(resp.status(), 409);
+    let result: VerifyResult = test::read_body_json(resp).await;
+    assert!(!result.verified);
+    assert_eq!(result.mismatches.len(), 2);
+    assert!(result.mismatches[0].starts_with("Drive MD5"));
+
+    let resp = verify(&format!("/drive/files/test_file_id/verify?sha256={}", "0".repeat(64))).await;
+    assert_eq!(resp.status(), 409);
+    let result: VerifyResult = test::read_body_json(resp).await;
+    assert_eq!(result.mismatches.len(), 1);
+    assert!(result.mismatches[0].starts_with("expected SHA-256"));
+}
+
+#[actix_web::test]
+async fn test_verify_rejects_invalid_requests() {
+    assert_eq!(verify("/drive/files/test_file_id/verify?md5=abc").await.status(), 400);
+    assert_eq!(verify(&format!("/drive/files/{}/verify", MOCK_DOC_ID)).await.status(), 400);
+}
+
+async fn post_folder(body: serde_json::Value) -> actix_web::dev::ServiceResponse {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(MockGoogleDriveService))
+            .app_data(web::Data::new(mock_config()))
+            .route("/drive/folders", web::post().to(create_folder::<MockGoogleDriveService>)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/drive/folders")
+        .insert_header((header::AUTHORIZATION, "Bearer mock_token"))
+        .set_json(body)
+        .to_request();
+    test::call_service(&app, req).await
+}
+
+#[actix_web::test]
+async fn test_create_folder_by_name() {
+    let resp = post_folder(serde_json::json!({ "name": "Invoices", "parent_id": "folder1" })).await;
+
+    assert_eq!(resp.status(), 201);
+    let folder: CreatedFolder = test::read_body_json(resp).await;
+    assert_eq!(folder.id, "new_Invoices");
+    assert_eq!(folder.name, "Invoices");
+    assert_eq!(folder.parent_id, "folder1");
+    assert!(folder.created);
+    assert!(folder.path.is_empty());
+}
+
+#[actix_web::test]
+async fn test_create_folder_path_reuses_existing_folders() {
+    let resp = post_folder(serde_json::json!({ "path": "Invoices/2026/Q3" })).await;
+
+    assert_eq!(resp.status(), 201);
+    let folder: CreatedFolder = test::read_body_json(resp).await;
+    assert_eq!(folder.id, "new_Q3");
+    assert_eq!(folder.parent_id, "invoices_2026_id");
+    let ids: Vec<(&str, bool)> = folder.path.iter().map(|segment| (segment.id.as_str(), segment.created)).collect();
+    assert_eq!(ids, vec![("invoices_id", false), ("invoices_2026_id", false), ("new_Q3", true)]);
+
+    let resp = post_folder(serde_json::json!({ "path": "/Invoices/2026/" })).await;
+    assert_eq!(resp.status(), 200);
+    let folder: CreatedFolder = test::read_body_json(resp).await;
+    assert_eq!(folder.id, "invoices_2026_id");
+    assert_eq!(folder.parent_id, "invoices_id");
+    assert!(!folder.created);
+}
+
+#[actix_web::test]
+async fn test_create_folder_rejects_invalid_requests() {
+    assert_eq!(post_folder(serde_json::json!({})).await.status(), 400);
+    assert_eq!(post_folder(serde_json::json!({ "name": "a", "path": "a/b" })).await.status(), 400);
+    assert_eq!(post_folder(serde_json::json!({ "name": "  " })).await.status(), 400);
+    assert_eq!(post_folder(serde_json::json!({ "path": "a/../b" })).await.status(), 400);
+    assert_eq!(post_folder(serde_json::json!({ "path": "//" })).await.status(), 400);
+}
+
+async fn trash_request(req: test::TestRequest) -> actix_web::dev::ServiceResponse {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(MockGoogleDriveService))
+            .app_data(web::Data::new(mock_config()))
+            .route("/drive/files/{file_id}", web::delete().to(delete_file::<MockGoogleDriveService>))
+            .route("/drive/files/{file_id}/restore", web::post().to(restore_file::<MockGoogleDriveService>))
+            .route("/drive/trash/empty", web::post().to(empty_trash::<MockGoogleDriveService>)),
+    )
+    .await;
+
+    let req = req.insert_header((header::AUTHORIZATION, "Bearer mock_token")).to_request();
+    test::call_service(&app, req).await
+}
+
+#[actix_web::test]
+async fn test_delete_file_trashes_by_default() {
+    let resp = trash_request(test::TestRequest::delete().uri("/drive/files/file1")).await;
+
+    assert_eq!(resp.status(), 200);
+    let file: FileInfo = test::read_body_json(resp).await;
+    assert_eq!(file.id.as_deref(), Some("file1"));
+
+    let resp = trash_request(test::TestRequest::delete().uri("/drive/files/file1?permanent=true")).await;
+    assert_eq!(resp.status(), 204);
+}
+
+#[actix_web::test]
+async fn test_restore_file_and_empty_trash() {
+    let resp = trash_request(test::TestRequest::post().uri("/drive/files/file1/restore")).await;
+    assert_eq!(resp.status(), 200);
+
+    let resp = trash_request(test::TestRequest::post().uri("/drive/trash/empty")).await;
+    assert_eq!(resp.status(), 204);
+}
+
+#[actix_web::test]
+async fn test_trash_operations_pass_on_not_found_and_forbidden() {
+    let missing = format!("/drive/files/{}", MOCK_MISSING_ID);
+    let locked = format!("/drive/files/{}", MOCK_LOCKED_ID);
+
+    assert_eq!(trash_request(test::TestRequest::delete().uri(&missing)).await.status(), 404);
+    assert_eq!(trash_request(test::TestRequest::delete().uri(&format!("{}?permanent=true", locked))).await.status(), 403);
+    assert_eq!(trash_request(test::TestRequest::post().uri(&format!("{}/restore", missing))).await.status(), 404);
+
+    let resp = trash_request(test::TestRequest::delete().uri(&locked)).await;
+    assert_eq!(resp.status(), 403);
+    let body = test::read_body(resp).await;
+    assert!(String::from_utf8_lossy(&body).contains("sufficient permissions"));
+}
+
+#[actix_web::test]
+async fn test_download_missing_file_is_not_found() {
+    let resp = download(&format!("/drive/files/{}", MOCK_MISSING_ID), &[]).await;
+    assert_eq!(resp.status(), 404);
+    assert_eq!(verify(&format!("/drive/files/{}/verify", MOCK_LOCKED_ID)).await.status(), 403);
+}
+
+#[actix_web::test]
+async fn test_refused_download_and_export_keep_drive_status() {
+    let resp = download(&format!("/drive/files/{}", MOCK_RESTRICTED_ID), &[]).await;
+    assert_eq!(resp.status(), 403);
+    let body = test::read_body(resp).await;
+    assert!(String::from_utf8_lossy(&body).contains("permission to download"));
+
+    assert_eq!(verify(&format!("/drive/files/{}/verify", MOCK_RESTRICTED_ID)).await.status(), 403);
+
+    let resp = download(&format!("/drive/files/{}", MOCK_LARGE_DOC_ID), &[]).await;
+    assert_eq!(resp.status(), 403);
+    let body = test::read_body(resp).await;
+    assert!(String::from_utf8_lossy(&body).contains("too large to be exported"));
+}
+
+async fn file_request(req: test::TestRequest) -> actix_web::dev::ServiceResponse {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(MockGoogleDriveService))
+            .app_data(web::Data::new(mock_config()))
+            .route("/drive/files/{file_id}", web::patch().to(update_file::<MockGoogleDriveService>))
+            .route("/drive/files/{file_id}/copy", web::post().to(copy_file::<MockGoogleDriveService>)),
+    )
+    .await;
+
+    let req = req.insert_header((header::AUTHORIZATION, "Bearer mock_token")).to_request();
+    test::call_service(&app, req).await
+}
+
+#[actix_web::test]
+async fn test_update_file_renames_and_moves() {
+    let body = serde_json::json!({ "name": "renamed.pdf", "add_parents": ["folder2"], "remove_parents": ["folder1"] });
+    let resp = file_request(test::TestRequest::patch().uri("/drive/files/file1").set_json(body)).await;
+
+    assert_eq!(resp.status(), 200);
+    let file: FileInfo = test::read_body_json(resp).await;
+    assert_eq!(file.name.as_deref(), Some("renamed.pdf"));
+}
+
+#[actix_web::test]
+async fn test_update_file_rejects_invalid_changes() {
+    let patch = |body: serde_json::Value| file_request(test::TestRequest::patch().uri("/drive/files/file1").set_json(body));
+
+    assert_eq!(patch(serde_json::json!({})).await.status(), 400);
+    assert_eq!(patch(serde_json::json!({ "name": " " })).await.status(), 400);
+    assert_eq!(patch(serde_json::json!({ "add_parents": ["a"], "remove_parents": ["a"] })).await.status(), 400);
+
+    let missing = test::TestRequest::patch().uri(&format!("/drive/files/{}", MOCK_MISSING_ID)).set_json(serde_json::json!({ "name": "x" }));
+    assert_eq!(file_request(missing).await.status(), 404);
+}
+
+#[actix_web::test]
+async fn test_copy_file() {
+    let body = serde_json::json!({ "name": "copy.pdf", "parent_id": "folder2" });
+    let resp = file_request(test::TestRequest::post().uri("/drive/files/file1/copy").set_json(body)).await;
+
+    assert_eq!(resp.status(), 201);
+    let copy: CopyResult = test::read_body_json(resp).await;
+    assert_eq!(copy.id, "copy_of_file1");
+    assert_eq!(copy.name.as_deref(), Some("copy.pdf"));
+    assert_eq!((copy.folders_copied, copy.files_copied), (0, 1));
+
+    // The body is optional.
+    let resp = file_request(test::TestRequest::post().uri("/drive/files/file1/copy")).await;
+    assert_eq!(resp.status(), 201);
+
+    let resp = file_request(test::TestRequest::post().uri(&format!("/drive/files/{}/copy", MOCK_LOCKED_ID))).await;
+    assert_eq!(resp.status(), 403);
+}
+
+#[actix_web::test]
+async fn test_copy_folder_reports_partial_copy() {
+    let resp = file_request(test::TestRequest::post().uri("/drive/files/tree_root/copy").set_json(serde_json::json!({}))).await;
+
+    assert_eq!(resp.status(), 207);
+    let copy: CopyResult = test::read_body_json(resp).await;
+    assert_eq!(copy.id, "new_Folder tree_root");
+    assert_eq!(copy.mime_type.as_deref(), Some("application/vnd.google-apps.folder"));
+    assert_eq!((copy.folders_copied, copy.files_copied), (5, 4));
+    assert_eq!(copy.failures.len(), 3);
+
+    let resp = file_request(test::TestRequest::post().uri("/drive/files/deep/copy").set_json(serde_json::json!({ "name": "Deep copy" }))).await;
+    assert_eq!(resp.status(), 201);
+    let copy: CopyResult = test::read_body_json(resp).await;
+    assert_eq!(copy.id, "new_Deep copy");
+    assert!(copy.failures.is_empty());
+}
+
+async fn get_json(uri: &str) -> (actix_web::http::StatusCode, serde_json::Value) {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(MockGoogleDriveService))
+            .app_data(web::Data::new(mock_config()))
+            .route("/drive/files/{file_id}/metadata", web::get().to(get_file_metadata::<MockGoogleDriveService>))
+            .route("/drive/list-folders", web::get().to(get_list_folders::<MockGoogleDriveService>))
+            .route("/drive/files", web::get().to(get_list_files_in_folder::<MockGoogleDriveService>))
+            .route("/drive/search", web::get().to(search_files::<MockGoogleDriveService>)),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri(uri)
+        .insert_header((header::AUTHORIZATION, "Bearer mock_token"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let status = resp.status();
+    let body = test::read_body(resp).await;
+    (status, serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null))
+}
+
+#[actix_web::test]
+async fn test_file_metadata_returns_every_field_by_default() {
+    let (status, body) = get_json("/drive/files/file1/metadata").await;
+
+    assert_eq!(status, 200);
+    assert_eq!(body["size"], MOCK_CONTENT.len());
+    assert_eq!(body["md5_checksum"], MOCK_MD5);
+    assert_eq!(body["owners"][0]["email_address"], "ana@example.com");
+    assert_eq!(body["capabilities"]["can_edit"], true);
+    assert_eq!(body["app_properties"]["invoice"], "2026-001");
+}
+
+#[actix_web::test]
+async fn test_file_metadata_projects_selected_fields() {
+    let (status, body) = get_json("/drive/files/file1/metadata?fields=name,webViewLink").await;
+
+    assert_eq!(status, 200);
+    let mut keys: Vec<&String> = body.as_object().unwrap().keys().collect();
+    keys.sort();
+    assert_eq!(keys, vec!["id", "name", "web_view_link"]);
+}
+
+#[actix_web::test]
+async fn test_file_metadata_errors() {
+    let (status, _) = get_json("/drive/files/file1/metadata?fields=name,exportLinks").await;
+    assert_eq!(status, 400);
+
+    let (status, _) = get_json(&format!("/drive/files/{}/metadata", MOCK_MISSING_ID)).await;
+    assert_eq!(status, 404);
+}
+
+#[actix_web::test]
+async fn test_listings_accept_fields_projection() {
+    let (status, body) = get_json("/drive/search?name=report&fields=size,starred").await;
+    assert_eq!(status, 200);
+    let page: FileMetadataPage = serde_json::from_value(body).unwrap();
+    assert_eq!(page.files[0].id.as_deref(), Some("name contains 'report' and trashed = false"));
+    assert_eq!(page.files[0].size, Some(MOCK_CONTENT.len() as u64));
+    assert_eq!(page.files[0].starred, Some(false));
+    assert_eq!(page.files[0].name, None);
+
+    let (_, body) = get_json("/drive/files?folder_id=folder1&fields=name").await;
+    assert_eq!(body["files"][0]["id"], "'folder1' in parents and trashed = false");
+    assert_eq!(body["files"][1]["name"], "file2.pdf");
+
+    let (status, _) = get_json(&format!("/drive/files?folder_id={}&fields=name", MOCK_MISSING_ID)).await;
+    assert_eq!(status, 404);
+    let (status, _) = get_json(&format!("/drive/files?folder_id={}&fields=name", MOCK_LOCKED_ID)).await;
+    assert_eq!(status, 403);
+
+    let (_, body) = get_json("/drive/list-folders?fields=parents").await;
+    assert_eq!(body["files"][0]["id"], "mimeType = 'application/vnd.google-apps.folder'");
+    assert_eq!(body["files"][0]["parents"][0], "parent_id");
+
+    let (status, _) = get_json("/drive/list-folders?fields=nope").await;
+    assert_eq!(status, 400);
+
+    // Without fields the listings keep their summary shape.
+    let (_, body) = get_json("/drive/list-folders").await;
+    assert!(body["folders"].is_array());
+}
+
+async fn permission_request(req: test::TestRequest) -> actix_web::dev::ServiceResponse {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(MockGoogleDriveService))
+            .app_data(web::Data::new(mock_config()))
+            .route("/drive/files/{file_id}/permissions", web::get().to(list_permissions::<MockGoogleDriveService>))
+            .route("/drive/files/{file_id}/permissions", web::post().to(create_permission::<MockGoogleDriveService>))
+            .route("/drive/files/{file_id}/permissions/{permission_id}", web::patch().to(update_permission::<MockGoogleDriveService>))
+            .route("/drive/files/{file_id}/permissions/{permission_id}", web::delete().to(delete_permission::<MockGoogleDriveService>)),
+    )
+    .await;
+
+    let req = req.insert_header((header::AUTHORIZATION, "Bearer mock_token")).to_request();
+    test::call_service(&app, req).await
+}
+
+#[actix_web::test]
+async fn test_list_and_share_permissions() {
+    let resp = permission_request(test::TestRequest::get().uri("/drive/files/file1/permissions")).await;
+    assert_eq!(resp.status(), 200);
+    let list: PermissionList = test::read_body_json(resp).await;
+    assert_eq!(list.permissions.len(), 1);
+    assert_eq!(list.permissions[0].role, PermissionRole::Owner);
+
+    let body = serde_json::json!({ "type": "user", "role": "writer", "email_address": "ana@example.com", "email_message": "Have a look" });
+    let resp = permission_request(test::TestRequest::post().uri("/drive/files/file1/permissions").set_json(body)).await;
+    assert_eq!(resp.status(), 201);
+    let permission: Permission = test::read_body_json(resp).await;
+    assert_eq!(permission.id.as_deref(), Some("new_permission"));
+    assert_eq!(permission.email_address.as_deref(), Some("ana@example.com"));
+}
+
+#[actix_web::test]
+async fn test_share_rejects_invalid_permissions() {
+    let post = |body: serde_json::Value| permission_request(test::TestRequest::post().uri("/drive/files/file1/permissions").set_json(body));
+
+    assert_eq!(post(serde_json::json!({ "type": "user", "role": "reader" })).await.status(), 400);
+    assert_eq!(post(serde_json::json!({ "type": "domain", "role": "reader" })).await.status(), 400);
+    assert_eq!(post(serde_json::json!({ "type": "anyone", "role": "everything" })).await.status(), 400);
+    assert_eq!(post(serde_json::json!({ "type": "anyone", "role": "reader", "email_message": "hi" })).await.status(), 400);
+    assert_eq!(post(serde_json::json!({ "type": "user", "role": "owner", "email_address": "ana@example.com" })).await.status(), 400);
+
+    let owner = serde_json::json!({ "type": "user", "role": "owner", "email_address": "ana@example.com", "transfer_ownership": true });
+    assert_eq!(post(owner).await.status(), 201);
+}
+
+#[actix_web::test]
+async fn test_update_and_delete_permissions() {
+    let uri = format!("/drive/files/file1/permissions/{}", MOCK_OWNER_PERMISSION_ID);
+
+    let body = serde_json::json!({ "role": "commenter", "expiration_time": "2027-01-01T00:00:00Z" });
+    let resp = permission_request(test::TestRequest::patch().uri(&uri).set_json(body)).await;
+    assert_eq!(resp.status(), 200);
+    let permission: Permission = test::read_body_json(resp).await;
+    assert_eq!(permission.role, PermissionRole::Commenter);
+    assert_eq!(permission.expiration_time.as_deref(), Some("2027-01-01T00:00:00Z"));
+
+    let owner_without_transfer = test::TestRequest::patch().uri(&uri).set_json(serde_json::json!({ "role": "owner" }));
+    assert_eq!(permission_request(owner_without_transfer).await.status(), 400);
+
+    assert_eq!(permission_request(test::TestRequest::delete().uri(&uri)).await.status(), 204);
+
+    let missing = format!("/drive/files/file1/permissions/{}", MOCK_MISSING_ID);
+    assert_eq!(permission_request(test::TestRequest::delete().uri(&missing)).await.status(), 404);
+    let locked = format!("/drive/files/{}/permissions", MOCK_LOCKED_ID);
+    assert_eq!(permission_request(test::TestRequest::get().uri(&locked)).await.status(), 403);
+}
@@ -0,0 +1,39 @@
+use api_drive::api::export_formats::{export_file_name, export_formats, find_export_format, is_workspace_type};
+
+const DOCUMENT: &str = "application/vnd.google-apps.document";
+const SPREADSHEET: &str = "application/vnd.google-apps.spreadsheet";
+
+#[test]
+fn test_workspace_types() {
+    assert!(is_workspace_type(DOCUMENT));
+    assert!(is_workspace_type("application/vnd.google-apps.folder"));
+    assert!(!is_workspace_type("application/pdf"));
+}
+
+#[test]
+fn test_default_format_is_pdf() {
+    assert_eq!(find_export_format(DOCUMENT, None).unwrap().mime_type, "application/pdf");
+    assert_eq!(find_export_format(SPREADSHEET, None).unwrap().name, "pdf");
+}
+
+#[test]
+fn test_formats_depend_on_source_type() {
+    assert_eq!(find_export_format(SPREADSHEET, Some("csv")).unwrap().mime_type, "text/csv");
+    assert_eq!(find_export_format(DOCUMENT, Some(" EPUB ")).unwrap().mime_type, "application/epub+zip");
+    assert!(find_export_format(DOCUMENT, Some("csv")).is_none());
+    assert!(find_export_format(SPREADSHEET, Some("docx")).is_none());
+}
+
+#[test]
+fn test_non_exportable_types() {
+    assert!(export_formats("application/vnd.google-apps.folder").is_empty());
+    assert!(export_formats("application/pdf").is_empty());
+    assert!(find_export_format("application/vnd.google-apps.form", None).is_none());
+}
+
+#[test]
+fn test_export_file_name() {
+    let pdf = find_export_format(DOCUMENT, None).unwrap();
+    assert_eq!(export_file_name("Notas", &pdf), "Notas.pdf");
+    assert_eq!(export_file_name("Informe.PDF", &pdf), "Informe.PDF");
+}
@@ -0,0 +1,61 @@
+use api_drive::api::file_metadata::{FieldSelection, FileMetadata, METADATA_FIELDS};
+
+#[test]
+fn test_parse_accepts_api_and_drive_names() {
+    let fields = FieldSelection::parse("size, modifiedTime,name,modified_time").unwrap();
+
+    assert_eq!(fields.names().collect::<Vec<_>>(), vec!["id", "name", "size", "modified_time"]);
+    assert_eq!(fields.drive_fields(), "id,name,size,modifiedTime");
+    assert_eq!(fields.drive_list_fields(), "nextPageToken,files(id,name,size,modifiedTime)");
+}
+
+#[test]
+fn test_parse_always_includes_id() {
+    assert_eq!(FieldSelection::parse("").unwrap().drive_fields(), "id");
+    assert_eq!(FieldSelection::parse("id").unwrap().drive_fields(), "id");
+}
+
+#[test]
+fn test_parse_rejects_fields_outside_the_allowlist() {
+    let message = FieldSelection::parse("name,permissions").unwrap_err();
+    assert!(message.contains("'permissions'"));
+    assert!(message.contains("web_view_link"));
+
+    // Drive's sub-field syntax is not passed through either.
+    assert!(FieldSelection::parse("capabilities/canEdit").is_err());
+    assert!(FieldSelection::parse("files(id)").is_err());
+}
+
+#[test]
+fn test_all_selects_every_field() {
+    assert_eq!(FieldSelection::all().names().count(), METADATA_FIELDS.len());
+}
+
+#[test]
+fn test_metadata_reads_drive_names_and_writes_snake_case() {
+    let drive = serde_json::json!({
+        "id": "file1",
+        "mimeType": "application/pdf",
+        "size": "2048",
+        "owners": [{ "displayName": "Ana", "emailAddress": "ana@example.com", "kind": "drive#user" }],
+        "capabilities": { "canEdit": true },
+        "appProperties": { "invoice": "2026-001" }
+    });
+    let metadata: FileMetadata = serde_json::from_value(drive).unwrap();
+
+    assert_eq!(metadata.size, Some(2048));
+    assert_eq!(metadata.owners.as_ref().unwrap()[0].email_address.as_deref(), Some("ana@example.com"));
+    assert_eq!(metadata.capabilities.as_ref().unwrap().can_edit, Some(true));
+
+    let written = serde_json::to_value(&metadata).unwrap();
+    assert_eq!(written["mime_type"], "application/pdf");
+    assert_eq!(written["size"], 2048);
+    assert_eq!(written["owners"][0]["display_name"], "Ana");
+    assert_eq!(written["app_properties"]["invoice"], "2026-001");
+    // Fields Drive did not send are left out rather than written as null.
+    assert!(written.get("name").is_none());
+    assert!(written.get("web_view_link").is_none());
+
+    let read_back: FileMetadata = serde_json::from_value(written).unwrap();
+    assert_eq!(read_back, metadata);
+}
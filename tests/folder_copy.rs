@@ -0,0 +1,49 @@
+use api_drive::services::folder_copy::copy_folder;
+
+#[path = "mocks/google_drive_service_mock.rs"]
+mod google_drive_service_mock;
+
+#[path = "mocks/config_mock.rs"]
+mod config_mock;
+
+use google_drive_service_mock::MockGoogleDriveService;
+use config_mock::mock_config;
+
+#[actix_web::test]
+async fn test_copy_folder_rebuilds_the_tree() {
+    let copy = copy_folder(&MockGoogleDriveService, "mock_token", "tree_root", "Backup", "target", &mock_config())
+        .await
+        .unwrap();
+
+    assert_eq!(copy.id, "new_Backup");
+    assert_eq!(copy.name.as_deref(), Some("Backup"));
+    // The root plus a, b, shared and deep; root.pdf, a.pdf, shared.pdf and deep.pdf.
+    assert_eq!(copy.folders_copied, 5);
+    assert_eq!(copy.files_copied, 4);
+}
+
+#[actix_web::test]
+async fn test_copy_folder_reports_what_it_skipped() {
+    let copy = copy_folder(&MockGoogleDriveService, "mock_token", "tree_root", "Backup", "target", &mock_config())
+        .await
+        .unwrap();
+
+    let mut skipped: Vec<(&str, &str)> = copy
+        .failures
+        .iter()
+        .map(|failure| (failure.source_id.as_deref().unwrap(), failure.error.as_str()))
+        .collect();
+    skipped.sort();
+
+    assert_eq!(skipped.len(), 3);
+    assert_eq!(skipped[0].0, "broken");
+    assert!(skipped[0].1.contains("500"));
+    assert_eq!(skipped[1], ("shared", "Folder already copied through another parent"));
+    assert_eq!(skipped[2], ("tree_root", "Folder already copied through another parent"));
+}
+
+#[actix_web::test]
+async fn test_copy_folder_fails_when_source_cannot_be_listed() {
+    let result = copy_folder(&MockGoogleDriveService, "mock_token", "broken", "Backup", "target", &mock_config()).await;
+    assert!(result.is_err());
+}
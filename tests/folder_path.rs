@@ -0,0 +1,23 @@
+use api_drive::services::folder_path::{parse_folder_path, MAX_PATH_SEGMENTS};
+
+#[test]
+fn test_parse_folder_path_skips_empty_segments() {
+    assert_eq!(parse_folder_path("Invoices/2026/Q3").unwrap(), vec!["Invoices", "2026", "Q3"]);
+    assert_eq!(parse_folder_path("/Invoices//2026/ ").unwrap(), vec!["Invoices", "2026"]);
+    assert_eq!(parse_folder_path(" Q3 Reports ").unwrap(), vec!["Q3 Reports"]);
+}
+
+#[test]
+fn test_parse_folder_path_rejects_empty_and_relative_paths() {
+    assert!(parse_folder_path("").is_err());
+    assert!(parse_folder_path("/ /").is_err());
+    assert!(parse_folder_path("Invoices/../Reports").unwrap_err().contains(".."));
+    assert!(parse_folder_path("./Invoices").is_err());
+}
+
+#[test]
+fn test_parse_folder_path_limits_depth() {
+    let deepest = vec!["a"; MAX_PATH_SEGMENTS].join("/");
+    assert_eq!(parse_folder_path(&deepest).unwrap().len(), MAX_PATH_SEGMENTS);
+    assert!(parse_folder_path(&format!("{}/a", deepest)).is_err());
+}
@@ -0,0 +1,107 @@
+use actix_web::{test, web, App, http::header};
+use api_drive::handlers::google_drive_handler::get_folder_tree;
+use api_drive::services::folder_tree::{walk_tree, TreeNode};
+
+#[path = "mocks/google_drive_service_mock.rs"]
+mod google_drive_service_mock;
+
+#[path = "mocks/config_mock.rs"]
+mod config_mock;
+
+use google_drive_service_mock::MockGoogleDriveService;
+use config_mock::mock_config;
+
+fn child<'a>(node: &'a TreeNode, id: &str) -> &'a TreeNode {
+    node.children
+        .iter()
+        .find(|child| child.id.as_deref() == Some(id))
+        .unwrap_or_else(|| panic!("Missing child '{}'", id))
+}
+
+#[actix_web::test]
+async fn test_walk_tree_nests_folders_and_files() {
+    let tree = walk_tree(&MockGoogleDriveService, "mock_token", "tree_root", 5, 2, &mock_config()).await;
+
+    assert_eq!(tree.id.as_deref(), Some("tree_root"));
+    assert!(tree.error.is_none());
+    assert_eq!(tree.children.len(), 4);
+
+    let a = child(&tree, "a");
+    assert!(a.is_folder);
+    let shared = child(a, "shared");
+    assert!(!shared.cycle);
+    let deep = child(shared, "deep");
+    assert_eq!(deep.children[0].name.as_deref(), Some("deep.pdf"));
+    assert!(!child(&tree, "root.pdf").is_folder);
+}
+
+#[actix_web::test]
+async fn test_walk_tree_marks_multi_parent_and_back_references_as_cycles() {
+    let tree = walk_tree(&MockGoogleDriveService, "mock_token", "tree_root", 5, 2, &mock_config()).await;
+
+    let b = child(&tree, "b");
+    let shared_again = child(b, "shared");
+    assert!(shared_again.cycle);
+    assert!(shared_again.children.is_empty());
+
+    let root_again = child(b, "tree_root");
+    assert!(root_again.cycle);
+    assert!(root_again.children.is_empty());
+}
+
+#[actix_web::test]
+async fn test_walk_tree_reports_failures_per_node() {
+    let tree = walk_tree(&MockGoogleDriveService, "mock_token", "tree_root", 5, 2, &mock_config()).await;
+
+    let broken = child(&tree, "broken");
+    assert!(broken.error.as_deref().unwrap().contains("500"));
+    assert!(tree.error.is_none());
+    assert!(child(&tree, "a").error.is_none());
+}
+
+#[actix_web::test]
+async fn test_walk_tree_stops_at_max_depth() {
+    let tree = walk_tree(&MockGoogleDriveService, "mock_token", "tree_root", 2, 2, &mock_config()).await;
+
+    let shared = child(child(&tree, "a"), "shared");
+    assert!(shared.truncated);
+    assert!(shared.children.is_empty());
+}
+
+#[actix_web::test]
+async fn test_get_folder_tree_endpoint() {
+    let mock_service = web::Data::new(MockGoogleDriveService);
+    let config_data = web::Data::new(mock_config());
+
+    let app = test::init_service(
+        App::new()
+            .app_data(mock_service.clone())
+            .app_data(config_data.clone())
+            .route("/drive/folders/{folder_id}/tree", web::get().to(get_folder_tree::<MockGoogleDriveService>)),
+    ).await;
+
+    let req = test::TestRequest::get()
+        .uri("/drive/folders/tree_root/tree?max_depth=3")
+        .insert_header((header::AUTHORIZATION, "Bearer mock_token"))
+        .to_request();
+    let tree: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+    assert_eq!(tree["id"], "tree_root");
+    assert_eq!(tree["children"][0]["children"][0]["id"], "shared");
+    assert_eq!(tree["children"][1]["children"][0]["cycle"], true);
+    assert!(tree["children"][2]["error"].is_string());
+
+    let req = test::TestRequest::get()
+        .uri("/drive/folders/tree_root/tree?max_depth=0")
+        .insert_header((header::AUTHORIZATION, "Bearer mock_token"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400, "Expected 400 status for max_depth=0");
+
+    let req = test::TestRequest::get()
+        .uri("/drive/folders/broken/tree")
+        .insert_header((header::AUTHORIZATION, "Bearer mock_token"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 500, "Expected 500 status when the root cannot be listed");
+}
@@ -0,0 +1,916 @@
+#[path = "mocks/google_server_mock.rs"]
+mod google_server_mock;
+#[path = "mocks/config_mock.rs"]
+mod config_mock;
+
+use api_drive::{config::Config, server};
+use config_mock::mock_config;
+use google_server_mock::{start_google_mock, MockGoogle, FULL_ACCESS_TOKEN, MISSING_FILE_ID, MOCK_DOC_ID, MOCK_MD5, MOCK_PDF, MOCK_PNG, MOCK_SHA256, READONLY_ACCESS_TOKEN, REJECTED_FILE_NAME, VALID_CODE};
+use reqwest::StatusCode;
+use std::net::TcpListener;
+
+/// Boots the real server, wired to the real Google services, against the mock Google endpoints.
+fn spawn_app(mock: &MockGoogle) -> String {
+    let mut config: Config = mock_config();
+    mock.configure(&mut config);
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind test listener");
+    let address = listener.local_addr().unwrap();
+    let server = server::run(config, listener).expect("Failed to start server");
+    actix_rt::spawn(server);
+
+    format!("http://{}", address)
+}
+
+#[actix_rt::test]
+async fn test_oauth_flow_then_list_folders_with_session() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let auth = client.get(format!("{}/auth", app)).send().await.unwrap();
+    let login_state = auth.headers()[reqwest::header::SET_COOKIE].to_str().unwrap().split(';').next().unwrap().to_string();
+    assert!(login_state.starts_with("login_state="));
+    let auth_url = auth.text().await.unwrap();
+    assert!(auth_url.starts_with(&format!("{}/o/oauth2/auth?", mock.base_url)));
+    let state = auth_url
+        .split('&')
+        .find_map(|param| param.strip_prefix("state="))
+        .expect("Auth URL should carry a state")
+        .to_string();
+
+    let callback = client
+        .get(format!("{}/auth/callback?code={}&state={}", app, VALID_CODE, state))
+        .header(reqwest::header::COOKIE, login_state)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(callback.status(), StatusCode::OK);
+    let session: serde_json::Value = callback.json().await.unwrap();
+    let session_id = session["session_id"].as_str().unwrap();
+
+    let folders = client
+        .get(format!("{}/drive/list-folders", app))
+        .bearer_auth(session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(folders.status(), StatusCode::OK);
+    let body: serde_json::Value = folders.json().await.unwrap();
+    assert_eq!(body["folders"][0]["name"], "Invoices");
+    assert_eq!(body["next_page_token"], "folders_page_2");
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_list_folders_follows_every_page_when_all_is_set() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let second_page: serde_json::Value = client
+        .get(format!("{}/drive/list-folders?page_token=folders_page_2", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(second_page["folders"][0]["name"], "Reports");
+    assert!(second_page["next_page_token"].is_null());
+
+    let all: serde_json::Value = client
+        .get(format!("{}/drive/list-folders?all=true", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let names: Vec<&str> = all["folders"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|folder| folder["name"].as_str())
+        .collect();
+    assert_eq!(names, vec!["Invoices", "Reports"]);
+    assert!(all["next_page_token"].is_null());
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_raw_google_token_is_validated_with_tokeninfo() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let download = client
+        .get(format!("{}/drive/files/file1", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(download.status(), StatusCode::OK);
+    assert_eq!(download.headers()["content-type"], "application/pdf");
+    assert_eq!(download.headers()["content-length"], MOCK_PDF.len().to_string().as_str());
+    assert_eq!(download.headers()["etag"], format!("\"{}\"", MOCK_MD5).as_str());
+    assert_eq!(download.headers()["last-modified"], "Wed, 23 Oct 2024 10:00:00 GMT");
+    assert_eq!(download.headers()["content-disposition"], "attachment; filename=\"a.pdf\"");
+    assert_eq!(download.bytes().await.unwrap().as_ref(), MOCK_PDF);
+
+    let partial = client
+        .get(format!("{}/drive/files/file1", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .header("Range", "bytes=1-3")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(partial.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(partial.headers()["content-range"], format!("bytes 1-3/{}", MOCK_PDF.len()).as_str());
+    assert_eq!(partial.bytes().await.unwrap().as_ref(), &MOCK_PDF[1..4]);
+
+    let cached = client
+        .get(format!("{}/drive/files/file1", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .header("If-None-Match", format!("\"{}\"", MOCK_MD5))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+
+    let verified = client
+        .get(format!("{}/drive/files/file1/verify", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(verified.status(), StatusCode::OK);
+    let verified: serde_json::Value = verified.json().await.unwrap();
+    assert_eq!(verified["verified"], true);
+    assert_eq!(verified["md5_checksum"], MOCK_MD5);
+
+    let exported = client
+        .get(format!("{}/drive/files/{}?format=csv", app, MOCK_DOC_ID))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(exported.status(), StatusCode::BAD_REQUEST);
+
+    let exported = client
+        .get(format!("{}/drive/files/{}?format=txt", app, MOCK_DOC_ID))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(exported.status(), StatusCode::OK);
+    assert_eq!(exported.headers()["content-type"], "text/plain");
+    assert_eq!(exported.headers()["content-disposition"], "attachment; filename=\"Notes.txt\"");
+    assert_eq!(exported.text().await.unwrap(), "exported as text/plain");
+
+    let rejected = client
+        .get(format!("{}/drive/list-folders", app))
+        .bearer_auth("unknown_token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(rejected.status(), StatusCode::UNAUTHORIZED);
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_readonly_token_cannot_upload() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(MOCK_PDF.to_vec()).file_name("a.pdf"));
+    let upload = client
+        .post(format!("{}/drive/files", app))
+        .bearer_auth(READONLY_ACCESS_TOKEN)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(upload.status(), StatusCode::FORBIDDEN);
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_through_resumable_session() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(MOCK_PDF.to_vec()).file_name("a.pdf"));
+    let upload = client
+        .post(format!("{}/drive/files?folder_id=folder1", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(upload.status(), StatusCode::OK);
+    let body: serde_json::Value = upload.json().await.unwrap();
+    assert_eq!(body[0]["file_id"], "uploaded_file_id");
+    assert_eq!(body[0]["file_name"], "a.pdf");
+    assert_eq!(body[0]["mime_type"], "application/pdf");
+    assert_eq!(body[0]["md5_checksum"], MOCK_MD5);
+    assert_eq!(body[0]["sha256_checksum"], MOCK_SHA256);
+
+    // The part declared no content type, so it was sniffed from the leading bytes.
+    let session = mock.upload("1");
+    assert_eq!(session.metadata["name"], "a.pdf");
+    assert_eq!(session.metadata["mimeType"], "application/pdf");
+    assert_eq!(session.metadata["parents"], serde_json::json!(["folder1"]));
+    assert_eq!(session.content_type.as_deref(), Some("application/pdf"));
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_form_fields_reach_drive_metadata() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let file = reqwest::multipart::Part::bytes(MOCK_PDF.to_vec())
+        .file_name("notes.bin")
+        .mime_str("application/pdf")
+        .unwrap();
+    let form = reqwest::multipart::Form::new()
+        .text("name", "Meeting notes.pdf")
+        .text("description", "Weekly sync")
+        .text("folder_id", "folder2")
+        .text("app_properties", r#"{"project":"apollo"}"#)
+        .part("file", file);
+    let upload = client
+        .post(format!("{}/drive/files?folder_id=folder1", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(upload.status(), StatusCode::OK);
+
+    let session = mock.upload("1");
+    assert_eq!(session.metadata["name"], "Meeting notes.pdf");
+    assert_eq!(session.metadata["mimeType"], "application/pdf");
+    assert_eq!(session.metadata["description"], "Weekly sync");
+    assert_eq!(session.metadata["appProperties"]["project"], "apollo");
+    assert_eq!(session.metadata["parents"], serde_json::json!(["folder2"]));
+    assert_eq!(session.content_type.as_deref(), Some("application/pdf"));
+    assert_eq!(session.content, MOCK_PDF);
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_several_files_in_separate_sessions() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let form = reqwest::multipart::Form::new()
+        .text("description", "Shared description")
+        .text("name", "First.pdf")
+        .part("file", reqwest::multipart::Part::bytes(MOCK_PDF.to_vec()).file_name("a.pdf"))
+        .part("file", reqwest::multipart::Part::bytes(MOCK_PNG.to_vec()).file_name("b.png"));
+    let upload = client
+        .post(format!("{}/drive/files", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(upload.status(), StatusCode::OK);
+    let body: serde_json::Value = upload.json().await.unwrap();
+    assert_eq!(body.as_array().unwrap().len(), 2);
+    assert_eq!(body[0]["file_name"], "First.pdf");
+    assert_eq!(body[1]["file_name"], "b.png");
+    assert_eq!(body[1]["mime_type"], "image/png");
+
+    // Each file got its own session; `name` applied to the first file only, `description` to both.
+    let mut sessions: Vec<_> = ["1", "2"].iter().map(|id| mock.upload(id)).collect();
+    sessions.sort_by_key(|session| session.metadata["name"].as_str().unwrap_or_default().to_string());
+    assert_eq!(sessions[0].metadata["name"], "First.pdf");
+    assert_eq!(sessions[0].content, MOCK_PDF);
+    assert_eq!(sessions[1].metadata["name"], "b.png");
+    assert_eq!(sessions[1].content, MOCK_PNG);
+    assert!(sessions.iter().all(|session| session.complete));
+    assert!(sessions.iter().all(|session| session.metadata["description"] == "Shared description"));
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_reports_each_file_result() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(MOCK_PDF.to_vec()).file_name(REJECTED_FILE_NAME))
+        .part("file", reqwest::multipart::Part::bytes(MOCK_PDF.to_vec()).file_name("a.pdf"));
+    let upload = client
+        .post(format!("{}/drive/files", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(upload.status(), StatusCode::MULTI_STATUS);
+    let body: serde_json::Value = upload.json().await.unwrap();
+    assert_eq!(body[0]["file_name"], REJECTED_FILE_NAME);
+    assert!(body[0]["file_id"].is_null());
+    assert!(body[0]["error"].as_str().unwrap().contains("Failed to initialize upload"));
+    assert_eq!(body[1]["file_id"], "uploaded_file_id");
+    assert!(body[1]["error"].is_null());
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_rejects_disallowed_type_before_reaching_drive() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(b"GIF89a image".to_vec()).file_name("a.gif"));
+    let upload = client
+        .post(format!("{}/drive/files", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(upload.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let body: serde_json::Value = upload.json().await.unwrap();
+    assert!(body[0]["error"].as_str().unwrap().contains("image/gif"));
+    assert!(mock.state.lock().unwrap().uploads.is_empty());
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_rejects_content_that_does_not_match_its_type() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(b"not a pdf".to_vec()).file_name("a.pdf").mime_str("application/pdf").unwrap());
+    let upload = client
+        .post(format!("{}/drive/files", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(upload.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert!(mock.state.lock().unwrap().uploads.is_empty());
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_cancels_truncated_pdf() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let truncated = &MOCK_PDF[..MOCK_PDF.len() - 7];
+    let form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(truncated.to_vec()).file_name("a.pdf"))
+        .part("file", reqwest::multipart::Part::bytes(MOCK_PDF.to_vec()).file_name("b.pdf"));
+    let upload = client
+        .post(format!("{}/drive/files", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(upload.status(), StatusCode::MULTI_STATUS);
+    let body: serde_json::Value = upload.json().await.unwrap();
+    assert!(body[0]["error"].as_str().unwrap().contains("%%EOF"));
+    assert_eq!(body[1]["file_id"], "uploaded_file_id");
+
+    // The truncated file's session was cancelled before it could be completed.
+    let names: Vec<_> = mock.state.lock().unwrap().uploads.values().map(|session| session.metadata["name"].clone()).collect();
+    assert_eq!(names, vec!["b.pdf"]);
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_rejects_file_over_size_limit() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let mut oversized = MOCK_PDF.to_vec();
+    oversized.resize(2 * 1024 * 1024, b' ');
+    let form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(oversized).file_name("big.pdf"));
+    let upload = client
+        .post(format!("{}/drive/files", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(upload.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body: serde_json::Value = upload.json().await.unwrap();
+    assert!(body[0]["error"].as_str().unwrap().contains("upload limit"));
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_fails_when_drive_stores_different_content() {
+    let mock = start_google_mock();
+    mock.state.lock().unwrap().corrupt_md5 = true;
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(MOCK_PDF.to_vec()).file_name("a.pdf"));
+    let upload = client
+        .post(format!("{}/drive/files", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(upload.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body: serde_json::Value = upload.json().await.unwrap();
+    assert!(body[0].get("file_id").is_none());
+    assert_eq!(body[0]["md5_checksum"], MOCK_MD5);
+    assert!(body[0]["error"].as_str().unwrap().contains("Checksum mismatch"));
+    // The corrupted file does not stay in Drive.
+    assert_eq!(mock.state.lock().unwrap().deleted, vec!["uploaded_file_id".to_string()]);
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_fails_when_drive_reports_no_checksum() {
+    let mock = start_google_mock();
+    mock.state.lock().unwrap().omit_md5 = true;
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(MOCK_PDF.to_vec()).file_name("a.pdf"));
+    let upload = client
+        .post(format!("{}/drive/files", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(upload.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body: serde_json::Value = upload.json().await.unwrap();
+    assert!(body[0].get("file_id").is_none());
+    assert!(body[0]["error"].as_str().unwrap().contains("Drive reported none"));
+    assert_eq!(mock.state.lock().unwrap().deleted, vec!["uploaded_file_id".to_string()]);
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_rejects_invalid_app_properties() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let form = reqwest::multipart::Form::new()
+        .text("app_properties", "[1, 2]")
+        .part("file", reqwest::multipart::Part::bytes(MOCK_PDF.to_vec()).file_name("a.pdf"));
+    let upload = client
+        .post(format!("{}/drive/files", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(upload.status(), StatusCode::BAD_REQUEST);
+    assert!(mock.state.lock().unwrap().uploads.is_empty());
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_create_folder_path_creates_only_missing_folders() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+    let create = |path: &str| {
+        client
+            .post(format!("{}/drive/folders", app))
+            .bearer_auth(FULL_ACCESS_TOKEN)
+            .json(&serde_json::json!({ "path": path, "app_properties": { "kind": "quarter" } }))
+            .send()
+    };
+
+    let first = create("Invoices/2026").await.unwrap();
+    assert_eq!(first.status(), StatusCode::CREATED);
+
+    let second = create("Invoices/2026/Q3").await.unwrap();
+    assert_eq!(second.status(), StatusCode::CREATED);
+    let body: serde_json::Value = second.json().await.unwrap();
+    assert_eq!(body["id"], "created_folder3");
+    assert_eq!(body["parent_id"], "created_folder2");
+    assert_eq!(body["path"][0]["created"], false);
+    assert_eq!(body["path"][2]["created"], true);
+
+    let folders = mock.state.lock().unwrap().folders.clone();
+    assert_eq!(folders.len(), 3);
+    assert_eq!(folders[2].name, "Q3");
+    assert_eq!(folders[2].parent_id, "created_folder2");
+    assert_eq!(folders[2].app_properties, serde_json::json!({ "kind": "quarter" }));
+    assert!(folders[0].app_properties.is_null());
+
+    let missing_parent = client
+        .post(format!("{}/drive/folders", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .json(&serde_json::json!({ "name": "Reports", "parent_id": MISSING_FILE_ID }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing_parent.status(), StatusCode::NOT_FOUND);
+
+    let readonly = client
+        .post(format!("{}/drive/folders", app))
+        .bearer_auth(READONLY_ACCESS_TOKEN)
+        .json(&serde_json::json!({ "name": "Reports" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(readonly.status(), StatusCode::FORBIDDEN);
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_trash_restore_and_delete_files() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let trash = |file_id: &str| client.delete(format!("{}/drive/files/{}", app, file_id)).bearer_auth(FULL_ACCESS_TOKEN).send();
+    assert_eq!(trash("file1").await.unwrap().status(), StatusCode::OK);
+    assert_eq!(trash("file2").await.unwrap().status(), StatusCode::OK);
+    assert_eq!(mock.state.lock().unwrap().trashed.len(), 2);
+
+    let restore = client
+        .post(format!("{}/drive/files/file2/restore", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(restore.status(), StatusCode::OK);
+    let body: serde_json::Value = restore.json().await.unwrap();
+    assert_eq!(body["id"], "file2");
+
+    let delete = client
+        .delete(format!("{}/drive/files/file3?permanent=true", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(delete.status(), StatusCode::NO_CONTENT);
+
+    let empty = client.post(format!("{}/drive/trash/empty", app)).bearer_auth(FULL_ACCESS_TOKEN).send().await.unwrap();
+    assert_eq!(empty.status(), StatusCode::NO_CONTENT);
+    let deleted = mock.state.lock().unwrap().deleted.clone();
+    assert_eq!(deleted, vec!["file3", "file1"]);
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_missing_file_and_readonly_token_get_clear_errors() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let trash = client.delete(format!("{}/drive/files/{}", app, MISSING_FILE_ID)).bearer_auth(FULL_ACCESS_TOKEN).send().await.unwrap();
+    assert_eq!(trash.status(), StatusCode::NOT_FOUND);
+    assert!(trash.text().await.unwrap().contains("File not found: missing."));
+
+    let download = client.get(format!("{}/drive/files/{}", app, MISSING_FILE_ID)).bearer_auth(FULL_ACCESS_TOKEN).send().await.unwrap();
+    assert_eq!(download.status(), StatusCode::NOT_FOUND);
+
+    let empty = client.post(format!("{}/drive/trash/empty", app)).bearer_auth(READONLY_ACCESS_TOKEN).send().await.unwrap();
+    assert_eq!(empty.status(), StatusCode::FORBIDDEN);
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_rename_move_and_copy_files() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let update = client
+        .patch(format!("{}/drive/files/file1", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .json(&serde_json::json!({ "name": "b.pdf", "add_parents": ["folder2"], "remove_parents": ["folder1"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(update.status(), StatusCode::OK);
+    let body: serde_json::Value = update.json().await.unwrap();
+    assert_eq!(body["name"], "b.pdf");
+
+    let copy = client
+        .post(format!("{}/drive/files/file1/copy", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .json(&serde_json::json!({ "name": "c.pdf", "parent_id": "folder2" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(copy.status(), StatusCode::CREATED);
+    let body: serde_json::Value = copy.json().await.unwrap();
+    assert_eq!(body["id"], "copy1");
+
+    let (changes, copies) = {
+        let state = mock.state.lock().unwrap();
+        (state.changes.clone(), state.copies.clone())
+    };
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].name.as_deref(), Some("b.pdf"));
+    assert_eq!(changes[0].add_parents.as_deref(), Some("folder2"));
+    assert_eq!(changes[0].remove_parents.as_deref(), Some("folder1"));
+    assert_eq!(copies[0].metadata, serde_json::json!({ "name": "c.pdf", "parents": ["folder2"] }));
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_copy_folder_on_the_server_side() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let copy = client
+        .post(format!("{}/drive/files/folder1/copy", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .json(&serde_json::json!({}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(copy.status(), StatusCode::CREATED);
+    let body: serde_json::Value = copy.json().await.unwrap();
+    assert_eq!(body["id"], "created_folder1");
+    assert_eq!(body["files_copied"], 1);
+
+    let (folders, copies) = {
+        let state = mock.state.lock().unwrap();
+        (state.folders.clone(), state.copies.clone())
+    };
+    assert_eq!(folders.len(), 1);
+    assert_eq!(folders[0].name, "Invoices");
+    assert_eq!(folders[0].parent_id, "root");
+    assert_eq!(copies[0].source_id, "file1");
+    assert_eq!(copies[0].metadata, serde_json::json!({ "name": "a.pdf", "parents": ["created_folder1"] }));
+
+    // Files in the trash are not copied.
+    let trash = client.delete(format!("{}/drive/files/file1", app)).bearer_auth(FULL_ACCESS_TOKEN).send().await.unwrap();
+    assert_eq!(trash.status(), StatusCode::OK);
+    let copy = client
+        .post(format!("{}/drive/files/folder1/copy", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .json(&serde_json::json!({}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(copy.status(), StatusCode::CREATED);
+    let body: serde_json::Value = copy.json().await.unwrap();
+    assert_eq!(body["files_copied"], 0);
+    assert_eq!(mock.state.lock().unwrap().copies.len(), 1);
+
+    // Nor listed, whichever fields are asked for.
+    for query in ["folder_id=folder1", "folder_id=folder1&fields=name"] {
+        let listing = client.get(format!("{}/drive/files?{}", app, query)).bearer_auth(FULL_ACCESS_TOKEN).send().await.unwrap();
+        assert_eq!(listing.status(), StatusCode::OK);
+        let body: serde_json::Value = listing.json().await.unwrap();
+        assert_eq!(body["files"], serde_json::json!([]), "{}", query);
+    }
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_cors_preflight_allows_patch() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    for path in ["/drive/files/file1", "/drive/files/file1/permissions/p1"] {
+        let preflight = client
+            .request(reqwest::Method::OPTIONS, format!("{}{}", app, path))
+            .header("Origin", "https://app.example.com")
+            .header("Access-Control-Request-Method", "PATCH")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(preflight.status(), StatusCode::OK);
+        let allowed = preflight.headers()["access-control-allow-methods"].to_str().unwrap().to_string();
+        assert!(allowed.contains("PATCH"), "{}", allowed);
+    }
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_file_metadata_passes_fields_through_to_drive() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let metadata = client
+        .get(format!("{}/drive/files/file1/metadata?fields=size,owners,capabilities", app))
+        .bearer_auth(READONLY_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(metadata.status(), StatusCode::OK);
+    let body: serde_json::Value = metadata.json().await.unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "id": "file1",
+            "size": MOCK_PDF.len(),
+            "owners": [{ "display_name": "Ana", "email_address": "ana@example.com" }],
+            "capabilities": { "can_edit": true, "can_download": true, "can_delete": false }
+        })
+    );
+
+    let listing = client
+        .get(format!("{}/drive/files?folder_id=folder1&fields=name,starred,web_view_link", app))
+        .bearer_auth(READONLY_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(listing.status(), StatusCode::OK);
+    let body: serde_json::Value = listing.json().await.unwrap();
+    assert_eq!(
+        body["files"][0],
+        serde_json::json!({ "id": "file1", "name": "a.pdf", "web_view_link": "https://drive.google.com/file/d/file1/view", "starred": true })
+    );
+
+    let missing = client
+        .get(format!("{}/drive/files/{}/metadata", app, MISSING_FILE_ID))
+        .bearer_auth(READONLY_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_token_cache_metrics_require_authentication() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let anonymous = client.get(format!("{}/metrics/token-cache", app)).send().await.unwrap();
+    assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+
+    let authenticated = client
+        .get(format!("{}/metrics/token-cache", app))
+        .bearer_auth(READONLY_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(authenticated.status(), StatusCode::OK);
+    let stats: serde_json::Value = authenticated.json().await.unwrap();
+    assert_eq!(stats["misses"], 1);
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_listing_a_missing_folder_keeps_drive_status() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    for fields in ["", "&fields=name"] {
+        let listing = client
+            .get(format!("{}/drive/files?folder_id={}{}", app, MISSING_FILE_ID, fields))
+            .bearer_auth(READONLY_ACCESS_TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(listing.status(), StatusCode::NOT_FOUND, "{}", fields);
+    }
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_share_update_and_unshare_a_file() {
+    let mock = start_google_mock();
+    let app = spawn_app(&mock);
+    let client = reqwest::Client::new();
+
+    let share = client
+        .post(format!("{}/drive/files/file1/permissions", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .json(&serde_json::json!({
+            "type": "user",
+            "role": "owner",
+            "email_address": "ana@example.com",
+            "email_message": "It is yours now",
+            "transfer_ownership": true
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(share.status(), StatusCode::CREATED);
+    let body: serde_json::Value = share.json().await.unwrap();
+    assert_eq!(body["id"], "permission1");
+    assert_eq!(body["email_address"], "ana@example.com");
+
+    let link = client
+        .post(format!("{}/drive/files/file1/permissions", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .json(&serde_json::json!({ "type": "anyone", "role": "reader", "allow_file_discovery": false }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(link.status(), StatusCode::CREATED);
+
+    let created = mock.state.lock().unwrap().permissions.clone();
+    assert_eq!(created[0].query.get("transferOwnership").map(String::as_str), Some("true"));
+    assert_eq!(created[0].query.get("emailMessage").map(String::as_str), Some("It is yours now"));
+    assert_eq!(created[0].permission["emailAddress"], "ana@example.com");
+    assert!(!created[1].query.contains_key("sendNotificationEmail"));
+    assert_eq!(created[1].permission["allowFileDiscovery"], false);
+
+    // The owner comes on the first page and the new permissions on the second.
+    let list = client
+        .get(format!("{}/drive/files/file1/permissions", app))
+        .bearer_auth(READONLY_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(list.status(), StatusCode::OK);
+    let body: serde_json::Value = list.json().await.unwrap();
+    let roles: Vec<&str> = body["permissions"].as_array().unwrap().iter().map(|p| p["role"].as_str().unwrap()).collect();
+    assert_eq!(roles, ["owner", "owner", "reader"]);
+
+    let update = client
+        .patch(format!("{}/drive/files/file1/permissions/permission2", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .json(&serde_json::json!({ "role": "commenter" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(update.status(), StatusCode::OK);
+    let body: serde_json::Value = update.json().await.unwrap();
+    assert_eq!(body["role"], "commenter");
+
+    let delete = client
+        .delete(format!("{}/drive/files/file1/permissions/permission2", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(delete.status(), StatusCode::NO_CONTENT);
+
+    let again = client
+        .delete(format!("{}/drive/files/file1/permissions/permission2", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(again.status(), StatusCode::NOT_FOUND);
+
+    let readonly = client
+        .post(format!("{}/drive/files/file1/permissions", app))
+        .bearer_auth(READONLY_ACCESS_TOKEN)
+        .json(&serde_json::json!({ "type": "anyone", "role": "reader" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(readonly.status(), StatusCode::FORBIDDEN);
+
+    assert_eq!(mock.state.lock().unwrap().permissions.len(), 1);
+    mock.stop().await;
+}
@@ -0,0 +1,33 @@
+use api_drive::services::mime_sniff::sniff_mime_type;
+
+#[test]
+fn test_sniff_recognizes_common_signatures() {
+    assert_eq!(sniff_mime_type(b"%PDF-1.7\n", "report"), Some("application/pdf"));
+    assert_eq!(sniff_mime_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", "image"), Some("image/png"));
+    assert_eq!(sniff_mime_type(&[0xFF, 0xD8, 0xFF, 0xE0], "photo"), Some("image/jpeg"));
+    assert_eq!(sniff_mime_type(b"GIF89a\x01\0", "anim"), Some("image/gif"));
+    assert_eq!(sniff_mime_type(b"RIFF\x24\0\0\0WEBPVP8 ", "pic"), Some("image/webp"));
+}
+
+#[test]
+fn test_sniff_refines_zip_by_extension() {
+    let zip = b"PK\x03\x04\x14\0";
+    assert_eq!(
+        sniff_mime_type(zip, "letter.DOCX"),
+        Some("application/vnd.openxmlformats-officedocument.wordprocessingml.document")
+    );
+    assert_eq!(
+        sniff_mime_type(zip, "budget.xlsx"),
+        Some("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+    );
+    assert_eq!(sniff_mime_type(zip, "archive.zip"), Some("application/zip"));
+    assert_eq!(sniff_mime_type(zip, "no_extension"), Some("application/zip"));
+}
+
+#[test]
+fn test_sniff_ignores_unknown_or_short_input() {
+    assert_eq!(sniff_mime_type(b"hello world", "a.pdf"), None);
+    assert_eq!(sniff_mime_type(b"%PD", "a.pdf"), None);
+    assert_eq!(sniff_mime_type(b"RIFF", "a.webp"), None);
+    assert_eq!(sniff_mime_type(b"", "a.png"), None);
+}
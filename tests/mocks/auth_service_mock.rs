@@ -11,15 +11,41 @@ impl AuthService for MockAuthService {
     fn get_access_token<'a>(
         &'a self,
         _code: &'a str,
+        _code_verifier: &'a str,
         _config: &'a Config
     ) -> Pin<Box<dyn Future<Output = Result<TokenResponse>> + Send + 'a>> {
         Box::pin(async {
             Ok(TokenResponse {
                 access_token: "mock_access_token".to_string(),
                 expires_in: 3600,
+                refresh_token: Some("mock_refresh_token".to_string()),
+                scope: Some("https://www.googleapis.com/auth/drive".to_string()),
             })
         })
     }
+
+    fn refresh_access_token<'a>(
+        &'a self,
+        _refresh_token: &'a str,
+        _config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<TokenResponse>> + Send + 'a>> {
+        Box::pin(async {
+            Ok(TokenResponse {
+                access_token: "mock_refreshed_access_token".to_string(),
+                expires_in: 3600,
+                refresh_token: None,
+                scope: Some("https://www.googleapis.com/auth/drive".to_string()),
+            })
+        })
+    }
+
+    fn revoke_token<'a>(
+        &'a self,
+        _token: &'a str,
+        _config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async { Ok(()) })
+    }
 }
 
 pub struct MockAuthServiceError;
@@ -28,10 +54,31 @@ impl AuthService for MockAuthServiceError {
     fn get_access_token<'a>(
         &'a self,
         _code: &'a str,
+        _code_verifier: &'a str,
         _config: &'a Config
     ) -> Pin<Box<dyn Future<Output = Result<TokenResponse>> + Send + 'a>> {
         Box::pin(async {
             Err(anyhow!("Mock error exchanging code for token"))
         })
     }
+
+    fn refresh_access_token<'a>(
+        &'a self,
+        _refresh_token: &'a str,
+        _config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<TokenResponse>> + Send + 'a>> {
+        Box::pin(async {
+            Err(anyhow!("Mock error refreshing access token"))
+        })
+    }
+
+    fn revoke_token<'a>(
+        &'a self,
+        _token: &'a str,
+        _config: &'a Config
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async {
+            Err(anyhow!("Mock error revoking token"))
+        })
+    }
 }
@@ -1,15 +1,33 @@
-use api_drive::config::Config;
+use actix_web::cookie::Key;
+use api_drive::config::{AuthMode, Config};
 
 pub fn mock_config() -> Config {
     Config {
+        auth_mode: AuthMode::OAuth,
         client_id: "test_client_id".to_string(),
         client_secret: "test_secret".to_string(),
         redirect_uri: "http://localhost:8080/callback".to_string(),
-        auth_uri: "https://accounts.google.com/o/oauth2/auth",
-        token_uri: "https://oauth2.googleapis.com/token",
+        auth_uri: "https://accounts.google.com/o/oauth2/auth".to_string(),
+        token_uri: "https://oauth2.googleapis.com/token".to_string(),
+        tokeninfo_uri: "https://www.googleapis.com/oauth2/v3/tokeninfo".to_string(),
+        revoke_uri: "https://oauth2.googleapis.com/revoke".to_string(),
+        userinfo_uri: "https://openidconnect.googleapis.com/v1/userinfo".to_string(),
         scope: "https://www.googleapis.com/auth/drive".to_string(),
         serv_addrs: "127.0.0.1:8080".to_string(),
         drive_api_base_url: "https://www.googleapis.com/drive/v3/files".to_string(),
         drive_upload_url: "https://www.googleapis.com/upload/drive/v3/files".to_string(),
+        session_key: Key::from(&[7u8; 64]),
+        session_cookie_secure: false,
+        session_store: "memory".to_string(),
+        session_db_path: ":memory:".to_string(),
+        token_cache_capacity: 100,
+        token_cache_negative_ttl_secs: 30,
+        upload_chunk_size: 256 * 1024,
+        upload_allowed_types: vec!["pdf".to_string(), "png".to_string()],
+        max_upload_size: 1024 * 1024,
+        validate_pdf_xref: true,
+        service_account_key_path: None,
+        service_account_subject: None,
+        service_api_key: None,
     }
 }
@@ -1,86 +1,527 @@
 use std::future::Future;
 use std::pin::Pin;
+use actix_web::web::Bytes;
 use anyhow::Result;
-use api_drive::{config::Config, services::google_drive_service::{DriveService, FileInfo, FolderInfo}};
+use futures::stream::{self, StreamExt};
+use api_drive::{api::{drive_error::DriveApiError, drive_query::DriveQuery, file_metadata::{Capabilities, FieldSelection, FileMetadata, Owner}, google_drive::{ChunkStatus, CopyMetadata, DownloadMetadata, DownloadStream, FileUpdate, FolderMetadata, UploadMetadata}, permissions::{NewPermission, Permission, PermissionOptions, PermissionRole, PermissionType, PermissionUpdate}}, config::Config, services::google_drive_service::{DriveService, FileInfo, FileMetadataPage, FilePage, FolderInfo, FolderPage, PageRequest}};
 
 pub struct MockGoogleDriveService;
 
+pub const MOCK_CONTENT: &[u8] = b"%PDF";
+pub const MOCK_MD5: &str = "bfa4b10a76324b166cfdad5e02a63730";
+pub const MOCK_SHA256: &str = "315d429b7714cedb6ad04ac31240145257692630457f3c88253c5beceac76027";
+pub const MOCK_MODIFIED_TIME: &str = "2024-10-23T10:00:00Z";
+/// A Google Docs document, which has no binary content and must be exported.
+pub const MOCK_DOC_ID: &str = "doc_id";
+/// A file whose content no longer matches the checksums Drive reports.
+pub const MOCK_CORRUPT_ID: &str = "corrupt_id";
+/// A file Drive does not know about.
+pub const MOCK_MISSING_ID: &str = "missing_id";
+/// A file the caller can see but not change.
+pub const MOCK_LOCKED_ID: &str = "locked_id";
+/// A file whose metadata can be read but whose owner disabled downloads.
+pub const MOCK_RESTRICTED_ID: &str = "restricted_id";
+/// A Google Docs document too large for Drive to export.
+pub const MOCK_LARGE_DOC_ID: &str = "large_doc_id";
+
+/// Folders of the hierarchy served by `list_files_in_folder`.
+pub const MOCK_TREE_FOLDERS: &[&str] = &["tree_root", "a", "b", "shared", "deep", "broken"];
+
+/// Fails like Drive does for the missing and locked mock files.
+fn check_access(file_id: &str) -> Result<()> {
+    match file_id {
+        MOCK_MISSING_ID => Err(DriveApiError::new(404, format!("File not found: {}.", file_id)).into()),
+        MOCK_LOCKED_ID => Err(DriveApiError::new(403, "The user does not have sufficient permissions for this file.").into()),
+        _ => Ok(()),
+    }
+}
+
+/// Folders that already exist, as (parent ID, name, ID), for name lookups.
+pub const MOCK_EXISTING_FOLDERS: &[(&str, &str, &str)] = &[
+    ("root", "Invoices", "invoices_id"),
+    ("invoices_id", "2026", "invoices_2026_id"),
+];
+
+/// The owner permission every mock file starts with.
+pub const MOCK_OWNER_PERMISSION_ID: &str = "owner_permission";
+
+fn mock_owner_permission() -> Permission {
+    Permission {
+        id: Some(MOCK_OWNER_PERMISSION_ID.to_string()),
+        permission_type: PermissionType::User,
+        role: PermissionRole::Owner,
+        email_address: Some("owner@example.com".to_string()),
+        domain: None,
+        display_name: Some("Owner".to_string()),
+        allow_file_discovery: None,
+        expiration_time: None,
+    }
+}
+
+fn mock_folder(id: &str) -> FileInfo {
+    FileInfo {
+        id: Some(id.to_string()),
+        name: Some(format!("Folder {}", id)),
+        mime_type: Some("application/vnd.google-apps.folder".to_string()),
+        created_time: None,
+    }
+}
+
+fn mock_file(name: &str) -> FileInfo {
+    FileInfo {
+        id: Some(name.to_string()),
+        name: Some(name.to_string()),
+        mime_type: Some("application/pdf".to_string()),
+        created_time: None,
+    }
+}
+
+/// Every field of a file, trimmed to `fields` as Drive would.
+fn mock_metadata(id: &str, fields: &FieldSelection) -> FileMetadata {
+    let full = FileMetadata {
+        id: Some(id.to_string()),
+        name: Some(format!("{}.pdf", id)),
+        mime_type: Some("application/pdf".to_string()),
+        size: Some(MOCK_CONTENT.len() as u64),
+        created_time: Some(MOCK_MODIFIED_TIME.to_string()),
+        modified_time: Some(MOCK_MODIFIED_TIME.to_string()),
+        md5_checksum: Some(MOCK_MD5.to_string()),
+        owners: Some(vec![Owner { display_name: Some("Ana".to_string()), email_address: Some("ana@example.com".to_string()) }]),
+        parents: Some(vec!["parent_id".to_string()]),
+        web_view_link: Some(format!("https://drive.google.com/file/d/{}/view", id)),
+        icon_link: Some("https://drive-thirdparty.googleusercontent.com/16/type/application/pdf".to_string()),
+        starred: Some(false),
+        trashed: Some(false),
+        capabilities: Some(Capabilities { can_edit: Some(true), can_download: Some(true), ..Capabilities::default() }),
+        app_properties: Some([("invoice".to_string(), "2026-001".to_string())].into()),
+    };
+
+    let mut value = serde_json::to_value(full).unwrap();
+    let selected: Vec<&str> = fields.names().collect();
+    value.as_object_mut().unwrap().retain(|key, _| selected.contains(&key.as_str()));
+    serde_json::from_value(value).unwrap()
+}
+
 impl DriveService for MockGoogleDriveService {
     fn list_folders<'a>(
         &'a self,
         _token: &'a str,
+        page: &'a PageRequest,
         _config: &'a Config,
-    ) -> Pin<Box<dyn Future<Output = Result<Vec<FolderInfo>>> + Send + 'a>> {
+    ) -> Pin<Box<dyn Future<Output = Result<FolderPage>> + Send + 'a>> {
         Box::pin(async move {
-            Ok(vec![
-                FolderInfo {
-                    id: Some("1".to_string()),
-                    name: Some("Folder 1".to_string()),
-                },
-                FolderInfo {
-                    id: Some("2".to_string()),
-                    name: Some("Folder 2".to_string()),
-                },
-            ])
+            let folder_1 = FolderInfo {
+                id: Some("1".to_string()),
+                name: Some("Folder 1".to_string()),
+            };
+            let folder_2 = FolderInfo {
+                id: Some("2".to_string()),
+                name: Some("Folder 2".to_string()),
+            };
+
+            // Two folders split across two pages of one item each.
+            Ok(match (page.all, page.page_token.as_deref()) {
+                (true, _) => FolderPage { folders: vec![folder_1, folder_2], next_page_token: None },
+                (false, Some("page_2")) => FolderPage { folders: vec![folder_2], next_page_token: None },
+                (false, _) => FolderPage { folders: vec![folder_1], next_page_token: Some("page_2".to_string()) },
+            })
         })
     }
 
     fn list_files_in_folder<'a>(
         &'a self,
         _token: &'a str,
-        _folder_id: &'a str,
+        folder_id: &'a str,
+        _page: &'a PageRequest,
         _config: &'a Config,
-    ) -> Pin<Box<dyn Future<Output = Result<Vec<FileInfo>>> + Send + 'a>> {
+    ) -> Pin<Box<dyn Future<Output = Result<FilePage>> + Send + 'a>> {
         Box::pin(async move {
-            Ok(vec![
-                FileInfo {
-                    id: Some("file1".to_string()),
-                    name: Some("File 1".to_string()),
-                    mime_type: Some("application/pdf".to_string()),
-                    created_time: Some("2024-10-23T10:00:00Z".to_string()),
-                },
-                FileInfo {
-                    id: Some("file2".to_string()),
-                    name: Some("File 2".to_string()),
+            // A small hierarchy for tree walks: "shared" has two parents,
+            // "loop" points back at the root and "broken" fails to list.
+            let files = match folder_id {
+                "tree_root" => vec![mock_folder("a"), mock_folder("b"), mock_folder("broken"), mock_file("root.pdf")],
+                "a" => vec![mock_folder("shared"), mock_file("a.pdf")],
+                "b" => vec![mock_folder("shared"), mock_folder("tree_root")],
+                "shared" => vec![mock_folder("deep"), mock_file("shared.pdf")],
+                "deep" => vec![mock_file("deep.pdf")],
+                "broken" => return Err(anyhow::anyhow!("Failed to list files: 500 Internal Server Error")),
+                _ => vec![
+                    FileInfo {
+                        id: Some("file1".to_string()),
+                        name: Some("File 1".to_string()),
+                        mime_type: Some("application/pdf".to_string()),
+                        created_time: Some("2024-10-23T10:00:00Z".to_string()),
+                    },
+                    FileInfo {
+                        id: Some("file2".to_string()),
+                        name: Some("File 2".to_string()),
+                        mime_type: Some("application/pdf".to_string()),
+                        created_time: Some("2024-10-24T11:00:00Z".to_string()),
+                    },
+                ],
+            };
+
+            Ok(FilePage {
+                files,
+                next_page_token: None,
+            })
+        })
+    }
+
+    fn search_files<'a>(
+        &'a self,
+        _token: &'a str,
+        query: &'a DriveQuery,
+        _page: &'a PageRequest,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<FilePage>> + Send + 'a>> {
+        Box::pin(async move {
+            let q = query.build();
+            if q.contains("name = '") {
+                let files = MOCK_EXISTING_FOLDERS
+                    .iter()
+                    .filter(|(parent, name, _)| {
+                        q.contains(&format!("'{}' in parents", parent)) && q.contains(&format!("name = '{}'", name))
+                    })
+                    .map(|(_, _, id)| mock_folder(id))
+                    .collect();
+                return Ok(FilePage { files, next_page_token: None });
+            }
+
+            // Echoes the compiled query so tests can assert on it.
+            Ok(FilePage {
+                files: vec![FileInfo {
+                    id: Some("search_result".to_string()),
+                    name: Some(query.build()),
                     mime_type: Some("application/pdf".to_string()),
-                    created_time: Some("2024-10-24T11:00:00Z".to_string()),
-                },
-            ])
+                    created_time: None,
+                }],
+                next_page_token: None,
+            })
+        })
+    }
+
+    fn list_file_metadata<'a>(
+        &'a self,
+        _token: &'a str,
+        query: &'a DriveQuery,
+        fields: &'a FieldSelection,
+        _page: &'a PageRequest,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<FileMetadataPage>> + Send + 'a>> {
+        Box::pin(async move {
+            let q = query.build();
+            for folder_id in [MOCK_MISSING_ID, MOCK_LOCKED_ID] {
+                if q.contains(&format!("'{}' in parents", folder_id)) {
+                    check_access(folder_id)?;
+                }
+            }
+            // The first file's ID echoes the compiled query so tests can assert on it.
+            Ok(FileMetadataPage {
+                files: vec![mock_metadata(&q, fields), mock_metadata("file2", fields)],
+                next_page_token: None,
+            })
+        })
+    }
+
+    fn get_file_metadata<'a>(
+        &'a self,
+        _token: &'a str,
+        file_id: &'a str,
+        fields: &'a FieldSelection,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<FileMetadata>> + Send + 'a>> {
+        Box::pin(async move {
+            check_access(file_id)?;
+            Ok(mock_metadata(file_id, fields))
         })
     }
 
-    fn download_pdf<'a>(
+    fn create_folder<'a>(
         &'a self,
         _token: &'a str,
-        _file_id: &'a str,
+        folder: &'a FolderMetadata,
         _config: &'a Config,
-    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>>> + Send + 'a>> {
+    ) -> Pin<Box<dyn Future<Output = Result<FolderInfo>> + Send + 'a>> {
         Box::pin(async move {
-            Ok(vec![0x25, 0x50, 0x44, 0x46])
+            Ok(FolderInfo {
+                id: Some(format!("new_{}", folder.name)),
+                name: Some(folder.name.clone()),
+            })
         })
     }
 
-    fn upload_pdf<'a>(
+    fn update_file<'a>(
+        &'a self,
+        _token: &'a str,
+        file_id: &'a str,
+        update: &'a FileUpdate,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<FileInfo>> + Send + 'a>> {
+        Box::pin(async move {
+            check_access(file_id)?;
+            let mut file = mock_file(file_id);
+            if let Some(name) = &update.name {
+                file.name = Some(name.clone());
+            }
+            Ok(file)
+        })
+    }
+
+    fn copy_file<'a>(
+        &'a self,
+        _token: &'a str,
+        file_id: &'a str,
+        copy: &'a CopyMetadata,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<FileInfo>> + Send + 'a>> {
+        Box::pin(async move {
+            check_access(file_id)?;
+            let mut file = mock_file(&format!("copy_of_{}", file_id));
+            file.name = Some(copy.name.clone().unwrap_or_else(|| file_id.to_string()));
+            Ok(file)
+        })
+    }
+
+    fn trash_file<'a>(
+        &'a self,
+        _token: &'a str,
+        file_id: &'a str,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<FileInfo>> + Send + 'a>> {
+        Box::pin(async move {
+            check_access(file_id)?;
+            Ok(mock_file(file_id))
+        })
+    }
+
+    fn restore_file<'a>(
+        &'a self,
+        _token: &'a str,
+        file_id: &'a str,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<FileInfo>> + Send + 'a>> {
+        Box::pin(async move {
+            check_access(file_id)?;
+            Ok(mock_file(file_id))
+        })
+    }
+
+    fn delete_file<'a>(
+        &'a self,
+        _token: &'a str,
+        file_id: &'a str,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move { check_access(file_id) })
+    }
+
+    fn empty_trash<'a>(
+        &'a self,
+        _token: &'a str,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn list_permissions<'a>(
+        &'a self,
+        _token: &'a str,
+        file_id: &'a str,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<Permission>>> + Send + 'a>> {
+        Box::pin(async move {
+            check_access(file_id)?;
+            Ok(vec![mock_owner_permission()])
+        })
+    }
+
+    fn create_permission<'a>(
+        &'a self,
+        _token: &'a str,
+        file_id: &'a str,
+        permission: &'a NewPermission,
+        _options: &'a PermissionOptions,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<Permission>> + Send + 'a>> {
+        Box::pin(async move {
+            check_access(file_id)?;
+            Ok(Permission {
+                id: Some("new_permission".to_string()),
+                permission_type: permission.permission_type,
+                role: permission.role,
+                email_address: permission.email_address.clone(),
+                domain: permission.domain.clone(),
+                display_name: None,
+                allow_file_discovery: permission.allow_file_discovery,
+                expiration_time: permission.expiration_time.clone(),
+            })
+        })
+    }
+
+    fn update_permission<'a>(
+        &'a self,
+        _token: &'a str,
+        file_id: &'a str,
+        permission_id: &'a str,
+        update: &'a PermissionUpdate,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<Permission>> + Send + 'a>> {
+        Box::pin(async move {
+            check_access(file_id)?;
+            check_access(permission_id)?;
+            Ok(Permission {
+                id: Some(permission_id.to_string()),
+                role: update.role,
+                expiration_time: update.expiration_time.clone(),
+                ..mock_owner_permission()
+            })
+        })
+    }
+
+    fn delete_permission<'a>(
+        &'a self,
+        _token: &'a str,
+        file_id: &'a str,
+        permission_id: &'a str,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            check_access(file_id)?;
+            check_access(permission_id)
+        })
+    }
+
+    fn get_download_metadata<'a>(
+        &'a self,
+        _token: &'a str,
+        file_id: &'a str,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<DownloadMetadata>> + Send + 'a>> {
+        Box::pin(async move {
+            check_access(file_id)?;
+            if file_id == MOCK_DOC_ID || file_id == MOCK_LARGE_DOC_ID {
+                return Ok(DownloadMetadata {
+                    name: Some("Notas".to_string()),
+                    mime_type: Some("application/vnd.google-apps.document".to_string()),
+                    md5_checksum: None,
+                    sha256_checksum: None,
+                    modified_time: Some(MOCK_MODIFIED_TIME.to_string()),
+                    size: None,
+                    parents: vec!["root".to_string()],
+                });
+            }
+            if MOCK_TREE_FOLDERS.contains(&file_id) {
+                return Ok(DownloadMetadata {
+                    name: Some(format!("Folder {}", file_id)),
+                    mime_type: Some("application/vnd.google-apps.folder".to_string()),
+                    md5_checksum: None,
+                    sha256_checksum: None,
+                    modified_time: Some(MOCK_MODIFIED_TIME.to_string()),
+                    size: None,
+                    parents: vec!["root".to_string()],
+                });
+            }
+            let (md5_checksum, sha256_checksum) = if file_id == MOCK_CORRUPT_ID {
+                ("0".repeat(32), "0".repeat(64))
+            } else {
+                (MOCK_MD5.to_string(), MOCK_SHA256.to_string())
+            };
+            Ok(DownloadMetadata {
+                name: Some("Informe año.pdf".to_string()),
+                mime_type: Some("application/pdf".to_string()),
+                md5_checksum: Some(md5_checksum),
+                sha256_checksum: Some(sha256_checksum),
+                modified_time: Some(MOCK_MODIFIED_TIME.to_string()),
+                size: Some(MOCK_CONTENT.len().to_string()),
+                parents: vec!["parent_id".to_string()],
+            })
+        })
+    }
+
+    fn download_file<'a>(
+        &'a self,
+        _token: &'a str,
+        file_id: &'a str,
+        range: Option<(u64, u64)>,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<DownloadStream>> + Send + 'a>> {
+        Box::pin(async move {
+            check_access(file_id)?;
+            if file_id == MOCK_RESTRICTED_ID {
+                return Err(DriveApiError::new(403, "The user does not have permission to download this file.").into());
+            }
+            let (start, end) = range.map_or((0, MOCK_CONTENT.len()), |(start, end)| (start as usize, end as usize + 1));
+            let content = &MOCK_CONTENT[start..end];
+            // One chunk per byte, so handlers are exercised on a body that arrives in pieces.
+            let chunks: Vec<Result<Bytes>> = content.chunks(1).map(|chunk| Ok(Bytes::copy_from_slice(chunk))).collect();
+            Ok(DownloadStream {
+                content_type: Some("application/pdf".to_string()),
+                content_length: Some(content.len() as u64),
+                etag: Some("\"upstream-etag\"".to_string()),
+                body: stream::iter(chunks).boxed(),
+            })
+        })
+    }
+
+    fn export_file<'a>(
+        &'a self,
+        _token: &'a str,
+        file_id: &'a str,
+        mime_type: &'a str,
+        _config: &'a Config,
+    ) -> Pin<Box<dyn Future<Output = Result<DownloadStream>> + Send + 'a>> {
+        Box::pin(async move {
+            if file_id == MOCK_LARGE_DOC_ID {
+                return Err(DriveApiError::new(403, "This file is too large to be exported.").into());
+            }
+            if file_id != MOCK_DOC_ID {
+                return Err(anyhow::anyhow!("Failed to export file: 403 Forbidden"));
+            }
+            let content = format!("exported as {}", mime_type);
+            Ok(DownloadStream {
+                content_type: Some(mime_type.to_string()),
+                content_length: None,
+                etag: None,
+                body: stream::once(async move { Ok(Bytes::from(content)) }).boxed(),
+            })
+        })
+    }
+
+    fn upload_chunk<'a>(
         &'a self,
         _token: &'a str,
         _resumable_url: &'a str,
-        _file_content: Vec<u8>,
-        _start_byte: Option<u64>,
-    ) -> Pin<Box<dyn Future<Output = Result<String>> + Send + 'a>> {
+        chunk: Vec<u8>,
+        start: u64,
+        total: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<ChunkStatus>> + Send + 'a>> {
         Box::pin(async move {
-            Ok("mock_file_id".to_string())
+            match total {
+                Some(_) => Ok(ChunkStatus::Complete { file_id: "mock_file_id".to_string(), md5_checksum: None }),
+                None => Ok(ChunkStatus::Incomplete { next_offset: start + chunk.len() as u64 }),
+            }
         })
     }
 
     fn initialize_resumable_upload<'a>(
         &'a self,
         _token: &'a str,
-        _folder_id: &'a str,
-        _file_name: &'a str,
+        _metadata: &'a UploadMetadata,
         _config: &'a Config,
     ) -> Pin<Box<dyn Future<Output = Result<String>> + Send + 'a>> {
         Box::pin(async move {
             Ok("mock_resumable_url".to_string())
         })
     }
+
+    fn cancel_upload<'a>(
+        &'a self,
+        _token: &'a str,
+        _resumable_url: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            Ok(())
+        })
+    }
 }
@@ -0,0 +1,754 @@
+#![allow(dead_code)]
+
+use actix_web::{dev::ServerHandle, http::StatusCode, web, App, HttpRequest, HttpResponse, HttpServer};
+use api_drive::config::Config;
+use md5::{Digest, Md5};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+pub const VALID_CODE: &str = "valid_code";
+pub const FULL_ACCESS_TOKEN: &str = "mock_google_token";
+pub const READONLY_ACCESS_TOKEN: &str = "mock_readonly_token";
+/// A minimal PDF whose `startxref` points at its `xref` table.
+pub const MOCK_PDF: &[u8] = b"%PDF-1.4\n1 0 obj\n<<>>\nendobj\nxref\n0 2\n0000000000 65535 f \n0000000009 00000 n \ntrailer\n<< /Size 2 /Root 1 0 R >>\nstartxref\n29\n%%EOF\n";
+/// The PNG signature followed by the closing `IEND` chunk.
+pub const MOCK_PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\0IEND\xaeB`\x82";
+pub const MOCK_ETAG: &str = "\"mock-etag-1\"";
+pub const MOCK_MD5: &str = "2c3ce6f7a2f8ac9012c3a749c26be4ea";
+pub const MOCK_SHA256: &str = "5fdb8b82245220312a8779552c81ade8175562caa3ecfeea45db3f0f11e06685";
+/// A Google Docs document, which can only be exported.
+pub const MOCK_DOC_ID: &str = "doc1";
+/// A file that does not exist; every request for it gets a 404.
+pub const MISSING_FILE_ID: &str = "missing";
+/// ID of the file every completed upload creates.
+pub const UPLOADED_FILE_ID: &str = "uploaded_file_id";
+/// Drive refuses to create a file with this name.
+pub const REJECTED_FILE_NAME: &str = "rejected.pdf";
+
+/// A resumable upload session as the mock has received it.
+#[derive(Clone, Debug, Default)]
+pub struct UploadSession {
+    pub metadata: serde_json::Value,
+    /// `X-Upload-Content-Type` and `X-Upload-Content-Length`, as sent when the session was created.
+    pub content_type: Option<String>,
+    pub content_length: Option<String>,
+    pub content: Vec<u8>,
+    pub content_ranges: Vec<String>,
+    pub complete: bool,
+}
+
+/// A folder created through the mock, findable by name in its parent.
+#[derive(Clone, Debug, Default)]
+pub struct MockFolder {
+    pub id: String,
+    pub name: String,
+    pub parent_id: String,
+    pub app_properties: serde_json::Value,
+}
+
+/// A rename or move received through `files.update`.
+#[derive(Clone, Debug, Default)]
+pub struct FileChange {
+    pub file_id: String,
+    pub name: Option<String>,
+    pub add_parents: Option<String>,
+    pub remove_parents: Option<String>,
+}
+
+/// A `files.copy` request: the source ID and the metadata sent for the copy.
+#[derive(Clone, Debug, Default)]
+pub struct FileCopy {
+    pub source_id: String,
+    pub metadata: serde_json::Value,
+}
+
+/// A permission created through the mock, with the query parameters of the
+/// last request that created or changed it.
+#[derive(Clone, Debug, Default)]
+pub struct MockPermission {
+    pub file_id: String,
+    /// The permission in Drive's camelCase, including its `id`.
+    pub permission: serde_json::Value,
+    pub query: HashMap<String, String>,
+}
+
+/// State shared between the mock server and the test driving it.
+#[derive(Default)]
+pub struct MockState {
+    pub uploads: HashMap<String, UploadSession>,
+    /// Persist at most this many bytes of each intermediate chunk, like Drive
+    /// does when it only keeps part of a chunk.
+    pub max_ack_per_chunk: Option<usize>,
+    /// Report a wrong `md5Checksum` for completed uploads, as if the content got corrupted.
+    pub corrupt_md5: bool,
+    /// Leave `md5Checksum` out of completed uploads.
+    pub omit_md5: bool,
+    pub folders: Vec<MockFolder>,
+    /// IDs of files currently in the trash.
+    pub trashed: HashSet<String>,
+    /// IDs of files deleted permanently, including by emptying the trash.
+    pub deleted: Vec<String>,
+    pub changes: Vec<FileChange>,
+    pub copies: Vec<FileCopy>,
+    pub permissions: Vec<MockPermission>,
+    next_permission_id: u64,
+    next_session_id: u64,
+}
+
+/// Local stand-in for the Google OAuth and Drive endpoints used by the API.
+pub struct MockGoogle {
+    pub base_url: String,
+    pub handle: ServerHandle,
+    pub state: Arc<Mutex<MockState>>,
+}
+
+impl MockGoogle {
+    /// Points every Google endpoint of `config` at this mock.
+    pub fn configure(&self, config: &mut Config) {
+        config.auth_uri = format!("{}/o/oauth2/auth", self.base_url);
+        config.token_uri = format!("{}/token", self.base_url);
+        config.tokeninfo_uri = format!("{}/tokeninfo", self.base_url);
+        config.revoke_uri = format!("{}/revoke", self.base_url);
+        config.userinfo_uri = format!("{}/userinfo", self.base_url);
+        config.drive_api_base_url = format!("{}/drive/v3/files", self.base_url);
+        config.drive_upload_url = format!("{}/upload/drive/v3/files", self.base_url);
+    }
+
+    pub fn upload(&self, session_id: &str) -> UploadSession {
+        self.state.lock().unwrap().uploads.get(session_id).cloned().unwrap_or_default()
+    }
+
+    pub async fn stop(self) {
+        self.handle.stop(true).await;
+    }
+}
+
+fn bearer(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get("Authorization")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string)
+}
+
+fn scope_for(token: &str) -> Option<&'static str> {
+    match token {
+        FULL_ACCESS_TOKEN => Some("https://www.googleapis.com/auth/drive"),
+        READONLY_ACCESS_TOKEN => Some("https://www.googleapis.com/auth/drive.readonly"),
+        _ => None,
+    }
+}
+
+fn unauthorized() -> HttpResponse {
+    HttpResponse::Unauthorized().json(serde_json::json!({
+        "error": { "code": 401, "message": "Request had invalid authentication credentials." }
+    }))
+}
+
+async fn token(form: web::Form<HashMap<String, String>>) -> HttpResponse {
+    let grant_type = form.get("grant_type").map(String::as_str);
+
+    match grant_type {
+        Some("authorization_code")
+            if form.get("code").map(String::as_str) == Some(VALID_CODE)
+                && form.get("code_verifier").is_some_and(|verifier| !verifier.is_empty()) =>
+        {
+            HttpResponse::Ok().json(serde_json::json!({
+                "access_token": FULL_ACCESS_TOKEN,
+                "refresh_token": "mock_google_refresh_token",
+                "expires_in": 3599,
+                "scope": "https://www.googleapis.com/auth/drive",
+                "token_type": "Bearer"
+            }))
+        }
+        Some("refresh_token") if form.get("refresh_token").map(String::as_str) == Some("mock_google_refresh_token") => {
+            HttpResponse::Ok().json(serde_json::json!({
+                "access_token": FULL_ACCESS_TOKEN,
+                "expires_in": 3599,
+                "scope": "https://www.googleapis.com/auth/drive",
+                "token_type": "Bearer"
+            }))
+        }
+        _ => HttpResponse::BadRequest().json(serde_json::json!({ "error": "invalid_grant" })),
+    }
+}
+
+async fn tokeninfo(query: web::Query<HashMap<String, String>>) -> HttpResponse {
+    let token = query.get("access_token").map(String::as_str).unwrap_or_default();
+
+    match scope_for(token) {
+        Some(scope) => HttpResponse::Ok().json(serde_json::json!({
+            "aud": "test_client_id",
+            "azp": "test_client_id",
+            "scope": scope,
+            "exp": "4102444800",
+            "expires_in": "3599",
+            "email": "user@example.com"
+        })),
+        None => HttpResponse::BadRequest().json(serde_json::json!({ "error": "invalid_token" })),
+    }
+}
+
+async fn revoke() -> HttpResponse {
+    HttpResponse::Ok().finish()
+}
+
+async fn list_files(
+    req: HttpRequest,
+    query: web::Query<HashMap<String, String>>,
+    state: web::Data<Mutex<MockState>>,
+) -> HttpResponse {
+    if bearer(&req).as_deref().and_then(scope_for).is_none() {
+        return unauthorized();
+    }
+
+    let q = query.get("q").map(String::as_str).unwrap_or_default();
+    if q.contains(&format!("'{}' in parents", MISSING_FILE_ID)) {
+        return not_found(MISSING_FILE_ID);
+    }
+    if q.contains("name = '") {
+        // Name lookups only see folders created through the mock.
+        let files: Vec<serde_json::Value> = state
+            .lock()
+            .unwrap()
+            .folders
+            .iter()
+            .filter(|folder| {
+                q.contains(&format!("'{}' in parents", folder.parent_id)) && q.contains(&format!("name = '{}'", folder.name))
+            })
+            .map(|folder| serde_json::json!({ "id": folder.id, "name": folder.name, "mimeType": "application/vnd.google-apps.folder" }))
+            .collect();
+        HttpResponse::Ok().json(serde_json::json!({ "files": files }))
+    } else if q.contains("mimeType = 'application/vnd.google-apps.folder'") {
+        // Folders are served one per page so pagination can be exercised.
+        match query.get("pageToken").map(String::as_str) {
+            None => HttpResponse::Ok().json(serde_json::json!({
+                "files": [{ "id": "folder1", "name": "Invoices" }],
+                "nextPageToken": "folders_page_2"
+            })),
+            Some("folders_page_2") => HttpResponse::Ok().json(serde_json::json!({
+                "files": [{ "id": "folder2", "name": "Reports" }]
+            })),
+            Some(_) => HttpResponse::BadRequest().json(serde_json::json!({
+                "error": { "code": 400, "message": "Invalid page token" }
+            })),
+        }
+    } else {
+        // Once trashed, `a.pdf` is left out of listings that exclude the trash.
+        if q.contains("trashed = false") && state.lock().unwrap().trashed.contains("file1") {
+            return HttpResponse::Ok().json(serde_json::json!({ "files": [] }));
+        }
+        let fields = query.get("fields").map(String::as_str);
+        HttpResponse::Ok().json(serde_json::json!({ "files": [project(mock_pdf_metadata(), fields)] }))
+    }
+}
+
+/// Everything Drive knows about `a.pdf`, before `fields` trims it.
+fn mock_pdf_metadata() -> serde_json::Value {
+    serde_json::json!({
+        "id": "file1",
+        "name": "a.pdf",
+        "mimeType": "application/pdf",
+        "md5Checksum": MOCK_MD5,
+        "sha256Checksum": MOCK_SHA256,
+        "createdTime": "2024-10-23T10:00:00Z",
+        "modifiedTime": "2024-10-23T10:00:00Z",
+        "size": MOCK_PDF.len().to_string(),
+        "owners": [{ "displayName": "Ana", "emailAddress": "ana@example.com", "kind": "drive#user" }],
+        "parents": ["folder1"],
+        "webViewLink": "https://drive.google.com/file/d/file1/view",
+        "iconLink": "https://drive-thirdparty.googleusercontent.com/16/type/application/pdf",
+        "starred": true,
+        "trashed": false,
+        "capabilities": { "canEdit": true, "canDownload": true, "canDelete": false },
+        "appProperties": { "invoice": "2026-001" }
+    })
+}
+
+/// Keeps only the fields Drive was asked for, from either `a,b` or `nextPageToken,files(a,b)`.
+fn project(mut file: serde_json::Value, fields: Option<&str>) -> serde_json::Value {
+    if let Some(fields) = fields {
+        let fields = fields.split_once("files(").map_or(fields, |(_, inner)| inner.trim_end_matches(')'));
+        let selected: Vec<&str> = fields.split(',').collect();
+        file.as_object_mut().unwrap().retain(|key, _| selected.contains(&key.as_str()));
+    }
+    file
+}
+
+fn not_found(file_id: &str) -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": { "code": 404, "message": format!("File not found: {}.", file_id) }
+    }))
+}
+
+async fn get_file(
+    req: HttpRequest,
+    file_id: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+    state: web::Data<Mutex<MockState>>,
+) -> HttpResponse {
+    if bearer(&req).as_deref().and_then(scope_for).is_none() {
+        return unauthorized();
+    }
+    if file_id.as_str() == MISSING_FILE_ID {
+        return not_found(&file_id);
+    }
+
+    if file_id.as_str() == MOCK_DOC_ID {
+        if query.contains_key("alt") {
+            return HttpResponse::Forbidden().json(serde_json::json!({
+                "error": { "code": 403, "message": "Only files with binary content can be downloaded. Use Export with Docs Editors files." }
+            }));
+        }
+        return HttpResponse::Ok().json(serde_json::json!({
+            "id": MOCK_DOC_ID,
+            "name": "Notes",
+            "mimeType": "application/vnd.google-apps.document",
+            "modifiedTime": "2024-10-23T10:00:00Z"
+        }));
+    }
+
+    if file_id.starts_with("folder") {
+        // Folders listed by `list_files`; each holds the single `a.pdf`.
+        return HttpResponse::Ok().json(serde_json::json!({
+            "id": file_id.as_str(),
+            "name": "Invoices",
+            "mimeType": "application/vnd.google-apps.folder",
+            "parents": ["root"]
+        }));
+    }
+
+    if query.get("alt").map(String::as_str) != Some("media") {
+        return HttpResponse::Ok().json(project(mock_pdf_metadata(), query.get("fields").map(String::as_str)));
+    }
+
+    // Every completed upload creates `uploaded_file_id`; it holds the content of the last one.
+    if file_id.as_str() == UPLOADED_FILE_ID {
+        let state = state.lock().unwrap();
+        let content = state
+            .uploads
+            .iter()
+            .filter(|(_, session)| session.complete)
+            .max_by_key(|(session_id, _)| session_id.parse::<u64>().unwrap_or_default())
+            .map(|(_, session)| session.content.clone());
+        return match content {
+            Some(content) => HttpResponse::Ok().content_type("application/octet-stream").body(content),
+            None => not_found(&file_id),
+        };
+    }
+
+    // Drive answers a single `bytes=a-b` range with 206.
+    let range = req
+        .headers()
+        .get("Range")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("bytes="))
+        .and_then(|value| value.split_once('-'))
+        .and_then(|(start, end)| Some((start.parse::<usize>().ok()?, end.parse::<usize>().ok()?)));
+
+    match range {
+        Some((start, end)) => {
+            let end = end.min(MOCK_PDF.len() - 1);
+            HttpResponse::PartialContent()
+                .content_type("application/pdf")
+                .insert_header(("Content-Range", format!("bytes {}-{}/{}", start, end, MOCK_PDF.len())))
+                .body(&MOCK_PDF[start..=end])
+        }
+        None => HttpResponse::Ok().content_type("application/pdf").insert_header(("ETag", MOCK_ETAG)).body(MOCK_PDF),
+    }
+}
+
+/// Creates a folder; this is the only metadata-only create the API makes.
+async fn create_file(
+    req: HttpRequest,
+    metadata: web::Json<serde_json::Value>,
+    state: web::Data<Mutex<MockState>>,
+) -> HttpResponse {
+    if bearer(&req).as_deref().and_then(scope_for).is_none() {
+        return unauthorized();
+    }
+    if metadata["mimeType"] != "application/vnd.google-apps.folder" {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": { "code": 400, "message": "Media upload required" } }));
+    }
+    if metadata["parents"][0] == MISSING_FILE_ID {
+        return not_found(MISSING_FILE_ID);
+    }
+
+    let mut state = state.lock().unwrap();
+    let folder = MockFolder {
+        id: format!("created_folder{}", state.folders.len() + 1),
+        name: metadata["name"].as_str().unwrap_or_default().to_string(),
+        parent_id: metadata["parents"][0].as_str().unwrap_or("root").to_string(),
+        app_properties: metadata["appProperties"].clone(),
+    };
+    state.folders.push(folder.clone());
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "id": folder.id,
+        "name": folder.name,
+        "mimeType": "application/vnd.google-apps.folder",
+        "createdTime": "2024-10-23T10:00:00Z"
+    }))
+}
+
+/// Trashes or restores a file; `trashed` is the only field the API updates.
+async fn update_file(
+    req: HttpRequest,
+    file_id: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+    body: web::Json<serde_json::Value>,
+    state: web::Data<Mutex<MockState>>,
+) -> HttpResponse {
+    if bearer(&req).as_deref().and_then(scope_for).is_none() {
+        return unauthorized();
+    }
+    if file_id.as_str() == MISSING_FILE_ID {
+        return not_found(&file_id);
+    }
+
+    let mut state = state.lock().unwrap();
+    match body["trashed"].as_bool() {
+        Some(true) => {
+            state.trashed.insert(file_id.to_string());
+        }
+        Some(false) => {
+            state.trashed.remove(file_id.as_str());
+        }
+        None => state.changes.push(FileChange {
+            file_id: file_id.to_string(),
+            name: body["name"].as_str().map(str::to_string),
+            add_parents: query.get("addParents").cloned(),
+            remove_parents: query.get("removeParents").cloned(),
+        }),
+    }
+    let name = body["name"].as_str().unwrap_or("a.pdf");
+    HttpResponse::Ok().json(serde_json::json!({ "id": file_id.as_str(), "name": name, "mimeType": "application/pdf" }))
+}
+
+async fn copy_file(
+    req: HttpRequest,
+    file_id: web::Path<String>,
+    metadata: web::Json<serde_json::Value>,
+    state: web::Data<Mutex<MockState>>,
+) -> HttpResponse {
+    if bearer(&req).as_deref().and_then(scope_for).is_none() {
+        return unauthorized();
+    }
+    if file_id.as_str() == MISSING_FILE_ID {
+        return not_found(&file_id);
+    }
+
+    let mut state = state.lock().unwrap();
+    state.copies.push(FileCopy { source_id: file_id.to_string(), metadata: metadata.clone() });
+    HttpResponse::Ok().json(serde_json::json!({
+        "id": format!("copy{}", state.copies.len()),
+        "name": metadata["name"].as_str().unwrap_or("a.pdf"),
+        "mimeType": "application/pdf"
+    }))
+}
+
+async fn delete_file(req: HttpRequest, file_id: web::Path<String>, state: web::Data<Mutex<MockState>>) -> HttpResponse {
+    if bearer(&req).as_deref().and_then(scope_for).is_none() {
+        return unauthorized();
+    }
+    if file_id.as_str() == MISSING_FILE_ID {
+        return not_found(&file_id);
+    }
+
+    let mut state = state.lock().unwrap();
+    state.trashed.remove(file_id.as_str());
+    state.deleted.push(file_id.to_string());
+    HttpResponse::NoContent().finish()
+}
+
+async fn empty_trash(req: HttpRequest, state: web::Data<Mutex<MockState>>) -> HttpResponse {
+    if bearer(&req).as_deref().and_then(scope_for).is_none() {
+        return unauthorized();
+    }
+
+    let mut state = state.lock().unwrap();
+    let mut trashed: Vec<String> = state.trashed.drain().collect();
+    trashed.sort();
+    state.deleted.extend(trashed);
+    HttpResponse::NoContent().finish()
+}
+
+fn owner_permission() -> serde_json::Value {
+    serde_json::json!({
+        "id": "owner",
+        "type": "user",
+        "role": "owner",
+        "emailAddress": "owner@example.com",
+        "displayName": "Owner"
+    })
+}
+
+/// Lists the owner on the first page and the permissions created through the
+/// mock on a second one, so callers have to follow `nextPageToken`.
+async fn list_permissions(
+    req: HttpRequest,
+    file_id: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+    state: web::Data<Mutex<MockState>>,
+) -> HttpResponse {
+    if bearer(&req).as_deref().and_then(scope_for).is_none() {
+        return unauthorized();
+    }
+    if file_id.as_str() == MISSING_FILE_ID {
+        return not_found(&file_id);
+    }
+
+    let state = state.lock().unwrap();
+    let created: Vec<serde_json::Value> = state
+        .permissions
+        .iter()
+        .filter(|permission| permission.file_id == file_id.as_str())
+        .map(|permission| permission.permission.clone())
+        .collect();
+    if query.contains_key("pageToken") {
+        HttpResponse::Ok().json(serde_json::json!({ "permissions": created }))
+    } else if created.is_empty() {
+        HttpResponse::Ok().json(serde_json::json!({ "permissions": [owner_permission()] }))
+    } else {
+        HttpResponse::Ok().json(serde_json::json!({ "permissions": [owner_permission()], "nextPageToken": "page2" }))
+    }
+}
+
+async fn create_permission(
+    req: HttpRequest,
+    file_id: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+    body: web::Json<serde_json::Value>,
+    state: web::Data<Mutex<MockState>>,
+) -> HttpResponse {
+    if bearer(&req).as_deref().and_then(scope_for).is_none() {
+        return unauthorized();
+    }
+    if file_id.as_str() == MISSING_FILE_ID {
+        return not_found(&file_id);
+    }
+
+    let mut state = state.lock().unwrap();
+    state.next_permission_id += 1;
+    let mut permission = body.into_inner();
+    permission["id"] = serde_json::json!(format!("permission{}", state.next_permission_id));
+    state.permissions.push(MockPermission {
+        file_id: file_id.to_string(),
+        permission: permission.clone(),
+        query: query.into_inner(),
+    });
+    HttpResponse::Ok().json(permission)
+}
+
+fn permission_not_found(permission_id: &str) -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": { "code": 404, "message": format!("Permission not found: {}.", permission_id) }
+    }))
+}
+
+async fn update_permission(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    query: web::Query<HashMap<String, String>>,
+    body: web::Json<serde_json::Value>,
+    state: web::Data<Mutex<MockState>>,
+) -> HttpResponse {
+    if bearer(&req).as_deref().and_then(scope_for).is_none() {
+        return unauthorized();
+    }
+    let (file_id, permission_id) = path.into_inner();
+
+    let mut state = state.lock().unwrap();
+    let Some(permission) = state
+        .permissions
+        .iter_mut()
+        .find(|permission| permission.file_id == file_id && permission.permission["id"] == permission_id.as_str())
+    else {
+        return permission_not_found(&permission_id);
+    };
+    permission.permission["role"] = body["role"].clone();
+    if let Some(expiration_time) = body.get("expirationTime") {
+        permission.permission["expirationTime"] = expiration_time.clone();
+    }
+    permission.query = query.into_inner();
+    HttpResponse::Ok().json(permission.permission.clone())
+}
+
+async fn delete_permission(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    state: web::Data<Mutex<MockState>>,
+) -> HttpResponse {
+    if bearer(&req).as_deref().and_then(scope_for).is_none() {
+        return unauthorized();
+    }
+    let (file_id, permission_id) = path.into_inner();
+
+    let mut state = state.lock().unwrap();
+    let before = state.permissions.len();
+    state
+        .permissions
+        .retain(|permission| !(permission.file_id == file_id && permission.permission["id"] == permission_id.as_str()));
+    if state.permissions.len() == before {
+        return permission_not_found(&permission_id);
+    }
+    HttpResponse::NoContent().finish()
+}
+
+/// Exports the mock document; the body names the requested MIME type.
+async fn export_file(req: HttpRequest, file_id: web::Path<String>, query: web::Query<HashMap<String, String>>) -> HttpResponse {
+    if bearer(&req).as_deref().and_then(scope_for).is_none() {
+        return unauthorized();
+    }
+    if file_id.as_str() != MOCK_DOC_ID {
+        return HttpResponse::Forbidden().json(serde_json::json!({ "error": { "code": 403, "message": "Export only supports Docs Editors files." } }));
+    }
+
+    match query.get("mimeType") {
+        Some(mime_type) => HttpResponse::Ok().content_type(mime_type.as_str()).body(format!("exported as {}", mime_type)),
+        None => HttpResponse::BadRequest().json(serde_json::json!({ "error": { "code": 400, "message": "Required parameter: mimeType" } })),
+    }
+}
+
+async fn start_resumable_upload(
+    req: HttpRequest,
+    metadata: web::Json<serde_json::Value>,
+    state: web::Data<Mutex<MockState>>,
+) -> HttpResponse {
+    if bearer(&req).as_deref().and_then(scope_for).is_none() {
+        return unauthorized();
+    }
+
+    if metadata["name"] == REJECTED_FILE_NAME {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": { "code": 400, "message": "Invalid file name" } }));
+    }
+
+    let mut state = state.lock().unwrap();
+    state.next_session_id += 1;
+    let session_id = state.next_session_id.to_string();
+    let header = |name: &str| req.headers().get(name).and_then(|value| value.to_str().ok()).map(str::to_string);
+    let session = UploadSession {
+        metadata: metadata.into_inner(),
+        content_type: header("x-upload-content-type"),
+        content_length: header("x-upload-content-length"),
+        ..UploadSession::default()
+    };
+    state.uploads.insert(session_id.clone(), session);
+
+    let location = format!("http://{}/upload/session/{}", req.connection_info().host(), session_id);
+    HttpResponse::Ok().insert_header(("Location", location)).finish()
+}
+
+/// Parses `bytes a-b/total` or `bytes */total` into (start, total).
+fn parse_content_range(value: &str) -> Option<(Option<u64>, Option<u64>)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let total = if total == "*" { None } else { Some(total.parse().ok()?) };
+    let start = if range == "*" { None } else { Some(range.split_once('-')?.0.parse().ok()?) };
+    Some((start, total))
+}
+
+async fn upload_chunk(
+    req: HttpRequest,
+    session_id: web::Path<String>,
+    body: web::Bytes,
+    state: web::Data<Mutex<MockState>>,
+) -> HttpResponse {
+    if bearer(&req).as_deref().and_then(scope_for).is_none() {
+        return unauthorized();
+    }
+
+    let content_range = req.headers().get("Content-Range").and_then(|value| value.to_str().ok()).unwrap_or_default().to_string();
+    let Some((start, total)) = parse_content_range(&content_range) else {
+        return HttpResponse::BadRequest().body(format!("Invalid Content-Range '{}'", content_range));
+    };
+
+    let mut state = state.lock().unwrap();
+    let max_ack = state.max_ack_per_chunk;
+    let (corrupt_md5, omit_md5) = (state.corrupt_md5, state.omit_md5);
+    let Some(session) = state.uploads.get_mut(session_id.as_str()) else {
+        return HttpResponse::NotFound().finish();
+    };
+    session.content_ranges.push(content_range.clone());
+
+    let created = |session: &UploadSession| {
+        let md5_checksum = if corrupt_md5 { "0".repeat(32) } else { format!("{:x}", Md5::digest(&session.content)) };
+        let mut file = serde_json::json!({ "id": UPLOADED_FILE_ID, "name": session.metadata["name"] });
+        if !omit_md5 {
+            file["md5Checksum"] = serde_json::json!(md5_checksum);
+        }
+        HttpResponse::Ok().json(file)
+    };
+
+    if session.complete {
+        return created(session);
+    }
+
+    if start.unwrap_or(session.content.len() as u64) != session.content.len() as u64 {
+        return HttpResponse::BadRequest().body(format!("Chunk does not start at byte {}", session.content.len()));
+    }
+    if total.is_none() && !body.len().is_multiple_of(256 * 1024) {
+        return HttpResponse::BadRequest().body("Intermediate chunks must be multiples of 256 KiB");
+    }
+
+    let accepted = match (total, max_ack) {
+        (None, Some(max_ack)) => body.len().min(max_ack),
+        _ => body.len(),
+    };
+    session.content.extend_from_slice(&body[..accepted]);
+
+    if total == Some(session.content.len() as u64) {
+        session.complete = true;
+        return created(session);
+    }
+
+    let mut response = HttpResponse::build(StatusCode::PERMANENT_REDIRECT);
+    if !session.content.is_empty() {
+        response.insert_header(("Range", format!("bytes=0-{}", session.content.len() - 1)));
+    }
+    response.finish()
+}
+
+async fn cancel_upload(session_id: web::Path<String>, state: web::Data<Mutex<MockState>>) -> HttpResponse {
+    state.lock().unwrap().uploads.remove(session_id.as_str());
+    HttpResponse::build(StatusCode::from_u16(499).unwrap()).finish()
+}
+
+/// Starts the mock Google server on an ephemeral local port.
+pub fn start_google_mock() -> MockGoogle {
+    let state = Arc::new(Mutex::new(MockState::default()));
+    let app_state = web::Data::from(state.clone());
+
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(app_state.clone())
+            .app_data(web::PayloadConfig::new(16 * 1024 * 1024))
+            .route("/token", web::post().to(token))
+            .route("/tokeninfo", web::get().to(tokeninfo))
+            .route("/revoke", web::post().to(revoke))
+            .route("/drive/v3/files", web::get().to(list_files))
+            .route("/drive/v3/files", web::post().to(create_file))
+            .route("/drive/v3/files/trash", web::delete().to(empty_trash))
+            .route("/drive/v3/files/{file_id}", web::get().to(get_file))
+            .route("/drive/v3/files/{file_id}", web::patch().to(update_file))
+            .route("/drive/v3/files/{file_id}", web::delete().to(delete_file))
+            .route("/drive/v3/files/{file_id}/copy", web::post().to(copy_file))
+            .route("/drive/v3/files/{file_id}/permissions", web::get().to(list_permissions))
+            .route("/drive/v3/files/{file_id}/permissions", web::post().to(create_permission))
+            .route("/drive/v3/files/{file_id}/permissions/{permission_id}", web::patch().to(update_permission))
+            .route("/drive/v3/files/{file_id}/permissions/{permission_id}", web::delete().to(delete_permission))
+            .route("/drive/v3/files/{file_id}/export", web::get().to(export_file))
+            .route("/upload/drive/v3/files", web::post().to(start_resumable_upload))
+            .route("/upload/session/{session_id}", web::put().to(upload_chunk))
+            .route("/upload/session/{session_id}", web::delete().to(cancel_upload))
+    })
+    .workers(1)
+    .bind(("127.0.0.1", 0))
+    .expect("Failed to bind mock Google server");
+
+    let base_url = format!("http://{}", server.addrs()[0]);
+    let server = server.run();
+    let handle = server.handle();
+    actix_rt::spawn(server);
+
+    MockGoogle { base_url, handle, state }
+}
@@ -0,0 +1,94 @@
+#![allow(dead_code)]
+
+use actix_web::{dev::ServerHandle, web, App, HttpResponse, HttpServer};
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use std::collections::HashMap;
+
+pub const VALID_REFRESH_TOKEN: &str = "valid_refresh_token";
+pub const SERVICE_ACCOUNT_EMAIL: &str = "batch@test-project.iam.gserviceaccount.com";
+
+const SERVICE_ACCOUNT_PUBLIC_KEY: &str = include_str!("../fixtures/service_account_public.pem");
+
+#[derive(serde::Deserialize)]
+struct AssertionClaims {
+    iss: String,
+    scope: String,
+    sub: Option<String>,
+}
+
+/// Verifies a JWT bearer assertion the way Google does and issues a token naming the impersonated principal.
+fn service_account_token(assertion: Option<&str>) -> HttpResponse {
+    let key = DecodingKey::from_rsa_pem(SERVICE_ACCOUNT_PUBLIC_KEY.as_bytes()).expect("Invalid test public key");
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.validate_aud = false;
+
+    match assertion.map(|assertion| decode::<AssertionClaims>(assertion, &key, &validation)) {
+        Some(Ok(token)) if token.claims.iss == SERVICE_ACCOUNT_EMAIL && !token.claims.scope.is_empty() => {
+            let principal = token.claims.sub.unwrap_or(token.claims.iss);
+            HttpResponse::Ok().json(serde_json::json!({
+                "access_token": format!("sa_token:{}", principal),
+                "expires_in": 3599,
+                "token_type": "Bearer"
+            }))
+        }
+        _ => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "invalid_grant",
+            "error_description": "Invalid JWT Signature."
+        })),
+    }
+}
+
+async fn token(form: web::Form<HashMap<String, String>>) -> HttpResponse {
+    let grant_type = form.get("grant_type").map(String::as_str);
+    let refresh_token = form.get("refresh_token").map(String::as_str);
+
+    if grant_type == Some("urn:ietf:params:oauth:grant-type:jwt-bearer") {
+        return service_account_token(form.get("assertion").map(String::as_str));
+    }
+
+    match (grant_type, refresh_token) {
+        (Some("refresh_token"), Some(VALID_REFRESH_TOKEN)) => HttpResponse::Ok().json(serde_json::json!({
+            "access_token": "refreshed_access_token",
+            "expires_in": 3599,
+            "scope": "https://www.googleapis.com/auth/drive",
+            "token_type": "Bearer"
+        })),
+        _ => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "invalid_grant",
+            "error_description": "Token has been expired or revoked."
+        })),
+    }
+}
+
+async fn revoke(query: web::Query<HashMap<String, String>>, body: String) -> HttpResponse {
+    let form: HashMap<String, String> = url::form_urlencoded::parse(body.as_bytes()).into_owned().collect();
+    let token = query.get("token").or_else(|| form.get("token")).map(String::as_str);
+
+    match token {
+        Some(VALID_REFRESH_TOKEN) => HttpResponse::Ok().finish(),
+        _ => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "unsupported_token_type",
+            "error_description": "Invalid token type."
+        })),
+    }
+}
+
+/// Starts a local stand-in for Google's token and revoke endpoints and returns the token endpoint URL.
+/// The revoke endpoint is served from the same host under `/revoke`.
+pub fn start_token_endpoint() -> (String, ServerHandle) {
+    let server = HttpServer::new(|| {
+        App::new()
+            .route("/token", web::post().to(token))
+            .route("/revoke", web::post().to(revoke))
+    })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .expect("Failed to bind mock token endpoint");
+
+    let addr = server.addrs()[0];
+    let server = server.run();
+    let handle = server.handle();
+    actix_rt::spawn(server);
+
+    (format!("http://{}/token", addr), handle)
+}
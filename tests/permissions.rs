@@ -0,0 +1,99 @@
+use api_drive::api::permissions::{NewPermission, Permission, PermissionOptions, PermissionRole, PermissionType, PermissionUpdate};
+
+fn share(permission_type: PermissionType, role: PermissionRole) -> NewPermission {
+    NewPermission {
+        permission_type,
+        role,
+        email_address: None,
+        domain: None,
+        allow_file_discovery: None,
+        expiration_time: None,
+    }
+}
+
+fn share_with(email: &str, role: PermissionRole) -> NewPermission {
+    NewPermission { email_address: Some(email.to_string()), ..share(PermissionType::User, role) }
+}
+
+#[test]
+fn test_each_type_needs_its_target() {
+    let none = PermissionOptions::default();
+
+    assert!(share_with("ana@example.com", PermissionRole::Writer).validate(&none).is_ok());
+    assert!(share(PermissionType::User, PermissionRole::Reader).validate(&none).is_err());
+    assert!(NewPermission { email_address: Some(" ".to_string()), ..share(PermissionType::Group, PermissionRole::Reader) }.validate(&none).is_err());
+
+    let domain = NewPermission { domain: Some("example.com".to_string()), ..share(PermissionType::Domain, PermissionRole::Reader) };
+    assert!(domain.validate(&none).is_ok());
+    assert!(share(PermissionType::Domain, PermissionRole::Reader).validate(&none).is_err());
+    assert!(NewPermission { email_address: Some("ana@example.com".to_string()), ..domain }.validate(&none).is_err());
+
+    assert!(share(PermissionType::Anyone, PermissionRole::Commenter).validate(&none).is_ok());
+    assert!(NewPermission { domain: Some("example.com".to_string()), ..share(PermissionType::Anyone, PermissionRole::Reader) }.validate(&none).is_err());
+}
+
+#[test]
+fn test_notifications_only_go_to_users_and_groups() {
+    let message = PermissionOptions { email_message: Some("Have a look".to_string()), ..Default::default() };
+    assert!(share_with("ana@example.com", PermissionRole::Reader).validate(&message).is_ok());
+    assert!(share(PermissionType::Anyone, PermissionRole::Reader).validate(&message).is_err());
+
+    let silent_message = PermissionOptions { send_notification_email: Some(false), ..message };
+    assert!(share_with("ana@example.com", PermissionRole::Reader).validate(&silent_message).is_err());
+
+    let silent = PermissionOptions { send_notification_email: Some(false), ..Default::default() };
+    assert!(share_with("ana@example.com", PermissionRole::Reader).validate(&silent).is_ok());
+}
+
+#[test]
+fn test_owner_role_needs_an_explicit_transfer() {
+    let transfer = PermissionOptions { transfer_ownership: true, ..Default::default() };
+
+    assert!(share_with("ana@example.com", PermissionRole::Owner).validate(&transfer).is_ok());
+    assert!(share_with("ana@example.com", PermissionRole::Owner).validate(&PermissionOptions::default()).is_err());
+    assert!(share_with("ana@example.com", PermissionRole::Writer).validate(&transfer).is_err());
+
+    let group = NewPermission { permission_type: PermissionType::Group, ..share_with("team@example.com", PermissionRole::Owner) };
+    assert!(group.validate(&transfer).is_err());
+
+    let silent = PermissionOptions { send_notification_email: Some(false), ..transfer };
+    assert!(share_with("ana@example.com", PermissionRole::Owner).validate(&silent).is_err());
+
+    let update = |role, transfer_ownership| PermissionUpdate { role, expiration_time: None, transfer_ownership }.validate();
+    assert!(update(PermissionRole::Owner, true).is_ok());
+    assert!(update(PermissionRole::Owner, false).is_err());
+    assert!(update(PermissionRole::Commenter, true).is_err());
+}
+
+#[test]
+fn test_new_permission_is_sent_in_drive_names() {
+    let permission = NewPermission {
+        allow_file_discovery: Some(false),
+        domain: Some("example.com".to_string()),
+        ..share(PermissionType::Domain, PermissionRole::FileOrganizer)
+    };
+
+    assert_eq!(
+        permission.to_json(),
+        serde_json::json!({ "type": "domain", "role": "fileOrganizer", "domain": "example.com", "allowFileDiscovery": false })
+    );
+}
+
+#[test]
+fn test_permission_reads_drive_names_and_writes_snake_case() {
+    let permission: Permission = serde_json::from_value(serde_json::json!({
+        "id": "p1",
+        "type": "user",
+        "role": "writer",
+        "emailAddress": "ana@example.com",
+        "displayName": "Ana"
+    }))
+    .unwrap();
+
+    assert_eq!(permission.permission_type, PermissionType::User);
+    assert_eq!(permission.role, PermissionRole::Writer);
+    assert_eq!(
+        serde_json::to_value(&permission).unwrap(),
+        serde_json::json!({ "id": "p1", "type": "user", "role": "writer", "email_address": "ana@example.com", "display_name": "Ana" })
+    );
+}
@@ -0,0 +1,18 @@
+use api_drive::services::pkce::{code_challenge, generate_code_verifier, generate_state};
+
+#[test]
+fn test_code_challenge_matches_rfc7636_example() {
+    let verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+
+    assert_eq!(code_challenge(verifier), "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+}
+
+#[test]
+fn test_code_verifier_is_valid_and_unique() {
+    let verifier = generate_code_verifier();
+
+    assert!((43..=128).contains(&verifier.len()));
+    assert!(verifier.chars().all(|c| c.is_ascii_alphanumeric() || "-._~".contains(c)));
+    assert_ne!(verifier, generate_code_verifier());
+    assert_ne!(generate_state(), generate_state());
+}
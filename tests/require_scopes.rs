@@ -0,0 +1,100 @@
+#[path = "mocks/config_mock.rs"]
+mod config_mock;
+
+use actix_web::{http::header, test::{init_service, try_call_service, TestRequest}, web, App, HttpResponse};
+use api_drive::api::auth::TokenInfo;
+use api_drive::middlewares::auth_guard::AuthGuard;
+use api_drive::middlewares::require_scopes::{RequireScopes, DRIVE, DRIVE_READONLY, READ_SCOPES, WRITE_SCOPES};
+use api_drive::middlewares::token_cache::TokenCache;
+use config_mock::mock_config;
+use std::time::Duration;
+
+fn token_info(aud: &str, scopes: &[&str]) -> TokenInfo {
+    TokenInfo {
+        aud: Some(aud.to_string()),
+        scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+        exp: None,
+        expires_in: Some(3600),
+        email: Some("user@example.com".to_string()),
+    }
+}
+
+async fn ok() -> HttpResponse {
+    HttpResponse::Ok().finish()
+}
+
+/// Calls `method /files` with a bearer token whose tokeninfo result is already cached,
+/// so the guard never reaches Google.
+async fn call_with_token(method: TestRequest, token_info: TokenInfo) -> u16 {
+    let cache = web::Data::new(TokenCache::new(10, Duration::from_secs(30)));
+    cache.insert_valid("cached_token", token_info, Duration::from_secs(60));
+
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(mock_config()))
+            .app_data(cache.clone())
+            .service(
+                web::scope("")
+                    .wrap(AuthGuard::new())
+                    .route("/files", web::get().to(ok).wrap(RequireScopes::any_of(READ_SCOPES)))
+                    .route("/files", web::post().to(ok).wrap(RequireScopes::any_of(WRITE_SCOPES))),
+            ),
+    )
+    .await;
+
+    let req = method
+        .uri("/files")
+        .insert_header((header::AUTHORIZATION, "Bearer cached_token"))
+        .to_request();
+
+    match try_call_service(&app, req).await {
+        Ok(resp) => resp.status().as_u16(),
+        Err(err) => err.as_response_error().status_code().as_u16(),
+    }
+}
+
+#[actix_web::test]
+async fn test_readonly_token_can_read() {
+    let status = call_with_token(TestRequest::get(), token_info("test_client_id", &[DRIVE_READONLY])).await;
+    assert_eq!(status, 200);
+}
+
+#[actix_web::test]
+async fn test_readonly_token_cannot_upload() {
+    let status = call_with_token(TestRequest::post(), token_info("test_client_id", &[DRIVE_READONLY])).await;
+    assert_eq!(status, 403);
+}
+
+#[actix_web::test]
+async fn test_full_drive_token_can_upload() {
+    let status = call_with_token(TestRequest::post(), token_info("test_client_id", &[DRIVE])).await;
+    assert_eq!(status, 200);
+}
+
+#[actix_web::test]
+async fn test_token_for_another_client_is_rejected() {
+    let status = call_with_token(TestRequest::get(), token_info("someone_else", &[DRIVE])).await;
+    assert_eq!(status, 401);
+}
+
+#[test]
+fn test_token_info_parses_tokeninfo_response() {
+    let json = serde_json::json!({
+        "azp": "test_client_id",
+        "aud": "test_client_id",
+        "scope": "https://www.googleapis.com/auth/drive.readonly openid",
+        "exp": "1700003600",
+        "expires_in": "3599",
+        "email": "user@example.com"
+    });
+
+    let token_info = TokenInfo::from_json(&json);
+
+    assert_eq!(token_info.aud.as_deref(), Some("test_client_id"));
+    assert_eq!(token_info.scopes, vec![DRIVE_READONLY.to_string(), "openid".to_string()]);
+    assert_eq!(token_info.exp, Some(1700003600));
+    assert_eq!(token_info.expires_in, Some(3599));
+    assert_eq!(token_info.email.as_deref(), Some("user@example.com"));
+    assert!(token_info.has_any_scope(READ_SCOPES));
+    assert!(!token_info.has_any_scope(WRITE_SCOPES));
+}
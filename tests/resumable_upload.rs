@@ -0,0 +1,139 @@
+#[path = "mocks/google_server_mock.rs"]
+mod google_server_mock;
+#[path = "mocks/config_mock.rs"]
+mod config_mock;
+
+use api_drive::api::google_drive::{content_range, next_offset_from_range, UploadMetadata};
+use api_drive::config::Config;
+use api_drive::services::google_drive_service::{DriveService, GoogleDriveService};
+use api_drive::services::resumable_upload::{aligned_chunk_size, ResumableUpload};
+use config_mock::mock_config;
+use google_server_mock::{start_google_mock, MockGoogle, FULL_ACCESS_TOKEN};
+
+const KIB_256: usize = 256 * 1024;
+
+fn config_for(mock: &MockGoogle) -> Config {
+    let mut config = mock_config();
+    mock.configure(&mut config);
+    config
+}
+
+fn test_bytes(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+/// Uploads `content` in `write_size` pieces through a fresh session and returns the file ID.
+async fn upload(mock: &MockGoogle, content: &[u8], write_size: usize, chunk_size: usize) -> String {
+    let config = config_for(mock);
+    let session_url = GoogleDriveService
+        .initialize_resumable_upload(FULL_ACCESS_TOKEN, &UploadMetadata::new("test.pdf"), &config)
+        .await
+        .unwrap();
+
+    let mut upload = ResumableUpload::new(&GoogleDriveService, FULL_ACCESS_TOKEN, session_url, chunk_size);
+    for piece in content.chunks(write_size) {
+        upload.write(piece).await.unwrap();
+    }
+    upload.finish().await.unwrap().file_id
+}
+
+#[test]
+fn test_content_range_header() {
+    assert_eq!(content_range(0, KIB_256, None), "bytes 0-262143/*");
+    assert_eq!(content_range(262144, 100, Some(262244)), "bytes 262144-262243/262244");
+    assert_eq!(content_range(0, 0, Some(0)), "bytes */0");
+    assert_eq!(content_range(524288, 0, Some(524288)), "bytes */524288");
+}
+
+#[test]
+fn test_next_offset_from_range_header() {
+    assert_eq!(next_offset_from_range(Some("bytes=0-262143")), Some(262144));
+    assert_eq!(next_offset_from_range(Some(" bytes=0-0 ")), Some(1));
+    assert_eq!(next_offset_from_range(None), Some(0));
+    assert_eq!(next_offset_from_range(Some("bytes=10-20")), None);
+    assert_eq!(next_offset_from_range(Some("garbage")), None);
+}
+
+#[test]
+fn test_chunk_size_is_aligned_to_256_kib() {
+    assert_eq!(aligned_chunk_size(0), KIB_256);
+    assert_eq!(aligned_chunk_size(1000), KIB_256);
+    assert_eq!(aligned_chunk_size(KIB_256), KIB_256);
+    assert_eq!(aligned_chunk_size(KIB_256 + 1), 2 * KIB_256);
+}
+
+#[actix_rt::test]
+async fn test_upload_sends_aligned_chunks_with_content_range() {
+    let mock = start_google_mock();
+    let content = test_bytes(2 * KIB_256 + 90_000);
+
+    let file_id = upload(&mock, &content, 64 * 1024, KIB_256).await;
+
+    assert_eq!(file_id, "uploaded_file_id");
+    let session = mock.upload("1");
+    assert!(session.complete);
+    assert_eq!(session.content, content);
+    assert_eq!(session.metadata["name"], "test.pdf");
+    assert_eq!(
+        session.content_ranges,
+        vec![
+            "bytes 0-262143/*".to_string(),
+            "bytes 262144-524287/*".to_string(),
+            "bytes 524288-614287/614288".to_string(),
+        ]
+    );
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_of_exact_multiple_declares_total_on_last_full_chunk() {
+    let mock = start_google_mock();
+    let content = test_bytes(2 * KIB_256);
+
+    upload(&mock, &content, 100_000, KIB_256).await;
+
+    let session = mock.upload("1");
+    assert_eq!(session.content, content);
+    assert_eq!(
+        session.content_ranges,
+        vec!["bytes 0-262143/*".to_string(), "bytes 262144-524287/524288".to_string()]
+    );
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_of_empty_file() {
+    let mock = start_google_mock();
+
+    upload(&mock, &[], 1, KIB_256).await;
+
+    let session = mock.upload("1");
+    assert!(session.complete);
+    assert_eq!(session.content_ranges, vec!["bytes */0".to_string()]);
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_resends_bytes_drive_did_not_persist() {
+    let mock = start_google_mock();
+    mock.state.lock().unwrap().max_ack_per_chunk = Some(KIB_256);
+    let content = test_bytes(3 * KIB_256 + 10);
+
+    upload(&mock, &content, 128 * 1024, 2 * KIB_256).await;
+
+    let session = mock.upload("1");
+    assert_eq!(session.content, content);
+    assert_eq!(
+        session.content_ranges,
+        vec![
+            "bytes 0-524287/*".to_string(),
+            "bytes 262144-786431/*".to_string(),
+            "bytes 524288-786441/786442".to_string(),
+        ]
+    );
+
+    mock.stop().await;
+}
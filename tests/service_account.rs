@@ -0,0 +1,88 @@
+#[path = "mocks/token_endpoint_mock.rs"]
+mod token_endpoint_mock;
+#[path = "mocks/config_mock.rs"]
+mod config_mock;
+
+use actix_web::{test, web, App, HttpRequest};
+use api_drive::config::AuthMode;
+use api_drive::middlewares::auth_guard::{access_token, AuthGuard};
+use api_drive::services::auth_service::{ServiceAccountKey, ServiceAccountTokenProvider};
+use config_mock::mock_config;
+use token_endpoint_mock::{start_token_endpoint, SERVICE_ACCOUNT_EMAIL};
+
+const KEY_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/service_account.json");
+
+#[actix_rt::test]
+async fn test_service_account_token_exchange() {
+    let (token_uri, server) = start_token_endpoint();
+    let mut config = mock_config();
+    config.token_uri = token_uri;
+
+    let key = ServiceAccountKey::from_file(KEY_PATH).expect("Failed to load test key");
+    let provider = ServiceAccountTokenProvider::new(key, None);
+
+    let token = provider.access_token(&config).await.expect("Exchange should succeed");
+    assert_eq!(token, format!("sa_token:{}", SERVICE_ACCOUNT_EMAIL));
+
+    let cached = provider.access_token(&config).await.unwrap();
+    assert_eq!(cached, token);
+
+    server.stop(true).await;
+}
+
+#[actix_rt::test]
+async fn test_service_account_domain_wide_delegation() {
+    let (token_uri, server) = start_token_endpoint();
+    let mut config = mock_config();
+    config.token_uri = token_uri;
+
+    let key = ServiceAccountKey::from_file(KEY_PATH).unwrap();
+    let provider = ServiceAccountTokenProvider::new(key, Some("user@example.com".to_string()));
+
+    let token = provider.access_token(&config).await.expect("Exchange should succeed");
+    assert_eq!(token, "sa_token:user@example.com");
+
+    server.stop(true).await;
+}
+
+async fn echo_token(req: HttpRequest) -> String {
+    access_token(&req).unwrap_or_default()
+}
+
+#[actix_rt::test]
+async fn test_auth_guard_uses_service_account_credentials() {
+    let (token_uri, server) = start_token_endpoint();
+    let mut config = mock_config();
+    config.token_uri = token_uri;
+    config.auth_mode = AuthMode::ServiceAccount;
+    config.service_api_key = Some("batch-api-key".to_string());
+
+    let key = ServiceAccountKey::from_file(KEY_PATH).unwrap();
+    let provider = web::Data::new(ServiceAccountTokenProvider::new(key, None));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(config))
+            .app_data(provider.clone())
+            .service(web::scope("").wrap(AuthGuard::new()).route("/whoami", web::get().to(echo_token))),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri("/whoami")
+        .insert_header(("X-Api-Key", "batch-api-key"))
+        .to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    assert_eq!(body, web::Bytes::from(format!("sa_token:{}", SERVICE_ACCOUNT_EMAIL)));
+
+    let req = test::TestRequest::get()
+        .uri("/whoami")
+        .insert_header(("X-Api-Key", "wrong-key"))
+        .to_request();
+    match test::try_call_service(&app, req).await {
+        Ok(_) => panic!("A wrong API key should be rejected"),
+        Err(err) => assert_eq!(err.as_response_error().status_code(), 401),
+    }
+
+    server.stop(true).await;
+}
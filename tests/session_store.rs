@@ -0,0 +1,135 @@
+#[path = "mocks/config_mock.rs"]
+mod config_mock;
+
+use actix_web::{http::header, test, web, App, HttpRequest};
+use api_drive::middlewares::auth_guard::{access_token, AuthGuard};
+use api_drive::middlewares::session::session_cookie;
+use api_drive::services::session_store::{now_secs, InMemorySessionStore, PendingLogin, Session, SessionStore};
+use api_drive::services::sqlite_session_store::SqliteSessionStore;
+use config_mock::mock_config;
+use std::sync::Arc;
+
+fn live_session() -> Session {
+    Session {
+        access_token: "google_access_token".to_string(),
+        refresh_token: Some("google_refresh_token".to_string()),
+        scope: Some("https://www.googleapis.com/auth/drive".to_string()),
+        expires_at: now_secs() + 3600,
+    }
+}
+
+async fn assert_round_trip(store: &dyn SessionStore) {
+    store.insert("session-1", live_session()).await.unwrap();
+
+    let session = store.get("session-1").await.unwrap().expect("Session should exist");
+    assert_eq!(session.access_token, "google_access_token");
+    assert_eq!(session.refresh_token, Some("google_refresh_token".to_string()));
+    assert!(!session.is_expired());
+
+    let mut updated = live_session();
+    updated.access_token = "rotated_access_token".to_string();
+    store.insert("session-1", updated).await.unwrap();
+    let session = store.get("session-1").await.unwrap().unwrap();
+    assert_eq!(session.access_token, "rotated_access_token");
+
+    store.remove("session-1").await.unwrap();
+    assert!(store.get("session-1").await.unwrap().is_none());
+
+    store.insert_login("state-1", PendingLogin::new("verifier-1".to_string())).await.unwrap();
+    let login = store.take_login("state-1").await.unwrap().expect("Pending login should exist");
+    assert_eq!(login.code_verifier, "verifier-1");
+    assert!(store.take_login("state-1").await.unwrap().is_none());
+
+    let expired = PendingLogin { code_verifier: "verifier-2".to_string(), expires_at: now_secs() - 1 };
+    store.insert_login("state-2", expired).await.unwrap();
+    assert!(store.take_login("state-2").await.unwrap().is_none());
+}
+
+#[actix_rt::test]
+async fn test_in_memory_store_round_trip() {
+    assert_round_trip(&InMemorySessionStore::new()).await;
+}
+
+#[actix_rt::test]
+async fn test_sqlite_store_round_trip() {
+    let store = SqliteSessionStore::open_in_memory().expect("Failed to open SQLite store");
+    assert_round_trip(&store).await;
+}
+
+async fn echo_token(req: HttpRequest) -> String {
+    access_token(&req).unwrap_or_default()
+}
+
+#[actix_web::test]
+async fn test_auth_guard_resolves_session_cookie() {
+    let config = mock_config();
+    let store = Arc::new(InMemorySessionStore::new());
+    store.insert("opaque-session", live_session()).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(config.clone()))
+            .app_data(web::Data::from(store.clone() as Arc<dyn SessionStore>))
+            .service(web::scope("").wrap(AuthGuard::new()).route("/whoami", web::get().to(echo_token))),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri("/whoami")
+        .cookie(session_cookie("opaque-session", &config))
+        .to_request();
+
+    let body = test::call_and_read_body(&app, req).await;
+    assert_eq!(body, web::Bytes::from_static(b"google_access_token"));
+}
+
+#[actix_web::test]
+async fn test_auth_guard_resolves_opaque_bearer_session() {
+    let config = mock_config();
+    let store = Arc::new(InMemorySessionStore::new());
+    store.insert("opaque-session", live_session()).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(config))
+            .app_data(web::Data::from(store.clone() as Arc<dyn SessionStore>))
+            .service(web::scope("").wrap(AuthGuard::new()).route("/whoami", web::get().to(echo_token))),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri("/whoami")
+        .insert_header((header::AUTHORIZATION, "Bearer opaque-session"))
+        .to_request();
+
+    let body = test::call_and_read_body(&app, req).await;
+    assert_eq!(body, web::Bytes::from_static(b"google_access_token"));
+}
+
+#[actix_web::test]
+async fn test_auth_guard_rejects_expired_session_without_refresh_token() {
+    let config = mock_config();
+    let store = Arc::new(InMemorySessionStore::new());
+    let mut expired = live_session();
+    expired.refresh_token = None;
+    expired.expires_at = now_secs() - 1;
+    store.insert("expired-session", expired).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(config.clone()))
+            .app_data(web::Data::from(store.clone() as Arc<dyn SessionStore>))
+            .service(web::scope("").wrap(AuthGuard::new()).route("/whoami", web::get().to(echo_token))),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri("/whoami")
+        .cookie(session_cookie("expired-session", &config))
+        .to_request();
+
+    match test::try_call_service(&app, req).await {
+        Ok(_) => panic!("Expired session should be rejected"),
+        Err(err) => assert_eq!(err.as_response_error().status_code(), 401),
+    }
+}
@@ -0,0 +1,104 @@
+use actix_web::{test::{call_and_read_body_json, init_service, TestRequest}, web, App};
+use api_drive::api::auth::TokenInfo;
+use api_drive::handlers::metrics_handler::get_token_cache_stats;
+use api_drive::middlewares::token_cache::{TokenCache, TokenCacheStats};
+use std::thread::sleep;
+use std::time::Duration;
+
+fn token_info() -> TokenInfo {
+    TokenInfo {
+        aud: Some("test_client_id".to_string()),
+        scopes: vec!["https://www.googleapis.com/auth/drive".to_string()],
+        exp: None,
+        expires_in: Some(3600),
+        email: Some("user@example.com".to_string()),
+    }
+}
+
+fn is_valid(cached: Option<Option<TokenInfo>>) -> Option<bool> {
+    cached.map(|token_info| token_info.is_some())
+}
+
+#[test]
+fn test_cache_hits_and_misses_are_counted() {
+    let cache = TokenCache::new(10, Duration::from_secs(30));
+
+    assert_eq!(is_valid(cache.get("token")), None);
+    cache.insert_valid("token", token_info(), Duration::from_secs(60));
+    assert_eq!(is_valid(cache.get("token")), Some(true));
+    assert_eq!(is_valid(cache.get("token")), Some(true));
+
+    let stats = cache.stats();
+    assert_eq!(stats.hits, 2);
+    assert_eq!(stats.misses, 1);
+    assert_eq!(stats.entries, 1);
+}
+
+#[test]
+fn test_entries_expire_at_token_expiry() {
+    let cache = TokenCache::new(10, Duration::from_secs(30));
+
+    cache.insert_valid("short_lived", token_info(), Duration::from_millis(50));
+    assert_eq!(is_valid(cache.get("short_lived")), Some(true));
+
+    sleep(Duration::from_millis(80));
+    assert_eq!(is_valid(cache.get("short_lived")), None);
+}
+
+#[test]
+fn test_negative_results_use_short_ttl() {
+    let cache = TokenCache::new(10, Duration::from_millis(50));
+
+    cache.insert_invalid("revoked");
+    assert_eq!(is_valid(cache.get("revoked")), Some(false));
+
+    sleep(Duration::from_millis(80));
+    assert_eq!(is_valid(cache.get("revoked")), None);
+}
+
+#[test]
+fn test_cache_is_bounded() {
+    let cache = TokenCache::new(2, Duration::from_secs(30));
+
+    cache.insert_valid("first", token_info(), Duration::from_secs(10));
+    cache.insert_valid("second", token_info(), Duration::from_secs(60));
+    cache.insert_valid("third", token_info(), Duration::from_secs(60));
+
+    assert_eq!(cache.stats().entries, 2);
+    assert_eq!(is_valid(cache.get("first")), None);
+    assert_eq!(is_valid(cache.get("second")), Some(true));
+    assert_eq!(is_valid(cache.get("third")), Some(true));
+}
+
+#[test]
+fn test_invalidate_removes_entry() {
+    let cache = TokenCache::new(10, Duration::from_secs(30));
+
+    cache.insert_valid("token", token_info(), Duration::from_secs(60));
+    cache.invalidate("token");
+
+    assert_eq!(is_valid(cache.get("token")), None);
+}
+
+#[actix_web::test]
+async fn test_token_cache_stats_endpoint() {
+    let cache = web::Data::new(TokenCache::new(10, Duration::from_secs(30)));
+    cache.insert_valid("token", token_info(), Duration::from_secs(60));
+    cache.get("token");
+    cache.get("unknown");
+
+    let app = init_service(
+        App::new()
+            .app_data(cache.clone())
+            .route("/metrics/token-cache", web::get().to(get_token_cache_stats)),
+    )
+    .await;
+
+    let req = TestRequest::get().uri("/metrics/token-cache").to_request();
+    let stats: TokenCacheStats = call_and_read_body_json(&app, req).await;
+
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.misses, 1);
+    assert_eq!(stats.entries, 1);
+    assert_eq!(stats.capacity, 10);
+}
@@ -0,0 +1,69 @@
+#[path = "mocks/token_endpoint_mock.rs"]
+mod token_endpoint_mock;
+#[path = "mocks/config_mock.rs"]
+mod config_mock;
+
+use api_drive::services::auth_service::{AuthService, AuthTokenService};
+use api_drive::api::auth::revoke_token;
+use config_mock::mock_config;
+use token_endpoint_mock::{start_token_endpoint, VALID_REFRESH_TOKEN};
+
+#[actix_rt::test]
+async fn test_refresh_access_token_against_local_endpoint() {
+    let (token_uri, server) = start_token_endpoint();
+    let mut config = mock_config();
+    config.token_uri = token_uri;
+
+    let token_response = AuthTokenService
+        .refresh_access_token(VALID_REFRESH_TOKEN, &config)
+        .await
+        .expect("Refresh should succeed");
+
+    assert_eq!(token_response.access_token, "refreshed_access_token");
+    assert_eq!(token_response.expires_in, 3599);
+    assert!(token_response.refresh_token.is_none());
+
+    server.stop(true).await;
+}
+
+#[actix_rt::test]
+async fn test_refresh_access_token_rejected_by_local_endpoint() {
+    let (token_uri, server) = start_token_endpoint();
+    let mut config = mock_config();
+    config.token_uri = token_uri;
+
+    let result = AuthTokenService
+        .refresh_access_token("revoked_refresh_token", &config)
+        .await;
+
+    let err = result.expect_err("Refresh with a revoked token should fail");
+    assert!(format!("{:?}", err).contains("invalid_grant"));
+
+    server.stop(true).await;
+}
+
+#[actix_rt::test]
+async fn test_revoke_token_with_form_parameter() {
+    let (token_uri, server) = start_token_endpoint();
+    let mut config = mock_config();
+    config.revoke_uri = token_uri.replace("/token", "/revoke");
+
+    revoke_token(VALID_REFRESH_TOKEN, &config).await.expect("Revocation should succeed");
+    assert!(revoke_token("unknown_token", &config).await.is_err());
+
+    server.stop(true).await;
+}
+
+#[actix_rt::test]
+async fn test_revoke_token_with_url_placeholder() {
+    let (token_uri, server) = start_token_endpoint();
+    let mut config = mock_config();
+    config.revoke_uri = token_uri.replace("/token", "/revoke?token={}");
+
+    AuthTokenService
+        .revoke_token(VALID_REFRESH_TOKEN, &config)
+        .await
+        .expect("Revocation should succeed");
+
+    server.stop(true).await;
+}
@@ -0,0 +1,362 @@
+#[path = "mocks/google_server_mock.rs"]
+mod google_server_mock;
+#[path = "mocks/config_mock.rs"]
+mod config_mock;
+
+use api_drive::config::Config;
+use api_drive::server;
+use api_drive::services::session_store::{new_session_id, now_secs};
+use api_drive::services::sqlite_upload_store::SqliteUploadStore;
+use api_drive::services::upload_store::{InMemoryUploadStore, UploadSession, UploadStore};
+use config_mock::mock_config;
+use google_server_mock::{start_google_mock, MockGoogle, FULL_ACCESS_TOKEN, UPLOADED_FILE_ID};
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::net::TcpListener;
+
+async fn assert_round_trip(store: &dyn UploadStore) {
+    let upload = UploadSession::new("https://upload.example/session/1".to_string(), "report.pdf".to_string(), Some(1000));
+    store.insert("upload-1", upload).await.unwrap();
+
+    let mut upload = store.get("upload-1").await.unwrap().expect("Upload should exist");
+    assert_eq!(upload.resumable_url, "https://upload.example/session/1");
+    assert_eq!(upload.total_size, Some(1000));
+    assert_eq!(upload.committed, 0);
+    assert!(!upload.is_complete());
+
+    upload.committed = 1000;
+    upload.file_id = Some("file-1".to_string());
+    store.insert("upload-1", upload).await.unwrap();
+    let upload = store.get("upload-1").await.unwrap().unwrap();
+    assert_eq!(upload.committed, 1000);
+    assert!(upload.is_complete());
+
+    store.remove("upload-1").await.unwrap();
+    assert!(store.get("upload-1").await.unwrap().is_none());
+
+    let mut expired = UploadSession::new("https://upload.example/session/2".to_string(), "old.pdf".to_string(), None);
+    expired.expires_at = now_secs() - 1;
+    store.insert("upload-2", expired).await.unwrap();
+    assert!(store.get("upload-2").await.unwrap().is_none());
+}
+
+#[actix_rt::test]
+async fn test_in_memory_upload_store_round_trip() {
+    assert_round_trip(&InMemoryUploadStore::new()).await;
+}
+
+#[actix_rt::test]
+async fn test_sqlite_upload_store_round_trip() {
+    let store = SqliteUploadStore::open_in_memory().expect("Failed to open SQLite store");
+    assert_round_trip(&store).await;
+}
+
+fn sqlite_config(mock: &MockGoogle) -> Config {
+    let mut config = mock_config();
+    mock.configure(&mut config);
+    config.session_store = "sqlite".to_string();
+    config.session_db_path = std::env::temp_dir()
+        .join(format!("api_drive_uploads_{}.db", new_session_id()))
+        .to_string_lossy()
+        .into_owned();
+    config
+}
+
+fn spawn_app(config: Config) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind test listener");
+    let address = listener.local_addr().unwrap();
+    let server = server::run(config, listener).expect("Failed to start server");
+    actix_rt::spawn(server);
+
+    format!("http://{}", address)
+}
+
+/// A PDF of exactly `len` bytes, padded with a comment after its header.
+fn padded_pdf(len: usize) -> Vec<u8> {
+    let body = |xref: usize| format!("\nxref\n0 1\n0000000000 65535 f \ntrailer\n<< /Size 1 >>\nstartxref\n{:010}\n%%EOF\n", xref);
+    let mut pdf = b"%PDF-1.4\n%".to_vec();
+    let padding = len - pdf.len() - body(0).len();
+    pdf.extend((0..padding).map(|i| b'a' + (i % 26) as u8));
+    let xref = pdf.len() + 1;
+    pdf.extend_from_slice(body(xref).as_bytes());
+    assert_eq!(pdf.len(), len);
+    pdf
+}
+
+async fn start_upload(client: &reqwest::Client, app: &str, request: Value) -> reqwest::Response {
+    client
+        .post(format!("{}/drive/uploads", app))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .json(&request)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn create_upload(client: &reqwest::Client, app: &str) -> String {
+    let request = serde_json::json!({ "file_name": "big.pdf", "folder_id": "folder1", "mime_type": "application/pdf" });
+    let response = start_upload(client, app, request).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let status: Value = response.json().await.unwrap();
+    assert_eq!(status["committed"], 0);
+    status["upload_id"].as_str().unwrap().to_string()
+}
+
+async fn put_range(client: &reqwest::Client, app: &str, upload_id: &str, content_range: &str, body: &[u8]) -> (StatusCode, Value) {
+    let response = client
+        .put(format!("{}/drive/uploads/{}", app, upload_id))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .header("Content-Range", content_range)
+        .body(body.to_vec())
+        .send()
+        .await
+        .unwrap();
+    let status = response.status();
+    (status, response.json().await.unwrap_or(Value::Null))
+}
+
+#[actix_rt::test]
+async fn test_upload_resumes_across_requests_and_restarts() {
+    let mock = start_google_mock();
+    let config = sqlite_config(&mock);
+    let client = reqwest::Client::new();
+    let content = padded_pdf(600_000);
+
+    let first_app = spawn_app(config.clone());
+    let upload_id = create_upload(&client, &first_app).await;
+
+    // Only whole 256 KiB blocks are committed; the tail has to be sent again.
+    let (status, body) = put_range(&client, &first_app, &upload_id, "bytes 0-299999/*", &content[..300_000]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["committed"], 262_144);
+    assert_eq!(body["complete"], false);
+
+    // A second server sharing the same database picks the upload up.
+    let second_app = spawn_app(config);
+    let status: Value = client
+        .get(format!("{}/drive/uploads/{}", second_app, upload_id))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(status["committed"], 262_144);
+
+    // Overlapping bytes the client resends are skipped.
+    let (status, body) = put_range(&client, &second_app, &upload_id, "bytes 200000-599999/600000", &content[200_000..]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["complete"], true);
+    assert_eq!(body["committed"], 600_000);
+    assert_eq!(body["file_id"], UPLOADED_FILE_ID);
+
+    let session = mock.upload("1");
+    assert!(session.complete);
+    assert_eq!(session.content, content);
+    assert_eq!(session.metadata["name"], "big.pdf");
+    assert_eq!(session.metadata["mimeType"], "application/pdf");
+    // The second server did not see the first bytes, so it read the file back to check it.
+    assert!(mock.state.lock().unwrap().deleted.is_empty());
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_restarted_midway_is_checked_once_complete() {
+    let mock = start_google_mock();
+    let config = sqlite_config(&mock);
+    let client = reqwest::Client::new();
+    // Valid up to the point where the first server stops; the trailer is missing.
+    let mut content = padded_pdf(400_000);
+    content.truncate(content.len() - 7);
+    let total = content.len();
+
+    let first_app = spawn_app(config.clone());
+    let upload_id = create_upload(&client, &first_app).await;
+    let (status, body) = put_range(&client, &first_app, &upload_id, "bytes 0-299999/*", &content[..300_000]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["committed"], 262_144);
+
+    let second_app = spawn_app(config);
+    let content_range = format!("bytes 262144-{}/{}", total - 1, total);
+    let (status, _) = put_range(&client, &second_app, &upload_id, &content_range, &content[262_144..]).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    // Drive had already created the file, so it is deleted again.
+    assert_eq!(mock.state.lock().unwrap().deleted, vec![UPLOADED_FILE_ID.to_string()]);
+    let response = client
+        .get(format!("{}/drive/uploads/{}", second_app, upload_id))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_is_deleted_when_drive_stores_different_content() {
+    let mock = start_google_mock();
+    let mut config = mock_config();
+    mock.configure(&mut config);
+    let app = spawn_app(config);
+    let client = reqwest::Client::new();
+    let content = padded_pdf(1000);
+
+    mock.state.lock().unwrap().corrupt_md5 = true;
+    let upload_id = create_upload(&client, &app).await;
+    let response = client
+        .put(format!("{}/drive/uploads/{}", app, upload_id))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .header("Content-Range", "bytes 0-999/1000")
+        .body(content.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(response.text().await.unwrap().contains("Checksum mismatch"));
+
+    // A missing checksum cannot confirm the content either.
+    {
+        let mut state = mock.state.lock().unwrap();
+        state.corrupt_md5 = false;
+        state.omit_md5 = true;
+    }
+    let upload_id_without_md5 = create_upload(&client, &app).await;
+    let (status, _) = put_range(&client, &app, &upload_id_without_md5, "bytes 0-999/1000", &content).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+    assert_eq!(mock.state.lock().unwrap().deleted, vec![UPLOADED_FILE_ID.to_string(); 2]);
+    for upload_id in [upload_id, upload_id_without_md5] {
+        let response = client
+            .get(format!("{}/drive/uploads/{}", app, upload_id))
+            .bearer_auth(FULL_ACCESS_TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_create_upload_checks_type_and_size() {
+    let mock = start_google_mock();
+    let mut config = mock_config();
+    mock.configure(&mut config);
+    let app = spawn_app(config);
+    let client = reqwest::Client::new();
+
+    let too_large = serde_json::json!({ "file_name": "big.pdf", "mime_type": "application/pdf", "total_size": 2 * 1024 * 1024 });
+    let response = start_upload(&client, &app, too_large).await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert!(response.text().await.unwrap().contains("upload limit"));
+
+    let disallowed = serde_json::json!({ "file_name": "notes.txt", "mime_type": "text/plain" });
+    assert_eq!(start_upload(&client, &app, disallowed).await.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let untyped = serde_json::json!({ "file_name": "big.pdf" });
+    assert_eq!(start_upload(&client, &app, untyped).await.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    assert!(mock.state.lock().unwrap().uploads.is_empty());
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_ranges_are_checked_before_reaching_drive() {
+    let mock = start_google_mock();
+    let mut config = mock_config();
+    mock.configure(&mut config);
+    let app = spawn_app(config);
+    let client = reqwest::Client::new();
+
+    // Content that does not match the declared type cancels the upload.
+    let upload_id = create_upload(&client, &app).await;
+    let (status, _) = put_range(&client, &app, &upload_id, "bytes 0-99/*", &[b'x'; 100]).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let (status, _) = put_range(&client, &app, &upload_id, "bytes 0-99/*", &padded_pdf(1000)[..100]).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // A truncated file is refused before its last chunk completes it.
+    let upload_id = create_upload(&client, &app).await;
+    let truncated = &padded_pdf(1000)[..990];
+    let (status, body) = put_range(&client, &app, &upload_id, "bytes 0-989/990", truncated).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(body, Value::Null);
+
+    // So is a file that grows past the limit.
+    let upload_id = create_upload(&client, &app).await;
+    let oversized = padded_pdf(1100 * 1024);
+    let (status, _) = put_range(&client, &app, &upload_id, "bytes 0-1126399/*", &oversized).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+
+    // Every session was cancelled and no file was created.
+    {
+        let state = mock.state.lock().unwrap();
+        assert!(state.uploads.is_empty());
+        assert!(state.deleted.is_empty());
+    }
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_upload_rejects_gaps_and_bad_ranges() {
+    let mock = start_google_mock();
+    let mut config = mock_config();
+    mock.configure(&mut config);
+    let app = spawn_app(config);
+    let client = reqwest::Client::new();
+    let upload_id = create_upload(&client, &app).await;
+
+    let content = padded_pdf(2000);
+
+    let (status, body) = put_range(&client, &app, &upload_id, "bytes 1000-1999/*", &content[1000..]).await;
+    assert_eq!(status, StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(body["committed"], 0);
+
+    let (status, _) = put_range(&client, &app, &upload_id, "bytes 0-99/*", &content[..50]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = put_range(&client, &app, &upload_id, "0-99", &content[..100]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = put_range(&client, &app, "unknown", "bytes 0-99/100", &content[..100]).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    mock.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_cancel_upload() {
+    let mock = start_google_mock();
+    let mut config = mock_config();
+    mock.configure(&mut config);
+    let app = spawn_app(config);
+    let client = reqwest::Client::new();
+    let upload_id = create_upload(&client, &app).await;
+
+    let response = client
+        .delete(format!("{}/drive/uploads/{}", app, upload_id))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(mock.state.lock().unwrap().uploads.is_empty());
+
+    let response = client
+        .get(format!("{}/drive/uploads/{}", app, upload_id))
+        .bearer_auth(FULL_ACCESS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    mock.stop().await;
+}
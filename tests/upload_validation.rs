@@ -0,0 +1,86 @@
+use api_drive::services::upload_validation::{DocxValidator, PdfValidator, PngValidator, Rejection, UploadValidation};
+
+const PDF: &[u8] = b"%PDF-1.4\n1 0 obj\n<<>>\nendobj\nxref\n0 2\n0000000000 65535 f \n0000000009 00000 n \ntrailer\n<< /Size 2 /Root 1 0 R >>\nstartxref\n29\n%%EOF\n";
+const DOCX_MIME: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+fn validation() -> UploadValidation {
+    UploadValidation::new(1024)
+        .with_validator(Box::new(PdfValidator { check_xref: true }))
+        .with_validator(Box::new(PngValidator))
+        .with_validator(Box::new(DocxValidator))
+}
+
+/// Feeds `content` in chunks of `chunk_size` bytes, as it would arrive from a request.
+fn validate(mime_type: &str, content: &[u8], chunk_size: usize) -> Result<(), Rejection> {
+    let mut check = validation().check(Some(mime_type), None)?;
+    for chunk in content.chunks(chunk_size) {
+        check.update(chunk)?;
+    }
+    check.finish()
+}
+
+#[test]
+fn test_valid_pdf_passes_in_any_chunking() {
+    for chunk_size in [1, 3, 7, PDF.len()] {
+        assert_eq!(validate("application/pdf", PDF, chunk_size), Ok(()), "chunk size {}", chunk_size);
+    }
+}
+
+#[test]
+fn test_pdf_without_magic_is_rejected_on_first_bytes() {
+    let mut check = validation().check(Some("application/pdf"), None).unwrap();
+    assert!(matches!(check.update(b"GIF89a"), Err(Rejection::UnsupportedType(_))));
+}
+
+#[test]
+fn test_truncated_pdf_is_rejected() {
+    let rejection = validate("application/pdf", &PDF[..PDF.len() - 7], 16).unwrap_err();
+    assert!(rejection.to_string().contains("%%EOF"));
+}
+
+#[test]
+fn test_pdf_with_wrong_startxref_is_rejected() {
+    let broken = String::from_utf8(PDF.to_vec()).unwrap().replace("startxref\n29", "startxref\n30");
+    let rejection = validate("application/pdf", broken.as_bytes(), 16).unwrap_err();
+    assert!(rejection.to_string().contains("startxref"));
+
+    let lenient = UploadValidation::new(1024).with_validator(Box::new(PdfValidator { check_xref: false }));
+    let mut check = lenient.check(Some("application/pdf"), None).unwrap();
+    check.update(broken.as_bytes()).unwrap();
+    assert_eq!(check.finish(), Ok(()));
+}
+
+#[test]
+fn test_png_needs_signature_and_iend() {
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\0IEND\xaeB`\x82";
+    assert_eq!(validate("image/png", png, 5), Ok(()));
+    assert!(validate("image/png", &png[..png.len() - 1], 5).is_err());
+}
+
+#[test]
+fn test_docx_needs_word_part_and_central_directory() {
+    let docx = b"PK\x03\x04....word/document.xml....PK\x01\x02....PK\x05\x06\0\0\0\0";
+    assert_eq!(validate(DOCX_MIME, docx, 4), Ok(()));
+
+    let zip = b"PK\x03\x04....xl/workbook.xml....PK\x05\x06\0\0\0\0";
+    assert!(validate(DOCX_MIME, zip, 4).unwrap_err().to_string().contains("not a Word document"));
+}
+
+#[test]
+fn test_types_without_validator_are_unsupported() {
+    let rejection = validation().check(Some("image/gif"), None).err().unwrap();
+    assert_eq!(rejection.status_code(), 415);
+    assert!(rejection.to_string().contains("pdf, png, docx"));
+    assert!(validation().check(None, None).is_err());
+}
+
+#[test]
+fn test_size_limit() {
+    let declared = validation().check(Some("application/pdf"), Some(2048)).err().unwrap();
+    assert_eq!(declared, Rejection::TooLarge(1024));
+    assert_eq!(declared.status_code(), 413);
+
+    let mut padded = PDF.to_vec();
+    padded.resize(2048, b' ');
+    assert_eq!(validate("application/pdf", &padded, 256), Err(Rejection::TooLarge(1024)));
+}